@@ -0,0 +1,514 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::upload_function::candles::{load_timeframe_candles, Candle};
+use crate::upload_function::column_mapping::open_settings_db;
+use crate::upload_function::local_upload::load_symbol_data;
+
+pub const DEFAULT_LEVEL_SET: &str = "Default";
+
+// Ratios drawn by the Fibonacci tool. Retracements run from the second anchor (0) back to the
+// first (1), extensions continue past the second anchor, projections repeat the first leg from
+// a third anchor.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FibLevelSet {
+    pub name: String,
+    pub retracement: Vec<f64>,
+    pub extension: Vec<f64>,
+    pub projection: Vec<f64>,
+}
+
+impl FibLevelSet {
+    fn builtin() -> Self {
+        FibLevelSet {
+            name: DEFAULT_LEVEL_SET.to_string(),
+            retracement: vec![0.0, 0.236, 0.382, 0.5, 0.618, 0.786, 1.0],
+            extension: vec![1.272, 1.414, 1.618, 2.0, 2.618],
+            projection: vec![0.618, 1.0, 1.272, 1.618],
+        }
+    }
+
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err("Level set name cannot be empty".to_string());
+        }
+
+        for ratios in [
+            &mut self.retracement,
+            &mut self.extension,
+            &mut self.projection,
+        ] {
+            if let Some(ratio) = ratios.iter().find(|r| !r.is_finite()) {
+                return Err(format!("Invalid Fibonacci ratio: {}", ratio));
+            }
+            ratios.sort_by(f64::total_cmp);
+            ratios.dedup();
+        }
+
+        if self.retracement.is_empty() && self.extension.is_empty() && self.projection.is_empty() {
+            return Err(format!("Level set '{}' has no levels", self.name));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LevelSetResult {
+    pub success: bool,
+    pub message: String,
+    pub name: String,
+}
+
+// Same schema as the v3 migration, in case the frontend has not loaded the db yet
+pub fn create_level_sets_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS fib_level_sets (
+            name TEXT PRIMARY KEY,
+            levels TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create fib_level_sets table: {}", e))?;
+
+    Ok(())
+}
+
+fn open_level_sets_db(app_handle: &AppHandle) -> Result<Connection, String> {
+    let conn = open_settings_db(app_handle)?;
+    create_level_sets_table(&conn)?;
+
+    Ok(conn)
+}
+
+// A saved level set, the built-in one for "Default" unless the user saved over it
+fn load_level_set(app_handle: &AppHandle, name: &str) -> Result<FibLevelSet, String> {
+    let conn = open_level_sets_db(app_handle)?;
+
+    let levels: Option<String> = conn
+        .query_row(
+            "SELECT levels FROM fib_level_sets WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to read level set '{}': {}", name, e))?;
+
+    match levels {
+        Some(levels) => serde_json::from_str(&levels)
+            .map_err(|e| format!("Failed to parse level set '{}': {}", name, e)),
+        None if name == DEFAULT_LEVEL_SET => Ok(FibLevelSet::builtin()),
+        None => Err(format!("Fibonacci level set '{}' not found", name)),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct FibAnchor {
+    pub timestamp: i64, // Unix seconds
+    pub price: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FibLevelKind {
+    Retracement,
+    Extension,
+    Projection,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FibLevel {
+    pub kind: FibLevelKind,
+    pub ratio: f64,
+    pub price: f64,
+    pub first_touch: Option<i64>, // First candle after the last anchor that traded through it
+    pub touches: usize,           // Candles that traded through it since
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FibRequest {
+    pub symbol: String,
+    pub timeframe: String,
+    pub anchors: Vec<FibAnchor>, // Two for retracements and extensions, a third adds projections
+    #[serde(default)]
+    pub level_set: Option<String>, // "Default" when missing
+    #[serde(default)]
+    pub snap: bool, // Move anchors to the nearest candle high or low
+    #[serde(default)]
+    pub snap_window: Option<usize>, // Candles searched on each side of an anchor, 0 by default
+}
+
+#[derive(Debug, Serialize)]
+pub struct FibResult {
+    pub anchors: Vec<FibAnchor>, // After snapping
+    pub level_set: String,
+    pub levels: Vec<FibLevel>,
+}
+
+// Move an anchor to the high or low nearest its price among the candles around its time
+pub fn snap_anchor(candles: &[Candle], anchor: FibAnchor, window: usize) -> FibAnchor {
+    let index = candles.partition_point(|c| c.timestamp < anchor.timestamp);
+
+    // The candle containing the anchor time is the one before the partition point, unless
+    // the anchor sits exactly on an open
+    let nearest = match candles.get(index) {
+        Some(c) if c.timestamp == anchor.timestamp => index,
+        _ => index.saturating_sub(1),
+    };
+    let first = nearest.saturating_sub(window);
+    let last = (nearest + window + 1).min(candles.len());
+
+    candles[first..last]
+        .iter()
+        .flat_map(|c| [(c.timestamp, c.high), (c.timestamp, c.low)])
+        .min_by(|a, b| {
+            (a.1 - anchor.price)
+                .abs()
+                .total_cmp(&(b.1 - anchor.price).abs())
+        })
+        .map(|(timestamp, price)| FibAnchor { timestamp, price })
+        .unwrap_or(anchor)
+}
+
+// Prices of every level of the set for two or three anchors
+pub fn fib_levels(
+    anchors: &[FibAnchor],
+    set: &FibLevelSet,
+) -> Result<Vec<(FibLevelKind, f64, f64)>, String> {
+    let (a, b) = match anchors {
+        [a, b] | [a, b, _] => (a.price, b.price),
+        _ => return Err("Fibonacci levels need two or three anchors".to_string()),
+    };
+
+    if a == b {
+        return Err("Fibonacci anchors must be at different prices".to_string());
+    }
+
+    let leg = b - a;
+    let mut levels: Vec<(FibLevelKind, f64, f64)> = set
+        .retracement
+        .iter()
+        .map(|&r| (FibLevelKind::Retracement, r, b - leg * r))
+        .chain(
+            set.extension
+                .iter()
+                .map(|&r| (FibLevelKind::Extension, r, a + leg * r)),
+        )
+        .collect();
+
+    if let Some(c) = anchors.get(2) {
+        levels.extend(
+            set.projection
+                .iter()
+                .map(|&r| (FibLevelKind::Projection, r, c.price + leg * r)),
+        );
+    }
+
+    Ok(levels)
+}
+
+// Fibonacci levels from chart anchors, with the candles that later reached each level
+#[tauri::command]
+pub async fn compute_fibonacci(
+    app_handle: AppHandle,
+    request: FibRequest,
+) -> Result<FibResult, String> {
+    let set_name = request
+        .level_set
+        .unwrap_or_else(|| DEFAULT_LEVEL_SET.to_string());
+    let set = load_level_set(&app_handle, &set_name)?;
+
+    let symbol_data = load_symbol_data(&app_handle, &request.symbol)?;
+    let candles = load_timeframe_candles(&symbol_data, &request.timeframe)?;
+
+    let anchors: Vec<FibAnchor> = if request.snap && !candles.is_empty() {
+        request
+            .anchors
+            .iter()
+            .map(|&anchor| snap_anchor(&candles, anchor, request.snap_window.unwrap_or(0)))
+            .collect()
+    } else {
+        request.anchors
+    };
+
+    // Only candles after the last anchor can touch a level
+    let after = anchors
+        .iter()
+        .map(|a| a.timestamp)
+        .max()
+        .unwrap_or(i64::MAX);
+    let later = &candles[candles.partition_point(|c| c.timestamp <= after)..];
+
+    let levels = fib_levels(&anchors, &set)?
+        .into_iter()
+        .map(|(kind, ratio, price)| {
+            let touching: Vec<i64> = later
+                .iter()
+                .filter(|c| c.low <= price && price <= c.high)
+                .map(|c| c.timestamp)
+                .collect();
+
+            FibLevel {
+                kind,
+                ratio,
+                price,
+                first_touch: touching.first().copied(),
+                touches: touching.len(),
+            }
+        })
+        .collect();
+
+    Ok(FibResult {
+        anchors,
+        level_set: set.name,
+        levels,
+    })
+}
+
+// Saved level sets, with the built-in default first unless it was overridden
+pub fn read_level_sets(conn: &Connection) -> Result<Vec<FibLevelSet>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, levels FROM fib_level_sets ORDER BY name")
+        .map_err(|e| format!("Failed to read level sets: {}", e))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("Failed to read level sets: {}", e))?;
+
+    let mut sets = Vec::new();
+
+    for row in rows {
+        let (name, levels) = row.map_err(|e| format!("Failed to read level set: {}", e))?;
+
+        match serde_json::from_str::<FibLevelSet>(&levels) {
+            Ok(set) => sets.push(set),
+            Err(e) => println!("✗ Skipping level set '{}': {}", name, e),
+        }
+    }
+
+    if !sets.iter().any(|set| set.name == DEFAULT_LEVEL_SET) {
+        sets.insert(0, FibLevelSet::builtin());
+    }
+
+    Ok(sets)
+}
+
+// Insert or overwrite a level set that has been validated
+pub fn write_level_set(conn: &Connection, level_set: &FibLevelSet) -> Result<(), String> {
+    let json = serde_json::to_string(level_set)
+        .map_err(|e| format!("Failed to serialize level set: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO fib_level_sets (name, levels, updated_at)
+         VALUES (?1, ?2, CURRENT_TIMESTAMP)
+         ON CONFLICT(name) DO UPDATE SET
+         levels = ?2,
+         updated_at = CURRENT_TIMESTAMP",
+        params![level_set.name, json],
+    )
+    .map_err(|e| format!("Failed to save level set: {}", e))?;
+
+    Ok(())
+}
+
+// Whether a level set of that name was stored
+pub fn remove_level_set(conn: &Connection, name: &str) -> Result<bool, String> {
+    let deleted = conn
+        .execute("DELETE FROM fib_level_sets WHERE name = ?1", params![name])
+        .map_err(|e| format!("Failed to delete level set: {}", e))?;
+
+    Ok(deleted > 0)
+}
+
+#[tauri::command]
+pub async fn list_fib_level_sets(app_handle: AppHandle) -> Result<Vec<FibLevelSet>, String> {
+    read_level_sets(&open_level_sets_db(&app_handle)?)
+}
+
+#[tauri::command]
+pub async fn save_fib_level_set(
+    app_handle: AppHandle,
+    mut level_set: FibLevelSet,
+) -> Result<LevelSetResult, String> {
+    level_set.validate()?;
+    write_level_set(&open_level_sets_db(&app_handle)?, &level_set)?;
+
+    Ok(LevelSetResult {
+        success: true,
+        message: format!("Level set '{}' saved", level_set.name),
+        name: level_set.name,
+    })
+}
+
+// Deleting "Default" restores the built-in levels
+#[tauri::command]
+pub async fn delete_fib_level_set(
+    app_handle: AppHandle,
+    name: String,
+) -> Result<LevelSetResult, String> {
+    if !remove_level_set(&open_level_sets_db(&app_handle)?, &name)? {
+        return Err(format!("Fibonacci level set '{}' not found", name));
+    }
+
+    Ok(LevelSetResult {
+        success: true,
+        message: format!("Level set '{}' deleted", name),
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor(timestamp: i64, price: f64) -> FibAnchor {
+        FibAnchor { timestamp, price }
+    }
+
+    fn candle(timestamp: i64, high: f64, low: f64) -> Candle {
+        Candle {
+            timestamp,
+            open: low,
+            high,
+            low,
+            close: high,
+            volume: 0.0,
+            adj_close: None,
+        }
+    }
+
+    fn level_set() -> FibLevelSet {
+        FibLevelSet {
+            name: "Test".to_string(),
+            retracement: vec![0.0, 0.5, 1.0],
+            extension: vec![1.618],
+            projection: vec![0.618, 1.0],
+        }
+    }
+
+    fn prices(anchors: &[FibAnchor]) -> Vec<(FibLevelKind, f64, f64)> {
+        fib_levels(anchors, &level_set())
+            .unwrap()
+            .into_iter()
+            .map(|(kind, ratio, price)| (kind, ratio, (price * 1e6).round() / 1e6))
+            .collect()
+    }
+
+    #[test]
+    fn levels_of_an_up_leg() {
+        let levels = prices(&[anchor(0, 100.0), anchor(60, 200.0), anchor(120, 150.0)]);
+
+        assert_eq!(
+            levels,
+            vec![
+                (FibLevelKind::Retracement, 0.0, 200.0),
+                (FibLevelKind::Retracement, 0.5, 150.0),
+                (FibLevelKind::Retracement, 1.0, 100.0),
+                (FibLevelKind::Extension, 1.618, 261.8),
+                (FibLevelKind::Projection, 0.618, 211.8),
+                (FibLevelKind::Projection, 1.0, 250.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn levels_of_a_down_leg() {
+        let levels = prices(&[anchor(0, 200.0), anchor(60, 100.0), anchor(120, 150.0)]);
+
+        assert_eq!(
+            levels,
+            vec![
+                (FibLevelKind::Retracement, 0.0, 100.0),
+                (FibLevelKind::Retracement, 0.5, 150.0),
+                (FibLevelKind::Retracement, 1.0, 200.0),
+                (FibLevelKind::Extension, 1.618, 38.2),
+                (FibLevelKind::Projection, 0.618, 88.2),
+                (FibLevelKind::Projection, 1.0, 50.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn projections_need_a_third_anchor() {
+        let levels = prices(&[anchor(0, 100.0), anchor(60, 200.0)]);
+        assert!(levels
+            .iter()
+            .all(|(kind, _, _)| *kind != FibLevelKind::Projection));
+        assert_eq!(levels.len(), 4);
+
+        let set = level_set();
+        assert!(fib_levels(&[anchor(0, 100.0)], &set).is_err());
+        assert!(fib_levels(&[anchor(0, 100.0), anchor(60, 100.0)], &set).is_err());
+    }
+
+    #[test]
+    fn anchors_snap_to_the_nearest_high_or_low() {
+        let candles = [
+            candle(0, 10.0, 9.0),
+            candle(60, 12.0, 10.0),
+            candle(120, 11.0, 8.0),
+        ];
+        let snapped = |timestamp: i64, price: f64, window: usize| {
+            let a = snap_anchor(&candles, anchor(timestamp, price), window);
+            (a.timestamp, a.price)
+        };
+
+        // Before the first candle and after the last, the edge candles are used
+        assert_eq!(snapped(-100, 9.1, 0), (0, 9.0));
+        assert_eq!(snapped(500, 11.2, 0), (120, 11.0));
+
+        // Exactly on an open is that candle, inside one is the candle containing it
+        assert_eq!(snapped(60, 11.9, 0), (60, 12.0));
+        assert_eq!(snapped(90, 11.9, 0), (60, 12.0));
+        assert_eq!(snapped(120, 11.9, 0), (120, 11.0));
+
+        // A window reaches the neighbours
+        assert_eq!(snapped(60, 8.2, 0), (60, 10.0));
+        assert_eq!(snapped(60, 8.2, 1), (120, 8.0));
+        assert_eq!(snapped(-100, 11.9, 5), (60, 12.0));
+
+        // Nothing to snap to
+        let a = snap_anchor(&[], anchor(60, 11.9), 1);
+        assert_eq!((a.timestamp, a.price), (60, 11.9));
+    }
+
+    #[test]
+    fn validate_sorts_and_removes_duplicate_ratios() {
+        let mut set = FibLevelSet {
+            name: "  Mine ".to_string(),
+            retracement: vec![0.618, 0.0, 0.5, 0.618],
+            extension: vec![2.0, 1.618, 2.0],
+            projection: Vec::new(),
+        };
+        set.validate().unwrap();
+
+        assert_eq!(set.name, "Mine");
+        assert_eq!(set.retracement, vec![0.0, 0.5, 0.618]);
+        assert_eq!(set.extension, vec![1.618, 2.0]);
+        assert!(set.projection.is_empty());
+    }
+
+    #[test]
+    fn validate_rejects_unusable_sets() {
+        let mut set = level_set();
+        set.extension.push(f64::NAN);
+        assert!(set.validate().is_err());
+
+        let mut set = level_set();
+        set.name = "   ".to_string();
+        assert!(set.validate().is_err());
+
+        let mut set = FibLevelSet {
+            name: "Empty".to_string(),
+            retracement: Vec::new(),
+            extension: Vec::new(),
+            projection: Vec::new(),
+        };
+        assert!(set.validate().is_err());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod fibonacci;
+pub mod patterns;
+pub mod structure;
+
+pub use fibonacci::{
+    compute_fibonacci, delete_fib_level_set, list_fib_level_sets, save_fib_level_set,
+};
+pub use patterns::scan_patterns;
+pub use structure::detect_structure;
@@ -0,0 +1,550 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use tauri::AppHandle;
+
+use super::structure::Direction;
+use crate::upload_function::candles::{load_timeframe_candles, Candle};
+use crate::upload_function::local_upload::load_symbol_data;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    Doji,
+    PinBar,
+    Engulfing,
+    InsideBar,
+    OutsideBar,
+    Harami,
+    MorningStar,
+    EveningStar,
+    ThreeWhiteSoldiers,
+    ThreeBlackCrows,
+}
+
+impl PatternKind {
+    pub const ALL: [PatternKind; 10] = [
+        PatternKind::Doji,
+        PatternKind::PinBar,
+        PatternKind::Engulfing,
+        PatternKind::InsideBar,
+        PatternKind::OutsideBar,
+        PatternKind::Harami,
+        PatternKind::MorningStar,
+        PatternKind::EveningStar,
+        PatternKind::ThreeWhiteSoldiers,
+        PatternKind::ThreeBlackCrows,
+    ];
+
+    // What the tolerance means differs per pattern, all are fractions:
+    // doji: largest body / range; pin bar: smallest wick / range; engulfing: body shortfall
+    // allowed / engulfed body; inside and outside bar: overshoot / mother bar range; harami:
+    // largest body / mother body; stars: largest middle body / first body; soldiers and
+    // crows: how far an open may sit outside the previous body / that body
+    pub fn default_tolerance(&self) -> f64 {
+        match self {
+            PatternKind::Doji => 0.1,
+            PatternKind::PinBar => 0.6,
+            PatternKind::Engulfing => 0.0,
+            PatternKind::InsideBar | PatternKind::OutsideBar => 0.0,
+            PatternKind::Harami => 0.5,
+            PatternKind::MorningStar | PatternKind::EveningStar => 0.3,
+            PatternKind::ThreeWhiteSoldiers | PatternKind::ThreeBlackCrows => 0.1,
+        }
+    }
+
+    // Bars the pattern spans, the last one completes it
+    pub fn bars(&self) -> usize {
+        match self {
+            PatternKind::Doji | PatternKind::PinBar => 1,
+            PatternKind::Engulfing
+            | PatternKind::InsideBar
+            | PatternKind::OutsideBar
+            | PatternKind::Harami => 2,
+            PatternKind::MorningStar
+            | PatternKind::EveningStar
+            | PatternKind::ThreeWhiteSoldiers
+            | PatternKind::ThreeBlackCrows => 3,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PatternConfig {
+    #[serde(default)]
+    pub patterns: Option<Vec<PatternKind>>, // Every pattern when missing
+    #[serde(default)]
+    pub tolerances: HashMap<PatternKind, f64>, // Overrides of the default tolerances
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PatternHit {
+    pub pattern: PatternKind,
+    pub direction: Option<Direction>, // None for indecision patterns (doji, inside bar)
+    pub strength: f64,                // 0-1, how clearly the bars match
+    pub start: i64,                   // First bar of the pattern
+    pub timestamp: i64,               // Bar that completed it
+}
+
+fn body(c: &Candle) -> f64 {
+    (c.close - c.open).abs()
+}
+
+fn range(c: &Candle) -> f64 {
+    c.high - c.low
+}
+
+fn bullish(c: &Candle) -> bool {
+    c.close > c.open
+}
+
+fn bearish(c: &Candle) -> bool {
+    c.close < c.open
+}
+
+fn body_top(c: &Candle) -> f64 {
+    c.open.max(c.close)
+}
+
+fn body_bottom(c: &Candle) -> f64 {
+    c.open.min(c.close)
+}
+
+fn direction_of(up: bool) -> Option<Direction> {
+    Some(if up {
+        Direction::Bullish
+    } else {
+        Direction::Bearish
+    })
+}
+
+// Direction and strength of `kind` ending at the last of `bars`, which hold exactly kind.bars()
+fn detect(kind: PatternKind, bars: &[&Candle], tolerance: f64) -> Option<(Option<Direction>, f64)> {
+    match kind {
+        PatternKind::Doji => {
+            let c = bars[0];
+            let limit = tolerance * range(c);
+            (range(c) > 0.0 && body(c) <= limit)
+                .then(|| (None, 1.0 - body(c) / limit.max(f64::EPSILON)))
+        }
+        PatternKind::PinBar => {
+            let c = bars[0];
+            let upper = c.high - body_top(c);
+            let lower = body_bottom(c) - c.low;
+            let (wick, up) = if lower >= upper {
+                (lower, true)
+            } else {
+                (upper, false)
+            };
+            (range(c) > 0.0 && wick >= tolerance * range(c))
+                .then(|| (direction_of(up), wick / range(c)))
+        }
+        PatternKind::Engulfing => {
+            let (a, b) = (bars[0], bars[1]);
+            let slack = tolerance * body(a);
+            let up = bearish(a) && bullish(b);
+            let down = bullish(a) && bearish(b);
+            ((up || down)
+                && body_top(b) >= body_top(a) - slack
+                && body_bottom(b) <= body_bottom(a) + slack
+                && body(b) > 0.0)
+                .then(|| (direction_of(up), (1.0 - body(a) / body(b)).clamp(0.0, 1.0)))
+        }
+        PatternKind::InsideBar => {
+            let (mother, c) = (bars[0], bars[1]);
+            let slack = tolerance * range(mother);
+            (range(mother) > 0.0
+                && c.high <= mother.high + slack
+                && c.low >= mother.low - slack
+                && range(c) < range(mother))
+            .then(|| (None, 1.0 - range(c) / range(mother)))
+        }
+        PatternKind::OutsideBar => {
+            let (mother, c) = (bars[0], bars[1]);
+            let slack = tolerance * range(mother);
+            (c.high >= mother.high - slack
+                && c.low <= mother.low + slack
+                && range(c) > range(mother)
+                && c.close != c.open)
+                .then(|| (direction_of(bullish(c)), 1.0 - range(mother) / range(c)))
+        }
+        PatternKind::Harami => {
+            let (mother, c) = (bars[0], bars[1]);
+            let up = bearish(mother) && bullish(c);
+            let down = bullish(mother) && bearish(c);
+            ((up || down)
+                && body_top(c) <= body_top(mother)
+                && body_bottom(c) >= body_bottom(mother)
+                && body(c) <= tolerance * body(mother))
+            .then(|| (direction_of(up), 1.0 - body(c) / body(mother)))
+        }
+        PatternKind::MorningStar | PatternKind::EveningStar => {
+            let (a, star, c) = (bars[0], bars[1], bars[2]);
+            let up = kind == PatternKind::MorningStar;
+            let midpoint = (a.open + a.close) / 2.0;
+
+            // A long first candle, a small star, and a reversal closing past the first's midpoint
+            let shape = if up {
+                bearish(a) && bullish(c) && c.close > midpoint
+            } else {
+                bullish(a) && bearish(c) && c.close < midpoint
+            };
+            (shape && body(star) <= tolerance * body(a)).then(|| {
+                let reach = (c.close - a.close).abs() / body(a);
+                (direction_of(up), reach.min(1.0))
+            })
+        }
+        PatternKind::ThreeWhiteSoldiers | PatternKind::ThreeBlackCrows => {
+            let up = kind == PatternKind::ThreeWhiteSoldiers;
+            let same_color = bars
+                .iter()
+                .all(|c| if up { bullish(c) } else { bearish(c) });
+            let advancing = bars.windows(2).all(|pair| {
+                let (previous, c) = (pair[0], pair[1]);
+                let slack = tolerance * body(previous);
+                let opens_inside =
+                    c.open >= body_bottom(previous) - slack && c.open <= body_top(previous) + slack;
+                let moves_on = if up {
+                    c.close > previous.close
+                } else {
+                    c.close < previous.close
+                };
+                opens_inside && moves_on
+            });
+
+            // Strong when the closes sit near the extremes
+            (same_color && advancing).then(|| {
+                let strength = bars
+                    .iter()
+                    .map(|c| body(c) / range(c).max(f64::EPSILON))
+                    .sum::<f64>()
+                    / 3.0;
+                (direction_of(up), strength)
+            })
+        }
+    }
+}
+
+// Recognizes patterns one closed bar at a time, only ever looking at that bar and the two
+// before it, so replays can't see the future
+pub struct PatternScanner {
+    patterns: Vec<(PatternKind, f64)>, // Enabled patterns with their tolerance
+    recent: VecDeque<Candle>,
+}
+
+impl PatternScanner {
+    pub fn new(config: &PatternConfig) -> Result<Self, String> {
+        if let Some((kind, tolerance)) = config
+            .tolerances
+            .iter()
+            .find(|(_, tolerance)| !(0.0..=1.0).contains(*tolerance))
+        {
+            return Err(format!(
+                "Tolerance of {:?} must be between 0 and 1, got {}",
+                kind, tolerance
+            ));
+        }
+
+        let kinds = config
+            .patterns
+            .clone()
+            .unwrap_or_else(|| PatternKind::ALL.to_vec());
+
+        Ok(PatternScanner {
+            patterns: kinds
+                .into_iter()
+                .map(|kind| {
+                    let tolerance = config
+                        .tolerances
+                        .get(&kind)
+                        .copied()
+                        .unwrap_or_else(|| kind.default_tolerance());
+                    (kind, tolerance)
+                })
+                .collect(),
+            recent: VecDeque::with_capacity(3),
+        })
+    }
+
+    // Feed one closed bar, returns the patterns it completes
+    pub fn push(&mut self, candle: &Candle) -> Vec<PatternHit> {
+        if self.recent.len() == 3 {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(candle.clone());
+
+        self.patterns
+            .iter()
+            .filter(|(kind, _)| kind.bars() <= self.recent.len())
+            .filter_map(|(kind, tolerance)| {
+                let bars: Vec<&Candle> = self
+                    .recent
+                    .range(self.recent.len() - kind.bars()..)
+                    .collect();
+                let (direction, strength) = detect(*kind, &bars, *tolerance)?;
+
+                Some(PatternHit {
+                    pattern: *kind,
+                    direction,
+                    strength: strength.clamp(0.0, 1.0),
+                    start: bars[0].timestamp,
+                    timestamp: candle.timestamp,
+                })
+            })
+            .collect()
+    }
+}
+
+pub fn scan(candles: &[Candle], config: &PatternConfig) -> Result<Vec<PatternHit>, String> {
+    let mut scanner = PatternScanner::new(config)?;
+    Ok(candles.iter().flat_map(|c| scanner.push(c)).collect())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatternRequest {
+    pub symbol: String,
+    pub timeframe: String,
+    #[serde(default)]
+    pub start: Option<i64>, // Unix seconds
+    #[serde(default)]
+    pub end: Option<i64>,
+    #[serde(default)]
+    pub config: PatternConfig,
+}
+
+// Candlestick patterns over a symbol's stored candles, oldest first
+#[tauri::command]
+pub async fn scan_patterns(
+    app_handle: AppHandle,
+    request: PatternRequest,
+) -> Result<Vec<PatternHit>, String> {
+    let symbol_data = load_symbol_data(&app_handle, &request.symbol)?;
+
+    let mut candles = load_timeframe_candles(&symbol_data, &request.timeframe)?;
+    candles.retain(|c| {
+        request.start.is_none_or(|start| c.timestamp >= start)
+            && request.end.is_none_or(|end| c.timestamp < end)
+    });
+
+    let hits = scan(&candles, &request.config)?;
+
+    println!(
+        "✓ {} {} - {} pattern hits in {} candles",
+        request.symbol,
+        request.timeframe,
+        hits.len(),
+        candles.len()
+    );
+
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume: 0.0,
+            adj_close: None,
+        }
+    }
+
+    // Open and close, with the wicks a tenth beyond the body
+    fn body_bar(timestamp: i64, open: f64, close: f64) -> Candle {
+        bar(
+            timestamp,
+            open,
+            open.max(close) + 0.1,
+            open.min(close) - 0.1,
+            close,
+        )
+    }
+
+    fn check(kind: PatternKind, candles: &[Candle]) -> Option<Option<Direction>> {
+        let bars: Vec<&Candle> = candles.iter().collect();
+        detect(kind, &bars, kind.default_tolerance()).map(|(direction, _)| direction)
+    }
+
+    // Pattern, bars matching it, their direction, and bars that just miss it
+    type Case = (PatternKind, Vec<Candle>, Option<Direction>, Vec<Candle>);
+
+    // A hand-built match and a near miss for every pattern, at the default tolerances
+    fn cases() -> Vec<Case> {
+        use Direction::{Bearish, Bullish};
+
+        vec![
+            (
+                PatternKind::Doji,
+                vec![bar(0, 10.0, 11.0, 9.0, 10.05)],
+                None,
+                vec![bar(0, 10.0, 11.0, 9.0, 10.3)],
+            ),
+            (
+                PatternKind::PinBar,
+                vec![bar(0, 10.8, 11.0, 9.0, 10.9)],
+                Some(Bullish),
+                vec![bar(0, 10.0, 11.0, 9.0, 10.5)],
+            ),
+            (
+                PatternKind::Engulfing,
+                vec![body_bar(0, 10.5, 10.0), body_bar(1, 9.9, 10.6)],
+                Some(Bullish),
+                vec![body_bar(0, 10.5, 10.0), body_bar(1, 10.1, 10.6)],
+            ),
+            (
+                PatternKind::InsideBar,
+                vec![bar(0, 9.5, 11.0, 9.0, 10.5), bar(1, 10.0, 10.5, 9.5, 10.2)],
+                None,
+                vec![bar(0, 9.5, 11.0, 9.0, 10.5), bar(1, 10.0, 11.1, 9.5, 10.2)],
+            ),
+            (
+                PatternKind::OutsideBar,
+                vec![bar(0, 10.0, 10.5, 9.5, 10.2), bar(1, 9.4, 11.0, 9.0, 10.8)],
+                Some(Bullish),
+                vec![bar(0, 10.0, 10.5, 9.5, 10.2), bar(1, 9.4, 10.4, 9.0, 10.3)],
+            ),
+            (
+                PatternKind::Harami,
+                vec![body_bar(0, 11.0, 9.0), body_bar(1, 9.5, 10.2)],
+                Some(Bullish),
+                vec![body_bar(0, 11.0, 9.0), body_bar(1, 9.2, 10.8)],
+            ),
+            (
+                PatternKind::MorningStar,
+                vec![
+                    body_bar(0, 11.0, 9.0),
+                    body_bar(1, 8.9, 8.8),
+                    body_bar(2, 9.0, 10.5),
+                ],
+                Some(Bullish),
+                vec![
+                    body_bar(0, 11.0, 9.0),
+                    body_bar(1, 8.9, 8.8),
+                    body_bar(2, 9.0, 9.8),
+                ],
+            ),
+            (
+                PatternKind::EveningStar,
+                vec![
+                    body_bar(0, 9.0, 11.0),
+                    body_bar(1, 11.1, 11.2),
+                    body_bar(2, 11.0, 9.5),
+                ],
+                Some(Bearish),
+                vec![
+                    body_bar(0, 9.0, 11.0),
+                    body_bar(1, 11.1, 11.2),
+                    body_bar(2, 11.0, 10.2),
+                ],
+            ),
+            (
+                PatternKind::ThreeWhiteSoldiers,
+                vec![
+                    body_bar(0, 10.0, 11.0),
+                    body_bar(1, 10.5, 11.5),
+                    body_bar(2, 11.0, 12.0),
+                ],
+                Some(Bullish),
+                vec![
+                    body_bar(0, 10.0, 11.0),
+                    body_bar(1, 10.5, 11.5),
+                    body_bar(2, 11.7, 12.0),
+                ],
+            ),
+            (
+                PatternKind::ThreeBlackCrows,
+                vec![
+                    body_bar(0, 12.0, 11.0),
+                    body_bar(1, 11.5, 10.5),
+                    body_bar(2, 11.0, 10.0),
+                ],
+                Some(Bearish),
+                vec![
+                    body_bar(0, 12.0, 11.0),
+                    body_bar(1, 11.5, 10.5),
+                    body_bar(2, 11.0, 10.6),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn every_pattern_matches_its_shape_and_rejects_a_near_miss() {
+        let cases = cases();
+        assert_eq!(cases.len(), PatternKind::ALL.len());
+
+        for (kind, matching, direction, near_miss) in cases {
+            assert_eq!(matching.len(), kind.bars(), "{:?}", kind);
+            assert_eq!(check(kind, &matching), Some(direction), "{:?}", kind);
+            assert_eq!(check(kind, &near_miss), None, "{:?} near miss", kind);
+        }
+    }
+
+    #[test]
+    fn a_looser_tolerance_accepts_the_near_miss() {
+        // The doji near miss has a body of 15% of its range
+        let candle = bar(0, 10.0, 11.0, 9.0, 10.3);
+        assert!(detect(PatternKind::Doji, &[&candle], 0.1).is_none());
+        assert!(detect(PatternKind::Doji, &[&candle], 0.2).is_some());
+    }
+
+    #[test]
+    fn hits_never_start_before_the_three_bar_window() {
+        // Loose tolerances so most bars complete something
+        let config = PatternConfig {
+            patterns: None,
+            tolerances: PatternKind::ALL.iter().map(|&kind| (kind, 1.0)).collect(),
+        };
+        let mut scanner = PatternScanner::new(&config).unwrap();
+
+        let mut price = 100.0;
+        let mut hits = 0;
+        for i in 0..200 {
+            let step = ((i * 37 % 11) as f64 - 5.0) / 2.0;
+            let open = price;
+            price += step;
+            let candle = bar(
+                i * 60,
+                open,
+                open.max(price) + (i % 3) as f64,
+                open.min(price) - (i % 4) as f64,
+                price,
+            );
+
+            for hit in scanner.push(&candle) {
+                assert_eq!(hit.timestamp, candle.timestamp);
+                assert!(hit.start >= candle.timestamp - 2 * 60, "{:?}", hit);
+                assert_eq!(
+                    hit.start,
+                    candle.timestamp - (hit.pattern.bars() as i64 - 1) * 60
+                );
+                assert!((0.0..=1.0).contains(&hit.strength));
+                hits += 1;
+            }
+        }
+
+        assert!(hits > 0);
+    }
+
+    #[test]
+    fn tolerances_outside_zero_to_one_are_rejected() {
+        for tolerance in [-0.1, 1.5, f64::NAN] {
+            let config = PatternConfig {
+                patterns: None,
+                tolerances: HashMap::from([(PatternKind::Doji, tolerance)]),
+            };
+            assert!(PatternScanner::new(&config).is_err(), "{}", tolerance);
+        }
+
+        let config = PatternConfig {
+            patterns: None,
+            tolerances: HashMap::from([(PatternKind::Doji, 0.0), (PatternKind::PinBar, 1.0)]),
+        };
+        assert!(PatternScanner::new(&config).is_ok());
+    }
+}
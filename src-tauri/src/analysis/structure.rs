@@ -0,0 +1,771 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use tauri::AppHandle;
+
+use crate::upload_function::bars::atr;
+use crate::upload_function::candles::{load_timeframe_candles, Candle};
+use crate::upload_function::local_upload::load_symbol_data;
+
+// How swing points are found. Fractals need `strength` lower highs (higher lows) on each
+// side, zigzag swings need a reversal of `deviation` percent from the extreme.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum SwingMethod {
+    Fractal { strength: usize },
+    Zigzag { deviation: f64 },
+}
+
+impl Default for SwingMethod {
+    fn default() -> Self {
+        SwingMethod::Fractal { strength: 2 }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    High,
+    Low,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum StructureLabel {
+    HH,
+    HL,
+    LH,
+    LL,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakKind {
+    Bos,   // Break in the direction of the trend
+    Choch, // Break against it, the trend flips
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Bullish,
+    Bearish,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SwingPoint {
+    pub side: Side,
+    pub timestamp: i64, // Bar of the extreme
+    pub price: f64,
+    pub label: Option<StructureLabel>, // None for the first swing of each side
+    pub confirmed_at: i64, // Bar that made the swing known, nothing before it can use it
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct StructureBreak {
+    pub kind: BreakKind,
+    pub direction: Direction,
+    pub level: f64,      // Swing price that was closed through
+    pub swing_time: i64, // When that swing was made
+    pub timestamp: i64,  // Bar that closed through it
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StructureEvent {
+    Swing(SwingPoint),
+    Break(StructureBreak),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
+pub struct StructureConfig {
+    #[serde(default, flatten)]
+    pub swings: SwingMethod,
+}
+
+// Finds swings and structure breaks one closed bar at a time, so a replay sees exactly what
+// was knowable at each bar
+pub struct StructureTracker {
+    method: SwingMethod,
+    window: VecDeque<Candle>,    // Fractal: the last 2 * strength + 1 bars
+    leg: Option<(Side, Candle)>, // Zigzag: direction of the current leg and its extreme bar
+    extremes: Option<(Candle, Candle)>, // Zigzag: highest and lowest bar before the first leg
+    last_high: Option<(SwingPoint, bool)>, // Latest swing high and whether it was broken
+    last_low: Option<(SwingPoint, bool)>,
+    trend: Option<Direction>,
+}
+
+impl StructureTracker {
+    pub fn new(config: &StructureConfig) -> Result<Self, String> {
+        match config.swings {
+            SwingMethod::Fractal { strength: 0 } => {
+                return Err("Fractal strength must be at least 1".to_string())
+            }
+            SwingMethod::Zigzag { deviation } if deviation <= 0.0 => {
+                return Err("Zigzag deviation must be positive".to_string())
+            }
+            _ => {}
+        }
+
+        Ok(StructureTracker {
+            method: config.swings,
+            window: VecDeque::new(),
+            leg: None,
+            extremes: None,
+            last_high: None,
+            last_low: None,
+            trend: None,
+        })
+    }
+
+    fn swing(&mut self, side: Side, bar: &Candle, confirmed_at: i64) -> SwingPoint {
+        let price = match side {
+            Side::High => bar.high,
+            Side::Low => bar.low,
+        };
+
+        let previous = match side {
+            Side::High => &self.last_high,
+            Side::Low => &self.last_low,
+        };
+
+        let label = previous.as_ref().map(|(previous, _)| match side {
+            Side::High if price > previous.price => StructureLabel::HH,
+            Side::High => StructureLabel::LH,
+            Side::Low if price > previous.price => StructureLabel::HL,
+            Side::Low => StructureLabel::LL,
+        });
+
+        let point = SwingPoint {
+            side,
+            timestamp: bar.timestamp,
+            price,
+            label,
+            confirmed_at,
+        };
+
+        match side {
+            Side::High => self.last_high = Some((point.clone(), false)),
+            Side::Low => self.last_low = Some((point.clone(), false)),
+        }
+
+        point
+    }
+
+    fn find_swings(&mut self, candle: &Candle) -> Vec<SwingPoint> {
+        let mut swings = Vec::new();
+
+        match self.method {
+            SwingMethod::Fractal { strength } => {
+                self.window.push_back(candle.clone());
+                if self.window.len() > 2 * strength + 1 {
+                    self.window.pop_front();
+                }
+                if self.window.len() < 2 * strength + 1 {
+                    return swings;
+                }
+
+                let middle = self.window[strength].clone();
+                let (left, right) = (
+                    self.window.range(..strength),
+                    self.window.range(strength + 1..),
+                );
+
+                // Strictly beyond the left side, not beyond the right, so flat tops count once
+                let is_high = left.clone().all(|c| c.high < middle.high)
+                    && right.clone().all(|c| c.high <= middle.high);
+                let is_low = left.clone().all(|c| c.low > middle.low)
+                    && right.clone().all(|c| c.low >= middle.low);
+
+                if is_high {
+                    swings.push(self.swing(Side::High, &middle, candle.timestamp));
+                }
+                if is_low {
+                    swings.push(self.swing(Side::Low, &middle, candle.timestamp));
+                }
+            }
+            SwingMethod::Zigzag { deviation } => {
+                let reversal = |extreme: f64| extreme * deviation / 100.0;
+
+                match self.leg.take() {
+                    None => {
+                        let (high, low) = self
+                            .extremes
+                            .get_or_insert_with(|| (candle.clone(), candle.clone()));
+                        if candle.high > high.high {
+                            *high = candle.clone();
+                        }
+                        if candle.low < low.low {
+                            *low = candle.clone();
+                        }
+
+                        // The first leg starts once price has moved far enough either way
+                        let (high, low) = (high.clone(), low.clone());
+                        if high.high - candle.low >= reversal(high.high)
+                            && high.timestamp < candle.timestamp
+                        {
+                            swings.push(self.swing(Side::High, &high, candle.timestamp));
+                            self.leg = Some((Side::Low, candle.clone()));
+                        } else if candle.high - low.low >= reversal(low.low)
+                            && low.timestamp < candle.timestamp
+                        {
+                            swings.push(self.swing(Side::Low, &low, candle.timestamp));
+                            self.leg = Some((Side::High, candle.clone()));
+                        }
+                    }
+                    Some((Side::High, extreme)) => {
+                        if candle.high >= extreme.high {
+                            self.leg = Some((Side::High, candle.clone()));
+                        } else if extreme.high - candle.low >= reversal(extreme.high) {
+                            swings.push(self.swing(Side::High, &extreme, candle.timestamp));
+                            self.leg = Some((Side::Low, candle.clone()));
+                        } else {
+                            self.leg = Some((Side::High, extreme));
+                        }
+                    }
+                    Some((Side::Low, extreme)) => {
+                        if candle.low <= extreme.low {
+                            self.leg = Some((Side::Low, candle.clone()));
+                        } else if candle.high - extreme.low >= reversal(extreme.low) {
+                            swings.push(self.swing(Side::Low, &extreme, candle.timestamp));
+                            self.leg = Some((Side::High, candle.clone()));
+                        } else {
+                            self.leg = Some((Side::Low, extreme));
+                        }
+                    }
+                }
+            }
+        }
+
+        swings
+    }
+
+    // A close through the latest unbroken swing is a break of structure, or a change of
+    // character when it goes against the current trend. Both sides are checked, high first,
+    // so a bar that closes through both reports both and leaves the trend bearish.
+    fn find_breaks(&mut self, candle: &Candle) -> Vec<StructureBreak> {
+        let mut broken_swings = Vec::new();
+
+        if let Some((high, broken @ false)) = &mut self.last_high {
+            if candle.close > high.price {
+                *broken = true;
+                broken_swings.push((Direction::Bullish, high.clone()));
+            }
+        }
+        if let Some((low, broken @ false)) = &mut self.last_low {
+            if candle.close < low.price {
+                *broken = true;
+                broken_swings.push((Direction::Bearish, low.clone()));
+            }
+        }
+
+        broken_swings
+            .into_iter()
+            .map(|(direction, swing)| {
+                let kind = match self.trend {
+                    Some(trend) if trend != direction => BreakKind::Choch,
+                    _ => BreakKind::Bos,
+                };
+                self.trend = Some(direction);
+
+                StructureBreak {
+                    kind,
+                    direction,
+                    level: swing.price,
+                    swing_time: swing.timestamp,
+                    timestamp: candle.timestamp,
+                }
+            })
+            .collect()
+    }
+
+    // Feed one closed bar, returns what it revealed
+    pub fn push(&mut self, candle: &Candle) -> Vec<StructureEvent> {
+        let mut events: Vec<StructureEvent> = self
+            .find_swings(candle)
+            .into_iter()
+            .map(StructureEvent::Swing)
+            .collect();
+
+        events.extend(
+            self.find_breaks(candle)
+                .into_iter()
+                .map(StructureEvent::Break),
+        );
+
+        events
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoneKind {
+    Support,
+    Resistance,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Zone {
+    pub kind: ZoneKind, // Relative to the last close
+    pub low: f64,
+    pub high: f64,
+    pub touches: usize, // Swings inside the zone
+    pub first_touch: i64,
+    pub last_touch: i64,
+}
+
+// Group swing prices into zones no wider than `tolerance`, lowest price first
+pub fn cluster_zones(
+    swings: &[SwingPoint],
+    tolerance: f64,
+    min_touches: usize,
+    last_close: f64,
+) -> Vec<Zone> {
+    let mut sorted: Vec<&SwingPoint> = swings.iter().collect();
+    sorted.sort_by(|a, b| a.price.total_cmp(&b.price));
+
+    let mut zones = Vec::new();
+    let mut cluster: Vec<&SwingPoint> = Vec::new();
+
+    let mut flush = |cluster: &mut Vec<&SwingPoint>| {
+        if cluster.len() >= min_touches.max(1) {
+            let low = cluster[0].price;
+            let high = cluster[cluster.len() - 1].price;
+            zones.push(Zone {
+                kind: if (low + high) / 2.0 <= last_close {
+                    ZoneKind::Support
+                } else {
+                    ZoneKind::Resistance
+                },
+                low,
+                high,
+                touches: cluster.len(),
+                first_touch: cluster.iter().map(|s| s.timestamp).min().unwrap_or(0),
+                last_touch: cluster.iter().map(|s| s.timestamp).max().unwrap_or(0),
+            });
+        }
+        cluster.clear();
+    };
+
+    for swing in sorted {
+        if cluster
+            .first()
+            .is_some_and(|first| swing.price - first.price > tolerance)
+        {
+            flush(&mut cluster);
+        }
+        cluster.push(swing);
+    }
+    flush(&mut cluster);
+
+    zones.sort_by(|a, b| b.touches.cmp(&a.touches).then(a.low.total_cmp(&b.low)));
+    zones
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DrawingKind {
+    Label,
+    Line,
+    Rectangle,
+}
+
+// Ready to draw on a chart: a label at (time1, price1), a line or a rectangle to (time2, price2)
+#[derive(Debug, Serialize, Clone)]
+pub struct Drawing {
+    pub kind: DrawingKind,
+    pub time1: i64,
+    pub price1: f64,
+    pub time2: Option<i64>,
+    pub price2: Option<f64>,
+    pub text: String,
+    pub color: String,
+}
+
+const BULLISH_COLOR: &str = "#22c55e";
+const BEARISH_COLOR: &str = "#ef4444";
+
+fn drawings(
+    swings: &[SwingPoint],
+    breaks: &[StructureBreak],
+    zones: &[Zone],
+    last_time: i64,
+) -> Vec<Drawing> {
+    let swing_labels = swings.iter().map(|swing| Drawing {
+        kind: DrawingKind::Label,
+        time1: swing.timestamp,
+        price1: swing.price,
+        time2: None,
+        price2: None,
+        text: swing
+            .label
+            .map(|label| format!("{:?}", label))
+            .unwrap_or_else(|| match swing.side {
+                Side::High => "H".to_string(),
+                Side::Low => "L".to_string(),
+            }),
+        color: match swing.label {
+            Some(StructureLabel::HH | StructureLabel::HL) => BULLISH_COLOR,
+            Some(StructureLabel::LH | StructureLabel::LL) => BEARISH_COLOR,
+            None => "#9ca3af",
+        }
+        .to_string(),
+    });
+
+    let break_lines = breaks.iter().map(|b| Drawing {
+        kind: DrawingKind::Line,
+        time1: b.swing_time,
+        price1: b.level,
+        time2: Some(b.timestamp),
+        price2: Some(b.level),
+        text: match b.kind {
+            BreakKind::Bos => "BOS".to_string(),
+            BreakKind::Choch => "CHoCH".to_string(),
+        },
+        color: match b.direction {
+            Direction::Bullish => BULLISH_COLOR,
+            Direction::Bearish => BEARISH_COLOR,
+        }
+        .to_string(),
+    });
+
+    let zone_boxes = zones.iter().map(|zone| Drawing {
+        kind: DrawingKind::Rectangle,
+        time1: zone.first_touch,
+        price1: zone.high,
+        time2: Some(last_time),
+        price2: Some(zone.low),
+        text: format!(
+            "{} x{}",
+            match zone.kind {
+                ZoneKind::Support => "S",
+                ZoneKind::Resistance => "R",
+            },
+            zone.touches
+        ),
+        color: match zone.kind {
+            ZoneKind::Support => BULLISH_COLOR,
+            ZoneKind::Resistance => BEARISH_COLOR,
+        }
+        .to_string(),
+    });
+
+    swing_labels.chain(break_lines).chain(zone_boxes).collect()
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StructureRequest {
+    pub symbol: String,
+    pub timeframe: String,
+    #[serde(default)]
+    pub start: Option<i64>, // Unix seconds
+    #[serde(default)]
+    pub end: Option<i64>,
+    #[serde(default)]
+    pub config: StructureConfig,
+    #[serde(default)]
+    pub zone_tolerance: Option<f64>, // Price distance that still joins a zone, half an ATR by default
+    #[serde(default)]
+    pub min_touches: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StructureResult {
+    pub swings: Vec<SwingPoint>,
+    pub breaks: Vec<StructureBreak>,
+    pub zones: Vec<Zone>,
+    pub drawings: Vec<Drawing>,
+    pub trend: Option<Direction>, // After the last bar
+}
+
+const DEFAULT_MIN_TOUCHES: usize = 2;
+
+pub fn analyze_structure(
+    candles: &[Candle],
+    config: &StructureConfig,
+    zone_tolerance: Option<f64>,
+    min_touches: usize,
+) -> Result<StructureResult, String> {
+    let mut tracker = StructureTracker::new(config)?;
+    let (mut swings, mut breaks) = (Vec::new(), Vec::new());
+
+    for candle in candles {
+        for event in tracker.push(candle) {
+            match event {
+                StructureEvent::Swing(swing) => swings.push(swing),
+                StructureEvent::Break(b) => breaks.push(b),
+            }
+        }
+    }
+
+    let tolerance = match zone_tolerance {
+        Some(tolerance) if tolerance < 0.0 => {
+            return Err("Zone tolerance can't be negative".to_string())
+        }
+        Some(tolerance) => tolerance,
+        None => atr(candles, 14).unwrap_or(0.0) / 2.0,
+    };
+
+    let (last_close, last_time) = candles
+        .last()
+        .map(|c| (c.close, c.timestamp))
+        .unwrap_or((0.0, 0));
+
+    let zones = cluster_zones(&swings, tolerance, min_touches, last_close);
+    let drawings = drawings(&swings, &breaks, &zones, last_time);
+
+    Ok(StructureResult {
+        swings,
+        breaks,
+        zones,
+        drawings,
+        trend: tracker.trend,
+    })
+}
+
+// Swings, market structure and support/resistance zones of a symbol's candles
+#[tauri::command]
+pub async fn detect_structure(
+    app_handle: AppHandle,
+    request: StructureRequest,
+) -> Result<StructureResult, String> {
+    let symbol_data = load_symbol_data(&app_handle, &request.symbol)?;
+
+    let mut candles = load_timeframe_candles(&symbol_data, &request.timeframe)?;
+    candles.retain(|c| {
+        request.start.is_none_or(|start| c.timestamp >= start)
+            && request.end.is_none_or(|end| c.timestamp < end)
+    });
+
+    if candles.is_empty() {
+        return Err("No candles in the selected range".to_string());
+    }
+
+    let result = analyze_structure(
+        &candles,
+        &request.config,
+        request.zone_tolerance,
+        request.min_touches.unwrap_or(DEFAULT_MIN_TOUCHES),
+    )?;
+
+    println!(
+        "✓ {} {} - {} swings, {} breaks, {} zones",
+        request.symbol,
+        request.timeframe,
+        result.swings.len(),
+        result.breaks.len(),
+        result.zones.len()
+    );
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(timestamp: i64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            timestamp,
+            open: close,
+            high,
+            low,
+            close,
+            volume: 0.0,
+            adj_close: None,
+        }
+    }
+
+    fn fractal(strength: usize) -> StructureTracker {
+        StructureTracker::new(&StructureConfig {
+            swings: SwingMethod::Fractal { strength },
+        })
+        .unwrap()
+    }
+
+    fn run(
+        tracker: &mut StructureTracker,
+        candles: &[Candle],
+    ) -> (Vec<SwingPoint>, Vec<StructureBreak>) {
+        let (mut swings, mut breaks) = (Vec::new(), Vec::new());
+        for candle in candles {
+            for event in tracker.push(candle) {
+                match event {
+                    StructureEvent::Swing(swing) => swings.push(swing),
+                    StructureEvent::Break(b) => breaks.push(b),
+                }
+            }
+        }
+        (swings, breaks)
+    }
+
+    // Up to a higher high, a close under the first low, then a lower high and a higher low
+    fn fixture() -> Vec<Candle> {
+        vec![
+            bar(0, 10.0, 9.0, 9.5),
+            bar(1, 12.0, 10.0, 11.0),
+            bar(2, 11.0, 8.0, 9.0),
+            bar(3, 13.0, 9.0, 12.5),
+            bar(4, 12.0, 9.5, 10.0),
+            bar(5, 11.0, 7.0, 7.5),
+            bar(6, 11.5, 8.5, 9.0),
+            bar(7, 10.0, 8.0, 9.0),
+            bar(8, 10.5, 9.0, 10.0),
+        ]
+    }
+
+    #[test]
+    fn fractal_swings_are_labelled_against_the_previous_swing() {
+        let (swings, _) = run(&mut fractal(1), &fixture());
+
+        let found: Vec<(Side, i64, f64, Option<StructureLabel>, i64)> = swings
+            .iter()
+            .map(|s| (s.side, s.timestamp, s.price, s.label, s.confirmed_at))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Side::High, 1, 12.0, None, 2),
+                (Side::Low, 2, 8.0, None, 3),
+                (Side::High, 3, 13.0, Some(StructureLabel::HH), 4),
+                (Side::Low, 5, 7.0, Some(StructureLabel::LL), 6),
+                (Side::High, 6, 11.5, Some(StructureLabel::LH), 7),
+                (Side::Low, 7, 8.0, Some(StructureLabel::HL), 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn closes_through_swings_are_bos_then_choch() {
+        let mut tracker = fractal(1);
+        let (_, breaks) = run(&mut tracker, &fixture());
+
+        let found: Vec<(BreakKind, Direction, f64, i64, i64)> = breaks
+            .iter()
+            .map(|b| (b.kind, b.direction, b.level, b.swing_time, b.timestamp))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (BreakKind::Bos, Direction::Bullish, 12.0, 1, 3),
+                (BreakKind::Choch, Direction::Bearish, 8.0, 2, 5),
+            ]
+        );
+        assert_eq!(tracker.trend, Some(Direction::Bearish));
+    }
+
+    #[test]
+    fn a_bar_closing_through_both_swings_reports_both_breaks() {
+        // A swing high under the swing low, both unbroken, and an uptrend
+        let mut tracker = fractal(1);
+        tracker.swing(Side::High, &bar(1, 10.0, 9.0, 9.5), 2);
+        tracker.swing(Side::Low, &bar(3, 13.0, 12.0, 12.5), 4);
+        tracker.trend = Some(Direction::Bullish);
+
+        let (_, breaks) = run(&mut tracker, &[bar(5, 13.0, 9.0, 11.0)]);
+
+        let found: Vec<(BreakKind, Direction, f64)> = breaks
+            .iter()
+            .map(|b| (b.kind, b.direction, b.level))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (BreakKind::Bos, Direction::Bullish, 10.0),
+                (BreakKind::Choch, Direction::Bearish, 12.0),
+            ]
+        );
+        assert_eq!(tracker.trend, Some(Direction::Bearish));
+
+        // Broken swings are not broken again
+        let (_, breaks) = run(&mut tracker, &[bar(6, 14.0, 8.0, 13.5)]);
+        assert!(breaks.is_empty());
+    }
+
+    #[test]
+    fn zigzag_swings_need_the_full_reversal() {
+        let mut tracker = StructureTracker::new(&StructureConfig {
+            swings: SwingMethod::Zigzag { deviation: 10.0 },
+        })
+        .unwrap();
+
+        let (swings, _) = run(
+            &mut tracker,
+            &[
+                bar(0, 104.0, 103.0, 103.5),
+                bar(1, 110.0, 105.0, 109.0),
+                bar(2, 108.0, 98.0, 99.0),
+                bar(3, 105.0, 90.0, 92.0),
+                bar(4, 98.0, 95.0, 97.0),
+                bar(5, 100.0, 95.0, 99.0),
+            ],
+        );
+
+        // 110 to 98 is a 10.9% drop, 90 to 98 only 8.9% until 100 makes it 11.1%
+        let found: Vec<(Side, i64, f64, i64)> = swings
+            .iter()
+            .map(|s| (s.side, s.timestamp, s.price, s.confirmed_at))
+            .collect();
+        assert_eq!(
+            found,
+            vec![(Side::High, 1, 110.0, 2), (Side::Low, 3, 90.0, 5)]
+        );
+    }
+
+    #[test]
+    fn invalid_swing_settings_are_rejected() {
+        for swings in [
+            SwingMethod::Fractal { strength: 0 },
+            SwingMethod::Zigzag { deviation: 0.0 },
+            SwingMethod::Zigzag { deviation: -1.0 },
+        ] {
+            assert!(StructureTracker::new(&StructureConfig { swings }).is_err());
+        }
+    }
+
+    #[test]
+    fn swing_prices_cluster_into_zones_within_the_tolerance() {
+        let swing = |timestamp: i64, price: f64| SwingPoint {
+            side: Side::Low,
+            timestamp,
+            price,
+            label: None,
+            confirmed_at: timestamp,
+        };
+        let swings = [
+            swing(5, 2.01),
+            swing(1, 1.00),
+            swing(9, 3.00),
+            swing(4, 1.05),
+            swing(2, 2.00),
+            swing(7, 1.02),
+        ];
+
+        let zones = cluster_zones(&swings, 0.06, 2, 1.5);
+
+        // 3.00 alone is under the minimum touches, the most touched zone comes first
+        let found: Vec<(ZoneKind, f64, f64, usize, i64, i64)> = zones
+            .iter()
+            .map(|z| {
+                (
+                    z.kind,
+                    z.low,
+                    z.high,
+                    z.touches,
+                    z.first_touch,
+                    z.last_touch,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (ZoneKind::Support, 1.00, 1.05, 3, 1, 7),
+                (ZoneKind::Resistance, 2.00, 2.01, 2, 2, 5),
+            ]
+        );
+
+        // A tighter tolerance splits the lower cluster
+        let zones = cluster_zones(&swings, 0.03, 1, 1.5);
+        assert_eq!(zones.len(), 4);
+        assert_eq!((zones[0].low, zones[0].high), (1.00, 1.02));
+    }
+}
@@ -0,0 +1,854 @@
+use rusqlite::Connection;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use tauri::AppHandle;
+
+use crate::analysis::fibonacci::{
+    create_level_sets_table, read_level_sets, remove_level_set, write_level_set, FibLevelSet,
+};
+use crate::presets::{
+    create_presets_table, read_presets, write_presets, DrawingStyle, IndicatorTemplate, Preset,
+    PresetKind, ShortcutBinding,
+};
+use crate::settings::{current_settings, merge_section, replace_settings, AppSettings};
+use crate::upload_function::column_mapping::{
+    create_profiles_table, open_settings_db, read_import_profiles, remove_import_profile,
+    write_import_profile, ColumnMapping,
+};
+
+// Bumped whenever the bundle format changes
+pub const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleSection {
+    Settings,
+    Shortcuts,
+    ImportProfiles,
+    IndicatorTemplates,
+    DrawingStyles,
+    FibLevelSets,
+}
+
+const SECTIONS: [BundleSection; 6] = [
+    BundleSection::Settings,
+    BundleSection::Shortcuts,
+    BundleSection::ImportProfiles,
+    BundleSection::IndicatorTemplates,
+    BundleSection::DrawingStyles,
+    BundleSection::FibLevelSets,
+];
+
+impl BundleSection {
+    fn key(self) -> &'static str {
+        match self {
+            BundleSection::Settings => "settings",
+            BundleSection::Shortcuts => "shortcuts",
+            BundleSection::ImportProfiles => "import_profiles",
+            BundleSection::IndicatorTemplates => "indicator_templates",
+            BundleSection::DrawingStyles => "drawing_styles",
+            BundleSection::FibLevelSets => "fib_level_sets",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BundleProfile {
+    pub name: String,
+    pub mapping: ColumnMapping,
+}
+
+// Everything needed to reproduce someone's setup on another machine. Sections left out of
+// an export are missing from the file and untouched by an import.
+#[derive(Debug, Serialize)]
+pub struct SettingsBundle {
+    pub version: u32,
+    pub exported_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub settings: Option<AppSettings>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shortcuts: Option<Vec<ShortcutBinding>>, // Only the ones rebound by the user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_profiles: Option<Vec<BundleProfile>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indicator_templates: Option<Vec<IndicatorTemplate>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drawing_styles: Option<Vec<DrawingStyle>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fib_level_sets: Option<Vec<FibLevelSet>>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    #[default]
+    Merge, // Add to and overwrite what is there
+    Replace, // Each imported section replaces the current one entirely
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BundleImportRequest {
+    pub path: String,
+    #[serde(default)]
+    pub mode: ImportMode,
+    #[serde(default)]
+    pub sections: Option<Vec<BundleSection>>, // Every section in the file when missing
+    #[serde(default)]
+    pub dry_run: bool, // Validate and report without changing anything
+}
+
+#[derive(Debug, Serialize)]
+pub struct SectionCount {
+    pub section: BundleSection,
+    pub count: usize, // Items, or settings groups, that were valid
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleExportResult {
+    pub success: bool,
+    pub message: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleImportResult {
+    pub success: bool,
+    pub message: String,
+    pub imported: Vec<SectionCount>,
+    pub issues: Vec<String>, // "section[index].key: problem" for everything that was skipped
+}
+
+fn wanted(sections: &Option<Vec<BundleSection>>, section: BundleSection) -> bool {
+    sections.as_ref().is_none_or(|s| s.contains(&section))
+}
+
+fn presets_of<T>(presets: &[Preset], pick: impl Fn(&Preset) -> Option<&T>) -> Vec<T>
+where
+    T: Clone,
+{
+    presets.iter().filter_map(pick).cloned().collect()
+}
+
+// Every table a bundle reads or writes lives in settings.db
+fn create_bundle_tables(conn: &Connection) -> Result<(), String> {
+    create_presets_table(conn)?;
+    create_profiles_table(conn)?;
+    create_level_sets_table(conn)
+}
+
+fn open_bundle_db(app_handle: &AppHandle) -> Result<Connection, String> {
+    let conn = open_settings_db(app_handle)?;
+    create_bundle_tables(&conn)?;
+
+    Ok(conn)
+}
+
+// Collect the wanted sections. Settings come from the settings service, so they are passed in.
+fn build_bundle(
+    conn: &Connection,
+    settings: Option<AppSettings>,
+    sections: &Option<Vec<BundleSection>>,
+) -> Result<SettingsBundle, String> {
+    let presets = read_presets(conn, None)?;
+
+    let mut bundle = SettingsBundle {
+        version: BUNDLE_VERSION,
+        exported_at: chrono::Utc::now().to_rfc3339(),
+        settings: settings.filter(|_| wanted(sections, BundleSection::Settings)),
+        shortcuts: None,
+        import_profiles: None,
+        indicator_templates: None,
+        drawing_styles: None,
+        fib_level_sets: None,
+    };
+
+    if wanted(sections, BundleSection::Shortcuts) {
+        bundle.shortcuts = Some(presets_of(&presets, |p| match p {
+            Preset::Shortcut(binding) => Some(binding),
+            _ => None,
+        }));
+    }
+    if wanted(sections, BundleSection::ImportProfiles) {
+        bundle.import_profiles = Some(
+            read_import_profiles(conn)?
+                .into_iter()
+                .map(|p| BundleProfile {
+                    name: p.name,
+                    mapping: p.mapping,
+                })
+                .collect(),
+        );
+    }
+    if wanted(sections, BundleSection::IndicatorTemplates) {
+        bundle.indicator_templates = Some(presets_of(&presets, |p| match p {
+            Preset::IndicatorTemplate(template) => Some(template),
+            _ => None,
+        }));
+    }
+    if wanted(sections, BundleSection::DrawingStyles) {
+        bundle.drawing_styles = Some(presets_of(&presets, |p| match p {
+            Preset::DrawingStyle(style) => Some(style),
+            _ => None,
+        }));
+    }
+    if wanted(sections, BundleSection::FibLevelSets) {
+        bundle.fib_level_sets = Some(read_level_sets(conn)?);
+    }
+
+    Ok(bundle)
+}
+
+#[tauri::command]
+pub async fn export_settings_bundle(
+    app_handle: AppHandle,
+    path: String,
+    sections: Option<Vec<BundleSection>>,
+) -> Result<BundleExportResult, String> {
+    let settings = if wanted(&sections, BundleSection::Settings) {
+        Some(current_settings(&app_handle)?)
+    } else {
+        None
+    };
+    let bundle = build_bundle(&open_bundle_db(&app_handle)?, settings, &sections)?;
+
+    let json = serde_json::to_string_pretty(&bundle)
+        .map_err(|e| format!("Failed to serialize bundle: {}", e))?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write bundle: {}", e))?;
+
+    println!("✓ Exported settings bundle to {}", path);
+
+    Ok(BundleExportResult {
+        success: true,
+        message: format!("Settings exported to {}", path),
+        path,
+    })
+}
+
+// Report keys of `value` that parsing dropped, by comparing with what the parsed item
+// serializes back to
+fn unknown_keys(path: &str, value: &Value, known: &Value, issues: &mut Vec<String>) {
+    match (value, known) {
+        (Value::Object(value), Value::Object(known)) => {
+            for (key, field) in value {
+                let field_path = format!("{}.{}", path, key);
+                match known.get(key) {
+                    Some(known) => unknown_keys(&field_path, field, known, issues),
+                    None => issues.push(format!("{}: unknown key", field_path)),
+                }
+            }
+        }
+        (Value::Array(values), Value::Array(known)) => {
+            for (index, (value, known)) in values.iter().zip(known).enumerate() {
+                unknown_keys(&format!("{}[{}]", path, index), value, known, issues);
+            }
+        }
+        _ => {}
+    }
+}
+
+// Parse and validate every item of a list section, keeping the good ones. A section that is
+// not a list at all fails the import.
+fn parse_items<T>(
+    section: BundleSection,
+    value: Value,
+    validate: impl Fn(&mut T) -> Result<(), String>,
+    issues: &mut Vec<String>,
+) -> Result<Vec<T>, String>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let Value::Array(items) = value else {
+        return Err(format!("{}: expected a list", section.key()));
+    };
+
+    let mut parsed = Vec::new();
+
+    for (index, item) in items.into_iter().enumerate() {
+        let path = format!("{}[{}]", section.key(), index);
+
+        let mut entry = match serde_json::from_value::<T>(item.clone()) {
+            Ok(entry) => entry,
+            Err(e) => {
+                issues.push(format!("{}: {}", path, e));
+                continue;
+            }
+        };
+
+        if let Ok(known) = serde_json::to_value(&entry) {
+            unknown_keys(&path, &item, &known, issues);
+        }
+
+        match validate(&mut entry) {
+            Ok(()) => parsed.push(entry),
+            Err(e) => issues.push(format!("{}: {}", path, e)),
+        }
+    }
+
+    Ok(parsed)
+}
+
+// A section that has been read and validated, waiting to be written
+enum ParsedSection {
+    Settings(AppSettings),
+    Presets(PresetKind, Vec<Preset>),
+    Profiles(Vec<BundleProfile>),
+    LevelSets(Vec<FibLevelSet>),
+}
+
+// Apply the settings groups to the current settings, or to the defaults when replacing.
+// Returns the settings with the number of groups that had at least one field accepted.
+fn parse_settings(
+    current: &AppSettings,
+    value: Value,
+    mode: ImportMode,
+    issues: &mut Vec<String>,
+) -> Result<(AppSettings, usize), String> {
+    let Value::Object(groups) = value else {
+        return Err("settings: expected an object".to_string());
+    };
+
+    // Replacing starts from the defaults, so settings missing from the bundle are reset
+    let mut settings = match mode {
+        ImportMode::Merge => current.clone(),
+        ImportMode::Replace => AppSettings::default(),
+    };
+
+    let mut count = 0;
+
+    for (group, patch) in &groups {
+        match patch {
+            Value::Object(patch) => {
+                let (applied, group_issues) = merge_section(&mut settings, group, patch);
+                issues.extend(
+                    group_issues
+                        .into_iter()
+                        .map(|issue| format!("settings.{}", issue)),
+                );
+                if applied > 0 {
+                    count += 1;
+                }
+            }
+            _ => issues.push(format!("settings.{}: expected an object", group)),
+        }
+    }
+
+    Ok((settings, count))
+}
+
+fn write_profiles(
+    conn: &Connection,
+    profiles: Vec<BundleProfile>,
+    mode: ImportMode,
+) -> Result<(), String> {
+    if mode == ImportMode::Replace {
+        for existing in read_import_profiles(conn)? {
+            if !profiles.iter().any(|p| p.name == existing.name) {
+                remove_import_profile(conn, &existing.name)?;
+            }
+        }
+    }
+
+    for profile in profiles {
+        write_import_profile(conn, &profile.name, &profile.mapping)?;
+    }
+
+    Ok(())
+}
+
+fn write_level_sets(
+    conn: &Connection,
+    sets: Vec<FibLevelSet>,
+    mode: ImportMode,
+) -> Result<(), String> {
+    if mode == ImportMode::Replace {
+        // The built-in default is listed without being stored, removing it is a no-op
+        for existing in read_level_sets(conn)? {
+            if !sets.iter().any(|s| s.name == existing.name) {
+                remove_level_set(conn, &existing.name)?;
+            }
+        }
+    }
+
+    for set in sets {
+        write_level_set(conn, &set)?;
+    }
+
+    Ok(())
+}
+
+// Read and validate every wanted section of a bundle, then write them in one transaction unless
+// this is a dry run. Settings are returned rather than written, they go through the settings
+// service so the rest of the app hears about them.
+fn import_bundle(
+    conn: &mut Connection,
+    content: &str,
+    current: &AppSettings,
+    request: &BundleImportRequest,
+) -> Result<(BundleImportResult, Option<AppSettings>), String> {
+    let Value::Object(mut bundle) = serde_json::from_str::<Value>(content)
+        .map_err(|e| format!("Failed to parse bundle: {}", e))?
+    else {
+        return Err("A settings bundle must be a JSON object".to_string());
+    };
+
+    let version = bundle
+        .get("version")
+        .and_then(Value::as_u64)
+        .ok_or("Not a settings bundle, 'version' is missing".to_string())?;
+    if version > BUNDLE_VERSION as u64 {
+        return Err(format!(
+            "Bundle was exported by a newer version (format {})",
+            version
+        ));
+    }
+
+    let mut issues = Vec::new();
+
+    for key in bundle.keys() {
+        if key != "version" && key != "exported_at" && !SECTIONS.iter().any(|s| s.key() == key) {
+            issues.push(format!("{}: unknown section", key));
+        }
+    }
+
+    let mut imported = Vec::new();
+    let mut parsed = Vec::new();
+
+    for section in SECTIONS {
+        if !wanted(&request.sections, section) {
+            continue;
+        }
+        let Some(value) = bundle.remove(section.key()) else {
+            continue;
+        };
+
+        // A section whose every entry was rejected is left alone rather than emptied
+        let had_entries = match &value {
+            Value::Array(items) => !items.is_empty(),
+            Value::Object(groups) => !groups.is_empty(),
+            _ => true,
+        };
+
+        let (count, parsed_section) = match section {
+            BundleSection::Settings => {
+                let (settings, count) = parse_settings(current, value, request.mode, &mut issues)?;
+                (count, ParsedSection::Settings(settings))
+            }
+            BundleSection::Shortcuts => {
+                let bindings = parse_items(section, value, ShortcutBinding::validate, &mut issues)?;
+                let presets: Vec<Preset> = bindings.into_iter().map(Preset::Shortcut).collect();
+                (
+                    presets.len(),
+                    ParsedSection::Presets(PresetKind::Shortcut, presets),
+                )
+            }
+            BundleSection::ImportProfiles => {
+                let profiles = parse_items(
+                    section,
+                    value,
+                    |profile: &mut BundleProfile| {
+                        profile.name = profile.name.trim().to_string();
+                        if profile.name.is_empty() {
+                            return Err("Profile name cannot be empty".to_string());
+                        }
+                        profile.mapping.validate().map(|_| ())
+                    },
+                    &mut issues,
+                )?;
+                (profiles.len(), ParsedSection::Profiles(profiles))
+            }
+            BundleSection::IndicatorTemplates => {
+                let templates =
+                    parse_items(section, value, IndicatorTemplate::validate, &mut issues)?;
+                let presets: Vec<Preset> = templates
+                    .into_iter()
+                    .map(Preset::IndicatorTemplate)
+                    .collect();
+                (
+                    presets.len(),
+                    ParsedSection::Presets(PresetKind::IndicatorTemplate, presets),
+                )
+            }
+            BundleSection::DrawingStyles => {
+                let styles = parse_items(section, value, DrawingStyle::validate, &mut issues)?;
+                let presets: Vec<Preset> = styles.into_iter().map(Preset::DrawingStyle).collect();
+                (
+                    presets.len(),
+                    ParsedSection::Presets(PresetKind::DrawingStyle, presets),
+                )
+            }
+            BundleSection::FibLevelSets => {
+                let sets = parse_items(section, value, FibLevelSet::validate, &mut issues)?;
+                (sets.len(), ParsedSection::LevelSets(sets))
+            }
+        };
+
+        imported.push(SectionCount { section, count });
+        if count > 0 || !had_entries {
+            parsed.push(parsed_section);
+        }
+    }
+
+    let mut settings = None;
+
+    if !request.dry_run {
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        for section in parsed {
+            match section {
+                ParsedSection::Settings(parsed) => settings = Some(parsed),
+                ParsedSection::Presets(kind, presets) => {
+                    write_presets(&tx, kind, &presets, request.mode == ImportMode::Replace)?
+                }
+                ParsedSection::Profiles(profiles) => write_profiles(&tx, profiles, request.mode)?,
+                ParsedSection::LevelSets(sets) => write_level_sets(&tx, sets, request.mode)?,
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to import bundle: {}", e))?;
+    }
+
+    let total: usize = imported.iter().map(|s| s.count).sum();
+    let message = format!(
+        "{} {} item(s) from {} section(s), {} issue(s)",
+        if request.dry_run {
+            "Checked"
+        } else {
+            "Imported"
+        },
+        total,
+        imported.len(),
+        issues.len()
+    );
+
+    Ok((
+        BundleImportResult {
+            success: true,
+            message,
+            imported,
+            issues,
+        },
+        settings,
+    ))
+}
+
+// Import a bundle written by export_settings_bundle. Unknown sections and keys are reported
+// and ignored, invalid items are reported and skipped, the rest is imported. A section of the
+// wrong shape fails the import, and every section is validated before the first one is written.
+#[tauri::command]
+pub async fn import_settings_bundle(
+    app_handle: AppHandle,
+    request: BundleImportRequest,
+) -> Result<BundleImportResult, String> {
+    let content =
+        fs::read_to_string(&request.path).map_err(|e| format!("Failed to read bundle: {}", e))?;
+    let current = current_settings(&app_handle)?;
+
+    let (result, settings) = import_bundle(
+        &mut open_bundle_db(&app_handle)?,
+        &content,
+        &current,
+        &request,
+    )?;
+
+    if let Some(settings) = settings {
+        replace_settings(&app_handle, settings, None)?;
+    }
+
+    println!("✓ {}", result.message);
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        create_bundle_tables(&conn).unwrap();
+        conn
+    }
+
+    fn request(mode: ImportMode, dry_run: bool) -> BundleImportRequest {
+        BundleImportRequest {
+            path: String::new(),
+            mode,
+            sections: None,
+            dry_run,
+        }
+    }
+
+    fn mapping() -> ColumnMapping {
+        serde_json::from_value(json!({
+            "has_header": false,
+            "date_col": 0,
+            "time_col": 1,
+            "date_format": "%Y.%m.%d %H:%M",
+            "open_col": 2,
+            "high_col": 3,
+            "low_col": 4,
+            "close_col": 5
+        }))
+        .unwrap()
+    }
+
+    fn style(name: &str) -> Preset {
+        Preset::DrawingStyle(DrawingStyle {
+            name: name.to_string(),
+            shape: "Trend Line".to_string(),
+            style: json!({"color": "#ff0000"}),
+        })
+    }
+
+    fn level_set(name: &str) -> FibLevelSet {
+        FibLevelSet {
+            name: name.to_string(),
+            retracement: vec![0.5],
+            extension: Vec::new(),
+            projection: Vec::new(),
+        }
+    }
+
+    // One of everything a bundle carries, plus the settings it was exported with
+    fn seed(conn: &Connection) -> AppSettings {
+        let shortcut = Preset::Shortcut(ShortcutBinding {
+            id: "new_chart".to_string(),
+            keys: vec!["ctrl+alt+n".to_string()],
+        });
+        write_presets(conn, PresetKind::Shortcut, &[shortcut], false).unwrap();
+        write_presets(conn, PresetKind::DrawingStyle, &[style("Red")], false).unwrap();
+        write_import_profile(conn, "MT4", &mapping()).unwrap();
+        write_level_set(conn, &level_set("Half")).unwrap();
+
+        let mut settings = AppSettings::default();
+        settings.trade.lot_size = 0.5;
+        settings
+    }
+
+    fn preset_names(conn: &Connection) -> Vec<String> {
+        read_presets(conn, None)
+            .unwrap()
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect()
+    }
+
+    fn profile_names(conn: &Connection) -> Vec<String> {
+        read_import_profiles(conn)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect()
+    }
+
+    fn level_set_names(conn: &Connection) -> Vec<String> {
+        read_level_sets(conn)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect()
+    }
+
+    fn exported(conn: &Connection, settings: AppSettings) -> String {
+        serde_json::to_string(&build_bundle(conn, Some(settings), &None).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn export_then_import_round_trips() {
+        let source = db();
+        let settings = seed(&source);
+        let content = exported(&source, settings.clone());
+
+        let mut target = db();
+        let (result, imported) = import_bundle(
+            &mut target,
+            &content,
+            &AppSettings::default(),
+            &request(ImportMode::Merge, false),
+        )
+        .unwrap();
+
+        assert!(result.issues.is_empty(), "{:?}", result.issues);
+        assert_eq!(imported, Some(settings));
+        assert_eq!(preset_names(&target), preset_names(&source));
+        assert_eq!(profile_names(&target), vec!["MT4"]);
+        assert_eq!(
+            read_import_profiles(&target).unwrap()[0].mapping.time_col,
+            Some(1)
+        );
+        // The built-in default travels with the bundle too
+        assert_eq!(level_set_names(&target), vec!["Default", "Half"]);
+
+        // Only the wanted sections are exported
+        let only = build_bundle(&source, None, &Some(vec![BundleSection::ImportProfiles])).unwrap();
+        assert!(only.settings.is_none() && only.shortcuts.is_none());
+        assert_eq!(only.import_profiles.map(|p| p.len()), Some(1));
+    }
+
+    #[test]
+    fn merge_adds_and_replace_swaps_sections() {
+        let source = db();
+        seed(&source);
+        let bundle: Value =
+            serde_json::from_str(&exported(&source, AppSettings::default())).unwrap();
+        let mut content = bundle.clone();
+        content["settings"] = json!({"general": {"showGrid": false}});
+        let content = content.to_string();
+
+        let mut current = AppSettings::default();
+        current.trade.lot_size = 0.2;
+
+        let existing = || {
+            let conn = db();
+            write_presets(&conn, PresetKind::DrawingStyle, &[style("Blue")], false).unwrap();
+            write_import_profile(&conn, "Old", &mapping()).unwrap();
+            write_level_set(&conn, &level_set("Wide")).unwrap();
+            conn
+        };
+
+        let mut merged = existing();
+        let (_, settings) = import_bundle(
+            &mut merged,
+            &content,
+            &current,
+            &request(ImportMode::Merge, false),
+        )
+        .unwrap();
+        let settings = settings.unwrap();
+        assert_eq!(preset_names(&merged), vec!["Blue", "Red", "new_chart"]);
+        assert_eq!(profile_names(&merged), vec!["MT4", "Old"]);
+        assert_eq!(level_set_names(&merged), vec!["Default", "Half", "Wide"]);
+        assert!(!settings.general.show_grid);
+        assert_eq!(settings.trade.lot_size, 0.2);
+
+        let mut replaced = existing();
+        let (_, settings) = import_bundle(
+            &mut replaced,
+            &content,
+            &current,
+            &request(ImportMode::Replace, false),
+        )
+        .unwrap();
+        let settings = settings.unwrap();
+        assert_eq!(preset_names(&replaced), vec!["Red", "new_chart"]);
+        assert_eq!(profile_names(&replaced), vec!["MT4"]);
+        assert_eq!(level_set_names(&replaced), vec!["Default", "Half"]);
+        // Settings missing from the bundle go back to their defaults
+        assert!(!settings.general.show_grid);
+        assert_eq!(settings.trade.lot_size, 0.01);
+    }
+
+    #[test]
+    fn dry_runs_write_nothing() {
+        let source = db();
+        let settings = seed(&source);
+        let content = exported(&source, settings);
+
+        let mut target = db();
+        let (result, imported) = import_bundle(
+            &mut target,
+            &content,
+            &AppSettings::default(),
+            &request(ImportMode::Replace, true),
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.message,
+            "Checked 8 item(s) from 6 section(s), 0 issue(s)"
+        );
+        assert_eq!(imported, None);
+        assert!(preset_names(&target).is_empty());
+        assert!(profile_names(&target).is_empty());
+        assert_eq!(level_set_names(&target), vec!["Default"]);
+    }
+
+    #[test]
+    fn unknown_and_invalid_entries_are_reported() {
+        let content = json!({
+            "version": 1,
+            "themes": [],
+            "settings": {"trade": {"lotSize": 0, "bogus": 1, "spread": 3}},
+            "import_profiles": [
+                {"name": "MT4", "mapping": serde_json::to_value(mapping()).unwrap(), "colour": "red"}
+            ],
+            "drawing_styles": [
+                {"name": "Red", "shape": "Trend Line"},
+                {"name": " ", "shape": "Trend Line"},
+                {"shape": "Ray"}
+            ]
+        })
+        .to_string();
+
+        let mut conn = db();
+        let (result, settings) = import_bundle(
+            &mut conn,
+            &content,
+            &AppSettings::default(),
+            &request(ImportMode::Merge, false),
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.issues[..4],
+            [
+                "themes: unknown section",
+                "settings.trade.bogus: unknown setting",
+                "settings.trade.lotSize: Lot size must be greater than 0",
+                "import_profiles[0].colour: unknown key",
+            ]
+        );
+        assert_eq!(
+            result.issues[4],
+            "drawing_styles[1]: Drawing style name cannot be empty"
+        );
+        assert!(result.issues[5].starts_with("drawing_styles[2]: missing field `name`"));
+        assert_eq!(result.issues.len(), 6);
+
+        // Everything valid is still imported
+        let settings = settings.unwrap();
+        assert_eq!(settings.trade.spread, 3.0);
+        assert_eq!(settings.trade.lot_size, 0.01);
+        assert_eq!(profile_names(&conn), vec!["MT4"]);
+        assert_eq!(preset_names(&conn), vec!["Red"]);
+    }
+
+    #[test]
+    fn a_malformed_section_writes_nothing() {
+        let content = json!({
+            "version": 1,
+            "settings": {"general": {"showGrid": false}},
+            "drawing_styles": [{"name": "Red", "shape": "Trend Line"}],
+            "shortcuts": "ctrl+n"
+        })
+        .to_string();
+
+        let mut conn = db();
+        let error = import_bundle(
+            &mut conn,
+            &content,
+            &AppSettings::default(),
+            &request(ImportMode::Merge, false),
+        )
+        .unwrap_err();
+
+        assert_eq!(error, "shortcuts: expected a list");
+        assert!(preset_names(&conn).is_empty());
+
+        let newer = json!({"version": BUNDLE_VERSION + 1}).to_string();
+        assert!(import_bundle(
+            &mut conn,
+            &newer,
+            &AppSettings::default(),
+            &request(ImportMode::Merge, false),
+        )
+        .unwrap_err()
+        .contains("newer version"));
+    }
+}
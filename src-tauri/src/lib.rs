@@ -1,26 +1,83 @@
+mod analysis;
+mod bundle;
+mod presets;
+mod settings;
 mod upload_function;
+mod workspace;
 
+use analysis::{
+    compute_fibonacci, delete_fib_level_set, detect_structure, list_fib_level_sets,
+    save_fib_level_set, scan_patterns,
+};
+use bundle::{export_settings_bundle, import_settings_bundle};
+use presets::{delete_preset, list_presets, save_preset};
+use settings::{get_settings, reset_settings, update_settings, SettingsService};
 use tauri_plugin_sql::{Migration, MigrationKind};
 use upload_function::{
-    delete_symbol, fetch_github_data_command, fetch_yfinance_data_command, get_available_symbols,
-    get_symbol_data, process_chart_folder, rename_symbol,
+    analyze_gaps, check_yfinance_symbol, compute_volume_profile, delete_import_profile,
+    delete_symbol, end_blind_replay, fetch_binance_data_command, fetch_dukascopy_data_command,
+    fetch_github_data_command, fetch_github_release_asset_command, fetch_yfinance_data_command,
+    get_available_symbols, get_candles, get_default_sessions, get_sessions, get_symbol_data,
+    get_symbol_provenance, get_tick_summary, import_tick_csv, list_github_releases,
+    list_import_profiles, merge_symbols, preview_chart_folder, preview_column_mapping,
+    process_chart_folder, record_blind_trade, rename_symbol, sample_csv_file, save_import_profile,
+    search_yfinance_symbols, start_blind_replay, start_tick_replay, step_blind_replay,
+    step_tick_replay, stop_tick_replay, validate_chart_folder, BlindReplays, TickReplays,
 };
+use workspace::{workspace_list, workspace_open, workspace_save};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    let migrations = vec![Migration {
-        version: 1,
-        description: "create_settings_table",
-        sql: "CREATE TABLE IF NOT EXISTS settings (
+    let migrations = vec![
+        Migration {
+            version: 1,
+            description: "create_settings_table",
+            sql: "CREATE TABLE IF NOT EXISTS settings (
                 key TEXT PRIMARY KEY,
                 value TEXT NOT NULL,
                 updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
             )",
-        kind: MigrationKind::Up,
-    }];
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "create_import_profiles_table",
+            sql: "CREATE TABLE IF NOT EXISTS import_profiles (
+                name TEXT PRIMARY KEY,
+                mapping TEXT NOT NULL,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "create_fib_level_sets_table",
+            sql: "CREATE TABLE IF NOT EXISTS fib_level_sets (
+                name TEXT PRIMARY KEY,
+                levels TEXT NOT NULL,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+            )",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "create_presets_table",
+            sql: "CREATE TABLE IF NOT EXISTS presets (
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+                PRIMARY KEY (kind, name)
+            )",
+            kind: MigrationKind::Up,
+        },
+    ];
 
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
+        .manage(TickReplays::default())
+        .manage(BlindReplays::default())
+        .manage(SettingsService::default())
         .plugin(
             tauri_plugin_sql::Builder::default()
                 .add_migrations("sqlite:settings.db", migrations)
@@ -28,15 +85,69 @@ pub fn run() {
         )
         .invoke_handler(tauri::generate_handler![
             // Local folder commands
+            preview_chart_folder,
+            validate_chart_folder,
             process_chart_folder,
             get_available_symbols,
             get_symbol_data,
+            get_candles,
+            analyze_gaps,
+            compute_volume_profile,
+            get_sessions,
+            get_default_sessions,
+            detect_structure,
+            scan_patterns,
+            compute_fibonacci,
+            list_fib_level_sets,
+            save_fib_level_set,
+            delete_fib_level_set,
             delete_symbol,
             rename_symbol,
+            merge_symbols,
+            get_symbol_provenance,
+            // Column mapping / import profile commands
+            sample_csv_file,
+            preview_column_mapping,
+            list_import_profiles,
+            save_import_profile,
+            delete_import_profile,
             // yFinance commands
             fetch_yfinance_data_command,
+            search_yfinance_symbols,
+            check_yfinance_symbol,
             // GitHub commands
             fetch_github_data_command,
+            list_github_releases,
+            fetch_github_release_asset_command,
+            // Binance commands
+            fetch_binance_data_command,
+            // Dukascopy commands
+            fetch_dukascopy_data_command,
+            // Tick commands
+            import_tick_csv,
+            get_tick_summary,
+            start_tick_replay,
+            step_tick_replay,
+            stop_tick_replay,
+            // Workspace commands
+            workspace_save,
+            workspace_open,
+            workspace_list,
+            // Blind replay commands
+            start_blind_replay,
+            step_blind_replay,
+            record_blind_trade,
+            end_blind_replay,
+            // Settings commands
+            get_settings,
+            update_settings,
+            reset_settings,
+            // Preset and settings bundle commands
+            list_presets,
+            save_preset,
+            delete_preset,
+            export_settings_bundle,
+            import_settings_bundle,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
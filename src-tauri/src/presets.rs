@@ -0,0 +1,306 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::upload_function::column_mapping::open_settings_db;
+use crate::workspace::WorkspaceIndicator;
+
+// Shortcuts the app ships with, user bindings can only rebind these
+const SHORTCUTS_JSON: &str = include_str!("../../src/shortcuts.json");
+
+#[derive(Deserialize)]
+struct ShippedShortcuts {
+    shortcuts: Vec<ShippedShortcut>,
+}
+
+#[derive(Deserialize)]
+struct ShippedShortcut {
+    id: String,
+}
+
+fn shortcut_ids() -> Vec<String> {
+    serde_json::from_str::<ShippedShortcuts>(SHORTCUTS_JSON)
+        .map(|shipped| shipped.shortcuts.into_iter().map(|s| s.id).collect())
+        .unwrap_or_default()
+}
+
+// Keys for one of the shipped shortcuts, an empty list unbinds it
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShortcutBinding {
+    pub id: String,
+    pub keys: Vec<String>, // e.g. "ctrl+shift+s"
+}
+
+impl ShortcutBinding {
+    pub fn validate(&mut self) -> Result<(), String> {
+        if !shortcut_ids().contains(&self.id) {
+            return Err(format!("Unknown shortcut '{}'", self.id));
+        }
+
+        for key in self.keys.iter_mut() {
+            *key = key.trim().to_lowercase();
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(format!("Invalid keys '{}' for shortcut '{}'", key, self.id));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Indicators added to a chart together, named the way IndicatorModal names them
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IndicatorTemplate {
+    pub name: String,
+    pub indicators: Vec<WorkspaceIndicator>,
+}
+
+impl IndicatorTemplate {
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err("Template name cannot be empty".to_string());
+        }
+        if self.indicators.is_empty() {
+            return Err(format!("Template '{}' has no indicators", self.name));
+        }
+        if self.indicators.iter().any(|i| i.name.trim().is_empty()) {
+            return Err(format!("Template '{}' has an unnamed indicator", self.name));
+        }
+
+        Ok(())
+    }
+}
+
+// Default look of a drawing tool, `style` is owned by the frontend like a workspace drawing's
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DrawingStyle {
+    pub name: String,
+    pub shape: String, // ShapeModal name, e.g. "Trend Line"
+    #[serde(default)]
+    pub style: serde_json::Value,
+}
+
+impl DrawingStyle {
+    pub fn validate(&mut self) -> Result<(), String> {
+        self.name = self.name.trim().to_string();
+        if self.name.is_empty() {
+            return Err("Drawing style name cannot be empty".to_string());
+        }
+        if self.shape.trim().is_empty() {
+            return Err(format!("Drawing style '{}' has no shape", self.name));
+        }
+        if !(self.style.is_object() || self.style.is_null()) {
+            return Err(format!(
+                "Drawing style '{}' must be an object of style properties",
+                self.name
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PresetKind {
+    Shortcut,
+    IndicatorTemplate,
+    DrawingStyle,
+}
+
+impl PresetKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            PresetKind::Shortcut => "shortcut",
+            PresetKind::IndicatorTemplate => "indicator_template",
+            PresetKind::DrawingStyle => "drawing_style",
+        }
+    }
+}
+
+// Small named pieces of user setup that live next to the settings
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Preset {
+    Shortcut(ShortcutBinding),
+    IndicatorTemplate(IndicatorTemplate),
+    DrawingStyle(DrawingStyle),
+}
+
+impl Preset {
+    pub fn kind(&self) -> PresetKind {
+        match self {
+            Preset::Shortcut(_) => PresetKind::Shortcut,
+            Preset::IndicatorTemplate(_) => PresetKind::IndicatorTemplate,
+            Preset::DrawingStyle(_) => PresetKind::DrawingStyle,
+        }
+    }
+
+    // Shortcuts are keyed by their id
+    pub fn name(&self) -> &str {
+        match self {
+            Preset::Shortcut(binding) => &binding.id,
+            Preset::IndicatorTemplate(template) => &template.name,
+            Preset::DrawingStyle(style) => &style.name,
+        }
+    }
+
+    pub fn validate(&mut self) -> Result<(), String> {
+        match self {
+            Preset::Shortcut(binding) => binding.validate(),
+            Preset::IndicatorTemplate(template) => template.validate(),
+            Preset::DrawingStyle(style) => style.validate(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PresetResult {
+    pub success: bool,
+    pub message: String,
+    pub name: String,
+}
+
+// Same schema as the v4 migration, in case the frontend has not loaded the db yet
+pub fn create_presets_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS presets (
+            kind TEXT NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            PRIMARY KEY (kind, name)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create presets table: {}", e))?;
+
+    Ok(())
+}
+
+fn open_presets_db(app_handle: &AppHandle) -> Result<Connection, String> {
+    let conn = open_settings_db(app_handle)?;
+    create_presets_table(&conn)?;
+
+    Ok(conn)
+}
+
+fn store_preset(conn: &Connection, preset: &Preset) -> Result<(), String> {
+    let json =
+        serde_json::to_string(preset).map_err(|e| format!("Failed to serialize preset: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO presets (kind, name, value, updated_at)
+         VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+         ON CONFLICT(kind, name) DO UPDATE SET
+         value = ?3,
+         updated_at = CURRENT_TIMESTAMP",
+        params![preset.kind().as_str(), preset.name(), json],
+    )
+    .map_err(|e| format!("Failed to save preset '{}': {}", preset.name(), e))?;
+
+    Ok(())
+}
+
+// Saved presets of one kind, or all of them, ordered by name
+pub fn read_presets(conn: &Connection, kind: Option<PresetKind>) -> Result<Vec<Preset>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, value FROM presets
+             WHERE ?1 IS NULL OR kind = ?1
+             ORDER BY kind, name",
+        )
+        .map_err(|e| format!("Failed to read presets: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![kind.map(PresetKind::as_str)], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("Failed to read presets: {}", e))?;
+
+    let mut presets = Vec::new();
+
+    for row in rows {
+        let (name, value) = row.map_err(|e| format!("Failed to read preset: {}", e))?;
+
+        match serde_json::from_str::<Preset>(&value) {
+            Ok(preset) => presets.push(preset),
+            Err(e) => println!("✗ Skipping preset '{}': {}", name, e),
+        }
+    }
+
+    Ok(presets)
+}
+
+// Save validated presets of one kind, first dropping every other preset of that kind when
+// `replace` is set. Callers run it inside a transaction.
+pub fn write_presets(
+    conn: &Connection,
+    kind: PresetKind,
+    presets: &[Preset],
+    replace: bool,
+) -> Result<(), String> {
+    if replace {
+        conn.execute(
+            "DELETE FROM presets WHERE kind = ?1",
+            params![kind.as_str()],
+        )
+        .map_err(|e| format!("Failed to clear presets: {}", e))?;
+    }
+    for preset in presets.iter().filter(|p| p.kind() == kind) {
+        store_preset(conn, preset)?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_presets(
+    app_handle: AppHandle,
+    kind: Option<PresetKind>,
+) -> Result<Vec<Preset>, String> {
+    read_presets(&open_presets_db(&app_handle)?, kind)
+}
+
+#[tauri::command]
+pub async fn save_preset(
+    app_handle: AppHandle,
+    mut preset: Preset,
+) -> Result<PresetResult, String> {
+    preset.validate()?;
+    store_preset(&open_presets_db(&app_handle)?, &preset)?;
+
+    Ok(PresetResult {
+        success: true,
+        message: format!("Preset '{}' saved", preset.name()),
+        name: preset.name().to_string(),
+    })
+}
+
+// Deleting a shortcut binding restores the shipped keys
+#[tauri::command]
+pub async fn delete_preset(
+    app_handle: AppHandle,
+    kind: PresetKind,
+    name: String,
+) -> Result<PresetResult, String> {
+    let conn = open_presets_db(&app_handle)?;
+
+    let deleted = conn
+        .execute(
+            "DELETE FROM presets WHERE kind = ?1 AND name = ?2",
+            params![kind.as_str(), name],
+        )
+        .map_err(|e| format!("Failed to delete preset: {}", e))?;
+
+    if deleted == 0 {
+        return Err(format!("Preset '{}' not found", name));
+    }
+
+    Ok(PresetResult {
+        success: true,
+        message: format!("Preset '{}' deleted", name),
+        name,
+    })
+}
@@ -0,0 +1,655 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::upload_function::bars::{BarType, DEFAULT_ATR_PERIOD};
+use crate::upload_function::column_mapping::open_settings_db;
+use crate::upload_function::timezone::SourceTimezone;
+
+// Bumped with every entry added to MIGRATIONS
+pub const SETTINGS_VERSION: u32 = 2;
+
+pub const SETTINGS_CHANGED_EVENT: &str = "settings://changed";
+
+const VERSION_KEY: &str = "schema_version";
+
+// Mirrors the frontend's AppSettings. Missing fields take their defaults, the same way the
+// settings store merges saved values over its defaults.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct GeneralSettings {
+    pub auto_save: bool,
+    pub show_grid: bool,
+    pub default_chart_type: String,
+    pub display_timezone: String,
+    pub renko_box_size: f64, // 0 sizes boxes from the ATR
+    pub renko_atr_period: usize,
+    pub range_bar_size: f64,
+    pub tick_bar_count: usize,
+    pub volume_bar_size: f64,
+}
+
+impl Default for GeneralSettings {
+    fn default() -> Self {
+        GeneralSettings {
+            auto_save: true,
+            show_grid: true,
+            default_chart_type: "candlesticks".to_string(),
+            display_timezone: "UTC".to_string(),
+            renko_box_size: 0.0,
+            renko_atr_period: DEFAULT_ATR_PERIOD,
+            range_bar_size: 0.001,
+            tick_bar_count: 100,
+            volume_bar_size: 1000.0,
+        }
+    }
+}
+
+const CHART_TYPES: [&str; 8] = [
+    "candlesticks",
+    "bar",
+    "line",
+    "heikin_ashi",
+    "renko",
+    "range",
+    "tick",
+    "volume",
+];
+
+impl GeneralSettings {
+    fn validate(&self) -> Result<(), String> {
+        if !CHART_TYPES.contains(&self.default_chart_type.as_str()) {
+            return Err(format!("Unknown chart type '{}'", self.default_chart_type));
+        }
+        SourceTimezone::parse(&self.display_timezone)?;
+
+        if self.renko_box_size < 0.0 {
+            return Err("Renko box size can't be negative".to_string());
+        }
+        if self.renko_atr_period == 0 {
+            return Err("Renko ATR period must be at least 1".to_string());
+        }
+        if self.range_bar_size <= 0.0 {
+            return Err("Range bar size must be positive".to_string());
+        }
+        if self.tick_bar_count == 0 {
+            return Err("Tick bars need at least 1 tick".to_string());
+        }
+        if self.volume_bar_size <= 0.0 {
+            return Err("Volume bar size must be positive".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Bars the default chart type is built from, same as barTypeFor in the frontend
+    pub fn bar_type(&self) -> Option<BarType> {
+        match self.default_chart_type.as_str() {
+            "heikin_ashi" => Some(BarType::HeikinAshi),
+            "renko" if self.renko_box_size > 0.0 => Some(BarType::Renko {
+                box_size: Some(self.renko_box_size),
+                atr_period: None,
+            }),
+            "renko" => Some(BarType::Renko {
+                box_size: None,
+                atr_period: Some(self.renko_atr_period),
+            }),
+            "range" => Some(BarType::Range {
+                size: self.range_bar_size,
+            }),
+            "tick" => Some(BarType::Tick {
+                count: self.tick_bar_count,
+            }),
+            "volume" => Some(BarType::Volume {
+                volume: self.volume_bar_size,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TradeSettings {
+    pub bid_distance: f64,
+    pub ask_distance: f64,
+    pub spread: f64,
+    pub slippage: f64,
+    pub lot_size: f64,
+    pub max_orders: u32,
+    pub stop_loss: f64,
+    pub take_profit: f64,
+}
+
+impl Default for TradeSettings {
+    fn default() -> Self {
+        TradeSettings {
+            bid_distance: 5.0,
+            ask_distance: 5.0,
+            spread: 2.0,
+            slippage: 1.0,
+            lot_size: 0.01,
+            max_orders: 10,
+            stop_loss: 50.0,
+            take_profit: 100.0,
+        }
+    }
+}
+
+impl TradeSettings {
+    fn validate(&self) -> Result<(), String> {
+        if !self.lot_size.is_finite() || self.lot_size <= 0.0 {
+            return Err("Lot size must be greater than 0".to_string());
+        }
+        if self.max_orders < 1 {
+            return Err("Max orders must be at least 1".to_string());
+        }
+
+        for (name, value) in [
+            ("Bid distance", self.bid_distance),
+            ("Ask distance", self.ask_distance),
+            ("Spread", self.spread),
+            ("Slippage", self.slippage),
+            ("Stop loss", self.stop_loss),
+            ("Take profit", self.take_profit),
+        ] {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{} can't be negative", name));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Dark,
+    Light,
+    Auto,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FontSize {
+    Small,
+    Medium,
+    Large,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AppearanceSettings {
+    pub theme: Theme,
+    pub font_size: FontSize,
+    pub accent_color: String, // "#rrggbb"
+}
+
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        AppearanceSettings {
+            theme: Theme::Dark,
+            font_size: FontSize::Medium,
+            accent_color: "#3b82f6".to_string(),
+        }
+    }
+}
+
+impl AppearanceSettings {
+    fn validate(&self) -> Result<(), String> {
+        let color = self.accent_color.strip_prefix('#').unwrap_or("");
+        if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!(
+                "Accent color '{}' must look like #rrggbb",
+                self.accent_color
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    pub general: GeneralSettings,
+    pub trade: TradeSettings,
+    pub appearance: AppearanceSettings,
+}
+
+impl AppSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.general.validate()?;
+        self.trade.validate()?;
+        self.appearance.validate()
+    }
+}
+
+// Setting sections as stored, one JSON row per section in the `settings` table
+type Sections = Map<String, Value>;
+
+// Version 1 rows were written by the frontend unchecked. Fields that don't parse or fail
+// validation fall back to their defaults, everything else is kept.
+fn repair_v1(sections: &mut Sections) {
+    fn repair<T: Serialize + for<'de> Deserialize<'de> + Default>(
+        value: &mut Value,
+        validate: impl Fn(&T) -> Result<(), String>,
+    ) {
+        let Value::Object(fields) = value else {
+            *value = serde_json::to_value(T::default()).unwrap_or_default();
+            return;
+        };
+
+        let defaults = match serde_json::to_value(T::default()) {
+            Ok(Value::Object(defaults)) => defaults,
+            _ => return,
+        };
+
+        // Try each field on its own over the defaults, drop the ones that break the section
+        for (key, default) in &defaults {
+            let Some(current) = fields.get(key).cloned() else {
+                continue;
+            };
+            let mut candidate = defaults.clone();
+            candidate.insert(key.clone(), current);
+
+            let ok = serde_json::from_value::<T>(Value::Object(candidate))
+                .is_ok_and(|parsed| validate(&parsed).is_ok());
+            if !ok {
+                println!("✗ Invalid setting {}, reset to its default", key);
+                fields.insert(key.clone(), default.clone());
+            }
+        }
+    }
+
+    for (name, value) in sections.iter_mut() {
+        match name.as_str() {
+            "general" => repair::<GeneralSettings>(value, |s| s.validate()),
+            "trade" => repair::<TradeSettings>(value, |s| s.validate()),
+            "appearance" => repair::<AppearanceSettings>(value, |s| s.validate()),
+            _ => {}
+        }
+    }
+}
+
+type Migration = (u32, fn(&mut Sections));
+
+// (version it produces, migration), applied in order to anything older
+const MIGRATIONS: [Migration; 1] = [(2, repair_v1)];
+
+fn read_sections(conn: &Connection) -> Result<(u32, Sections), String> {
+    // Created by the frontend's plugin too, the same schema as its v1 migration
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create settings table: {}", e))?;
+
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM settings")
+        .map_err(|e| format!("Failed to read settings: {}", e))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| format!("Failed to read settings: {}", e))?;
+
+    let mut version = 1;
+    let mut sections = Sections::new();
+
+    for row in rows {
+        let (key, value) = row.map_err(|e| format!("Failed to read setting: {}", e))?;
+
+        match serde_json::from_str::<Value>(&value) {
+            Ok(value) if key == VERSION_KEY => {
+                version = value.as_u64().unwrap_or(1) as u32;
+            }
+            Ok(value) => {
+                sections.insert(key, value);
+            }
+            Err(e) => println!("✗ Skipping setting '{}': {}", key, e),
+        }
+    }
+
+    Ok((version, sections))
+}
+
+fn write_section(conn: &Connection, key: &str, value: &Value) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO settings (key, value, updated_at)
+         VALUES (?1, ?2, CURRENT_TIMESTAMP)
+         ON CONFLICT(key) DO UPDATE SET
+         value = ?2,
+         updated_at = CURRENT_TIMESTAMP",
+        params![key, value.to_string()],
+    )
+    .map_err(|e| format!("Failed to save setting '{}': {}", key, e))?;
+
+    Ok(())
+}
+
+fn save_settings(conn: &Connection, settings: &AppSettings) -> Result<(), String> {
+    let value = serde_json::to_value(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    if let Value::Object(sections) = value {
+        for (key, section) in &sections {
+            write_section(conn, key, section)?;
+        }
+    }
+    write_section(conn, VERSION_KEY, &Value::from(SETTINGS_VERSION))
+}
+
+// Read the stored settings, migrating and writing them back when they are older
+fn load_settings(conn: &Connection) -> Result<AppSettings, String> {
+    let (version, mut sections) = read_sections(conn)?;
+
+    if version > SETTINGS_VERSION {
+        return Err(format!(
+            "Settings were saved by a newer version (schema {})",
+            version
+        ));
+    }
+
+    for (target, migrate) in MIGRATIONS {
+        if version < target {
+            migrate(&mut sections);
+        }
+    }
+
+    let settings: AppSettings = serde_json::from_value(Value::Object(sections))
+        .map_err(|e| format!("Failed to parse settings: {}", e))?;
+    settings.validate()?;
+
+    if version < SETTINGS_VERSION {
+        save_settings(conn, &settings)?;
+        println!(
+            "✓ Migrated settings from schema {} to {}",
+            version, SETTINGS_VERSION
+        );
+    }
+
+    Ok(settings)
+}
+
+// Settings as the backend sees them, loaded on first use
+#[derive(Default)]
+pub struct SettingsService {
+    settings: Mutex<Option<AppSettings>>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct SettingsChanged {
+    pub section: Option<String>, // None when everything changed (reset, import)
+    pub settings: AppSettings,
+}
+
+// Apply the fields of `patch` to one section one at a time, keeping the valid ones and
+// describing the rest as "section.key: problem". Returns how many fields were applied.
+pub fn merge_section(
+    settings: &mut AppSettings,
+    section: &str,
+    patch: &Map<String, Value>,
+) -> (usize, Vec<String>) {
+    let Ok(current) = serde_json::to_value(&*settings) else {
+        return (
+            0,
+            vec![format!("{}: failed to serialize settings", section)],
+        );
+    };
+    if !current.get(section).is_some_and(Value::is_object) {
+        return (0, vec![format!("{}: unknown settings section", section)]);
+    }
+
+    let mut applied = 0;
+    let mut issues = Vec::new();
+
+    for (key, field) in patch {
+        let Ok(mut value) = serde_json::to_value(&*settings) else {
+            break;
+        };
+        let Some(Value::Object(fields)) = value.get_mut(section) else {
+            break;
+        };
+        if !fields.contains_key(key) {
+            issues.push(format!("{}.{}: unknown setting", section, key));
+            continue;
+        }
+        fields.insert(key.clone(), field.clone());
+
+        match serde_json::from_value::<AppSettings>(value) {
+            Ok(candidate) => match candidate.validate() {
+                Ok(()) => {
+                    *settings = candidate;
+                    applied += 1;
+                }
+                Err(e) => issues.push(format!("{}.{}: {}", section, key, e)),
+            },
+            Err(e) => issues.push(format!("{}.{}: {}", section, key, e)),
+        }
+    }
+
+    (applied, issues)
+}
+
+// Held for a whole read-modify-write, so concurrent updates can't drop each other's changes
+fn lock_settings(app_handle: &AppHandle) -> Result<MutexGuard<'_, Option<AppSettings>>, String> {
+    app_handle
+        .state::<SettingsService>()
+        .inner()
+        .settings
+        .lock()
+        .map_err(|_| "Settings are unavailable".to_string())
+}
+
+fn loaded_settings(
+    app_handle: &AppHandle,
+    settings: &mut Option<AppSettings>,
+) -> Result<AppSettings, String> {
+    if let Some(settings) = settings.as_ref() {
+        return Ok(settings.clone());
+    }
+
+    let loaded = load_settings(&open_settings_db(app_handle)?)?;
+    *settings = Some(loaded.clone());
+
+    Ok(loaded)
+}
+
+fn store_settings(
+    app_handle: &AppHandle,
+    settings: &mut Option<AppSettings>,
+    new_settings: AppSettings,
+    section: Option<String>,
+) -> Result<AppSettings, String> {
+    new_settings.validate()?;
+    save_settings(&open_settings_db(app_handle)?, &new_settings)?;
+    *settings = Some(new_settings.clone());
+
+    app_handle
+        .emit(
+            SETTINGS_CHANGED_EVENT,
+            SettingsChanged {
+                section,
+                settings: new_settings.clone(),
+            },
+        )
+        .map_err(|e| format!("Failed to announce settings change: {}", e))?;
+
+    Ok(new_settings)
+}
+
+// The current settings, the source of truth for the replay and trading code
+pub fn current_settings(app_handle: &AppHandle) -> Result<AppSettings, String> {
+    let mut settings = lock_settings(app_handle)?;
+    loaded_settings(app_handle, &mut settings)
+}
+
+// Validate, store and announce new settings
+pub fn replace_settings(
+    app_handle: &AppHandle,
+    new_settings: AppSettings,
+    section: Option<String>,
+) -> Result<AppSettings, String> {
+    let mut settings = lock_settings(app_handle)?;
+    store_settings(app_handle, &mut settings, new_settings, section)
+}
+
+#[tauri::command]
+pub async fn get_settings(app_handle: AppHandle) -> Result<AppSettings, String> {
+    current_settings(&app_handle)
+}
+
+// Merge `patch` into one section ("general", "trade" or "appearance")
+#[tauri::command]
+pub async fn update_settings(
+    app_handle: AppHandle,
+    section: String,
+    patch: Map<String, Value>,
+) -> Result<AppSettings, String> {
+    let mut guard = lock_settings(&app_handle)?;
+    let settings = loaded_settings(&app_handle, &mut guard)?;
+
+    let mut value = serde_json::to_value(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    let Some(Value::Object(fields)) = value.get_mut(&section) else {
+        return Err(format!("Unknown settings section '{}'", section));
+    };
+
+    for (key, field) in patch {
+        if !fields.contains_key(&key) {
+            return Err(format!("Unknown setting '{}.{}'", section, key));
+        }
+        fields.insert(key, field);
+    }
+
+    let updated: AppSettings = serde_json::from_value(value)
+        .map_err(|e| format!("Invalid {} settings: {}", section, e))?;
+
+    store_settings(&app_handle, &mut guard, updated, Some(section))
+}
+
+#[tauri::command]
+pub async fn reset_settings(app_handle: AppHandle) -> Result<AppSettings, String> {
+    replace_settings(&app_handle, AppSettings::default(), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn db_with(rows: &[(&str, Value)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        read_sections(&conn).unwrap();
+        for (key, value) in rows {
+            write_section(&conn, key, value).unwrap();
+        }
+        conn
+    }
+
+    #[test]
+    fn v1_settings_are_repaired_field_by_field() {
+        let conn = db_with(&[
+            (
+                "general",
+                json!({"defaultChartType": "bogus", "showGrid": false}),
+            ),
+            (
+                "trade",
+                json!({"lotSize": -1, "maxOrders": 0, "spread": 3.5}),
+            ),
+            (
+                "appearance",
+                json!({"accentColor": "blue", "theme": "light"}),
+            ),
+        ]);
+
+        let settings = load_settings(&conn).unwrap();
+        let defaults = AppSettings::default();
+
+        // Invalid fields fall back to their defaults, valid ones are kept
+        assert_eq!(
+            settings.general.default_chart_type,
+            defaults.general.default_chart_type
+        );
+        assert!(!settings.general.show_grid);
+        assert_eq!(settings.trade.lot_size, defaults.trade.lot_size);
+        assert_eq!(settings.trade.max_orders, defaults.trade.max_orders);
+        assert_eq!(settings.trade.spread, 3.5);
+        assert_eq!(
+            settings.appearance.accent_color,
+            defaults.appearance.accent_color
+        );
+        assert_eq!(settings.appearance.theme, Theme::Light);
+
+        // The repaired settings are written back at the current schema
+        let (version, sections) = read_sections(&conn).unwrap();
+        assert_eq!(version, SETTINGS_VERSION);
+        assert_eq!(sections["trade"]["lotSize"], json!(0.01));
+        assert_eq!(load_settings(&conn).unwrap(), settings);
+    }
+
+    #[test]
+    fn newer_schemas_are_refused() {
+        let conn = db_with(&[
+            (VERSION_KEY, Value::from(SETTINGS_VERSION + 1)),
+            ("trade", json!({"lotSize": 1.0})),
+        ]);
+
+        let error = load_settings(&conn).unwrap_err();
+        assert!(error.contains("newer version"));
+
+        // Nothing is rewritten
+        let (version, _) = read_sections(&conn).unwrap();
+        assert_eq!(version, SETTINGS_VERSION + 1);
+    }
+
+    #[test]
+    fn lot_size_and_max_orders_are_validated() {
+        for (lot_size, max_orders) in [(0.0, 1), (-0.5, 1), (f64::NAN, 1), (0.01, 0)] {
+            let trade = TradeSettings {
+                lot_size,
+                max_orders,
+                ..TradeSettings::default()
+            };
+            assert!(trade.validate().is_err(), "{} {}", lot_size, max_orders);
+        }
+        let smallest = TradeSettings {
+            lot_size: 0.001,
+            max_orders: 1,
+            ..TradeSettings::default()
+        };
+        assert!(smallest.validate().is_ok());
+
+        // Current-schema rows are not repaired, so bad values fail the load
+        let conn = db_with(&[
+            (VERSION_KEY, Value::from(SETTINGS_VERSION)),
+            ("trade", json!({"maxOrders": 0})),
+        ]);
+        assert_eq!(
+            load_settings(&conn).unwrap_err(),
+            "Max orders must be at least 1"
+        );
+
+        // Updates keep the valid fields of a patch and report the rest
+        let mut settings = AppSettings::default();
+        let patch = json!({"lotSize": 0, "maxOrders": 5});
+        let (applied, issues) = merge_section(&mut settings, "trade", patch.as_object().unwrap());
+        assert_eq!(applied, 1);
+        assert_eq!(
+            issues,
+            vec!["trade.lotSize: Lot size must be greater than 0"]
+        );
+        assert_eq!(settings.trade.max_orders, 5);
+        assert_eq!(settings.trade.lot_size, 0.01);
+    }
+}
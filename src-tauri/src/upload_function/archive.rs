@@ -0,0 +1,91 @@
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveKind {
+    Zip,
+    TarGz,
+}
+
+impl ArchiveKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let lower = name.to_lowercase();
+        if lower.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if lower.ends_with(".tar.gz") || lower.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+// Timeframe code of an archive entry like "data/EURUSD/M15.csv", if it is a CSV file
+fn csv_timeframe(entry_name: &str) -> Option<String> {
+    let file_name = Path::new(entry_name).file_name()?.to_str()?;
+    let upper = file_name.to_uppercase();
+    upper.strip_suffix(".CSV").map(|stem| stem.to_string())
+}
+
+// Walk the archive entry by entry, without unpacking it to disk, and hand every
+// `<TF>.csv` it contains to `on_csv` as a reader along with its timeframe, so large entries are
+// never held in memory whole. Returns the number of CSVs seen.
+pub fn for_each_csv<F>(path: &Path, kind: ArchiveKind, mut on_csv: F) -> Result<usize, String>
+where
+    F: FnMut(&str, &mut dyn BufRead) -> Result<(), String>,
+{
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    let mut reader = BufReader::new(file);
+    let mut count = 0;
+
+    match kind {
+        ArchiveKind::Zip => loop {
+            let mut entry = match zip::read::read_zipfile_from_stream(&mut reader) {
+                Ok(Some(entry)) => entry,
+                Ok(None) => break,
+                Err(e) => return Err(format!("Failed to read zip entry: {}", e)),
+            };
+
+            if !entry.is_file() {
+                continue;
+            }
+
+            if let Some(timeframe) = csv_timeframe(entry.name()) {
+                let entry_name = entry.name().to_string();
+                on_csv(&timeframe, &mut BufReader::new(&mut entry))
+                    .map_err(|e| format!("{}: {}", entry_name, e))?;
+                count += 1;
+            }
+        },
+        ArchiveKind::TarGz => {
+            let mut archive = tar::Archive::new(GzDecoder::new(reader));
+            let entries = archive
+                .entries()
+                .map_err(|e| format!("Failed to read tar archive: {}", e))?;
+
+            for entry in entries {
+                let mut entry = entry.map_err(|e| format!("Failed to read tar entry: {}", e))?;
+
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+
+                let entry_name = entry
+                    .path()
+                    .map_err(|e| format!("Invalid tar entry path: {}", e))?
+                    .to_string_lossy()
+                    .to_string();
+
+                if let Some(timeframe) = csv_timeframe(&entry_name) {
+                    on_csv(&timeframe, &mut BufReader::new(&mut entry))
+                        .map_err(|e| format!("{}: {}", entry_name, e))?;
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    Ok(count)
+}
@@ -0,0 +1,551 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use super::candles::Candle;
+use super::ticks::{for_each_tick, CandleBuilder, TickRecord};
+
+// Bars derived from time candles or from price movement. Everything except Heikin Ashi is
+// built from stored ticks, or from M1 candles when the symbol has no ticks.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BarType {
+    HeikinAshi,
+    Renko {
+        #[serde(default)]
+        box_size: Option<f64>, // Fixed box in price units
+        #[serde(default)]
+        atr_period: Option<usize>, // Box from the timeframe's ATR when no fixed size is given
+    },
+    Range {
+        size: f64, // High - low of every bar, in price units
+    },
+    Tick {
+        count: usize,
+    },
+    Volume {
+        volume: f64,
+    },
+}
+
+pub const DEFAULT_ATR_PERIOD: usize = 14;
+
+// Most bars one price can close. A gap wider than this many bars means the size is far too
+// small for the price, the rest of the gap is skipped instead of emitting millions of bars.
+const MAX_BARS_PER_PUSH: usize = 10_000;
+
+impl BarType {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            BarType::HeikinAshi => Ok(()),
+            BarType::Renko {
+                box_size: Some(size),
+                ..
+            } if size <= 0.0 => Err("Renko box size must be positive".to_string()),
+            BarType::Renko {
+                atr_period: Some(0),
+                ..
+            } => Err("ATR period must be at least 1".to_string()),
+            BarType::Renko { .. } => Ok(()),
+            BarType::Range { size } if size <= 0.0 => {
+                Err("Range bar size must be positive".to_string())
+            }
+            BarType::Tick { count: 0 } => Err("Tick bars need at least 1 tick".to_string()),
+            BarType::Volume { volume } if volume <= 0.0 => {
+                Err("Volume bar size must be positive".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Renko without a fixed box needs the chart timeframe's candles for its ATR
+    pub fn uses_atr(&self) -> bool {
+        matches!(self, BarType::Renko { box_size: None, .. })
+    }
+
+    // Tick-count bars can't be faked from candles
+    pub fn needs_ticks(&self) -> bool {
+        matches!(self, BarType::Tick { .. })
+    }
+}
+
+// Average true range of the last `period` candles
+pub fn atr(candles: &[Candle], period: usize) -> Option<f64> {
+    let start = candles.len().checked_sub(period + 1)?;
+    let window = &candles[start..];
+
+    let sum: f64 = window
+        .windows(2)
+        .map(|pair| {
+            let (previous, candle) = (&pair[0], &pair[1]);
+            (candle.high - candle.low)
+                .max((candle.high - previous.close).abs())
+                .max((candle.low - previous.close).abs())
+        })
+        .sum();
+
+    Some(sum / period as f64)
+}
+
+fn heikin_ashi_bar(candle: &Candle, previous: Option<&Candle>) -> Candle {
+    let close = (candle.open + candle.high + candle.low + candle.close) / 4.0;
+    let open = match previous {
+        Some(previous) => (previous.open + previous.close) / 2.0,
+        None => (candle.open + candle.close) / 2.0,
+    };
+
+    Candle {
+        timestamp: candle.timestamp,
+        open,
+        high: candle.high.max(open).max(close),
+        low: candle.low.min(open).min(close),
+        close,
+        volume: candle.volume,
+        adj_close: None,
+    }
+}
+
+pub fn heikin_ashi(candles: &[Candle]) -> Vec<Candle> {
+    let mut bars: Vec<Candle> = Vec::with_capacity(candles.len());
+
+    for candle in candles {
+        let bar = heikin_ashi_bar(candle, bars.last());
+        bars.push(bar);
+    }
+
+    bars
+}
+
+// Price path walked through an M1 candle when there are no ticks: open, the nearer extreme,
+// the other extreme, close, spread over the minute
+pub fn candle_path(candle: &Candle) -> [TickRecord; 4] {
+    let (first, second) = if candle.close >= candle.open {
+        (candle.low, candle.high)
+    } else {
+        (candle.high, candle.low)
+    };
+
+    let prices = [candle.open, first, second, candle.close];
+    let tick = |i: usize| TickRecord {
+        timestamp_ms: candle.timestamp * 1000 + i as i64 * 15_000,
+        bid: prices[i],
+        ask: 0.0,
+        last: 0.0,
+        volume: candle.volume / 4.0,
+        flags: 0,
+    };
+
+    [tick(0), tick(1), tick(2), tick(3)]
+}
+
+#[derive(Debug, Clone, Copy)]
+enum PriceBarKind {
+    Renko { size: f64 },
+    Range { size: f64 },
+    Tick { count: usize },
+    Volume { volume: f64 },
+}
+
+// Bars closed by price movement, tick count or volume instead of the clock
+pub struct PriceBars {
+    kind: PriceBarKind,
+    current: Option<Candle>,
+    ticks: usize,
+    anchor: Option<f64>, // Renko: close of the last brick
+    direction: i8,       // Renko: +1 after an up brick, -1 after a down brick
+    last_time: i64,
+}
+
+impl PriceBars {
+    fn new(kind: PriceBarKind) -> Self {
+        PriceBars {
+            kind,
+            current: None,
+            ticks: 0,
+            anchor: None,
+            direction: 0,
+            last_time: i64::MIN,
+        }
+    }
+
+    // Closed bars keep strictly increasing times, several can close on one tick
+    fn close_bar(&mut self, mut bar: Candle) -> Candle {
+        bar.timestamp = bar.timestamp.max(self.last_time.saturating_add(1));
+        self.last_time = bar.timestamp;
+        bar
+    }
+
+    fn open_bar(timestamp: i64, open: f64) -> Candle {
+        Candle {
+            timestamp,
+            open,
+            high: open,
+            low: open,
+            close: open,
+            volume: 0.0,
+            adj_close: None,
+        }
+    }
+
+    fn push(&mut self, timestamp: i64, price: f64, volume: f64) -> Vec<Candle> {
+        let mut closed = Vec::new();
+
+        match self.kind {
+            PriceBarKind::Renko { size } => {
+                let mut anchor = *self
+                    .anchor
+                    .get_or_insert_with(|| (price / size).floor() * size);
+                let bar = self
+                    .current
+                    .get_or_insert_with(|| Self::open_bar(timestamp, anchor));
+                bar.volume += volume;
+
+                loop {
+                    // A reversal has to clear the previous brick first
+                    let up_open = if self.direction < 0 {
+                        anchor + size
+                    } else {
+                        anchor
+                    };
+                    let down_open = if self.direction > 0 {
+                        anchor - size
+                    } else {
+                        anchor
+                    };
+                    let tolerance = size * 1e-9;
+
+                    let (open, close, direction) = if price >= up_open + size - tolerance {
+                        (up_open, up_open + size, 1)
+                    } else if price <= down_open - size + tolerance {
+                        (down_open, down_open - size, -1)
+                    } else {
+                        break;
+                    };
+
+                    let bar = self
+                        .current
+                        .take()
+                        .unwrap_or_else(|| Self::open_bar(timestamp, open));
+                    closed.push(self.close_bar(Candle {
+                        open,
+                        high: open.max(close),
+                        low: open.min(close),
+                        close,
+                        ..bar
+                    }));
+
+                    anchor = close;
+                    self.direction = direction;
+
+                    if closed.len() >= MAX_BARS_PER_PUSH {
+                        anchor = (price / size).floor() * size;
+                        break;
+                    }
+                }
+                self.anchor = Some(anchor);
+
+                let bar = self
+                    .current
+                    .get_or_insert_with(|| Self::open_bar(timestamp, anchor));
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+            }
+            PriceBarKind::Range { size } => {
+                let mut bar = self
+                    .current
+                    .take()
+                    .unwrap_or_else(|| Self::open_bar(timestamp, price));
+
+                // Jumps larger than the range close several bars at the boundaries
+                loop {
+                    let boundary = if price > bar.low + size {
+                        bar.low + size
+                    } else if price < bar.high - size {
+                        bar.high - size
+                    } else {
+                        break;
+                    };
+
+                    bar.high = bar.high.max(boundary);
+                    bar.low = bar.low.min(boundary);
+                    bar.close = boundary;
+                    closed.push(self.close_bar(bar));
+                    bar = Self::open_bar(timestamp, boundary);
+
+                    if closed.len() >= MAX_BARS_PER_PUSH {
+                        bar = Self::open_bar(timestamp, price);
+                        break;
+                    }
+                }
+
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += volume;
+                self.current = Some(bar);
+            }
+            PriceBarKind::Tick { .. } | PriceBarKind::Volume { .. } => {
+                let bar = self
+                    .current
+                    .get_or_insert_with(|| Self::open_bar(timestamp, price));
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += volume;
+                self.ticks += 1;
+
+                let full = match self.kind {
+                    PriceBarKind::Tick { count } => self.ticks >= count,
+                    PriceBarKind::Volume { volume } => bar.volume >= volume,
+                    _ => false,
+                };
+
+                if let Some(bar) = self.current.take_if(|_| full) {
+                    self.ticks = 0;
+                    closed.push(self.close_bar(bar));
+                }
+            }
+        }
+
+        closed
+    }
+}
+
+// Builds any bar type as prices arrive, used for loading and for replay alike
+pub enum BarBuilder {
+    Time(CandleBuilder),
+    HeikinAshi {
+        raw: CandleBuilder,
+        previous: Option<Candle>,
+    },
+    Price(PriceBars),
+}
+
+impl BarBuilder {
+    // `reference` is the chart timeframe's history, only read for ATR-sized Renko boxes
+    pub fn new(
+        timeframe: &str,
+        bar_type: Option<&BarType>,
+        reference: &[Candle],
+    ) -> Result<Self, String> {
+        let Some(bar_type) = bar_type else {
+            return Ok(BarBuilder::Time(CandleBuilder::new(timeframe)?));
+        };
+
+        bar_type.validate()?;
+
+        let kind = match *bar_type {
+            BarType::HeikinAshi => {
+                return Ok(BarBuilder::HeikinAshi {
+                    raw: CandleBuilder::new(timeframe)?,
+                    previous: None,
+                })
+            }
+            BarType::Renko {
+                box_size: Some(size),
+                ..
+            } => PriceBarKind::Renko { size },
+            BarType::Renko { atr_period, .. } => {
+                let period = atr_period.unwrap_or(DEFAULT_ATR_PERIOD);
+                let size = atr(reference, period)
+                    .filter(|size| *size > 0.0)
+                    .ok_or(format!(
+                        "Not enough {} candles for a {}-period ATR box",
+                        timeframe, period
+                    ))?;
+                PriceBarKind::Renko { size }
+            }
+            BarType::Range { size } => PriceBarKind::Range { size },
+            BarType::Tick { count } => PriceBarKind::Tick { count },
+            BarType::Volume { volume } => PriceBarKind::Volume { volume },
+        };
+
+        Ok(BarBuilder::Price(PriceBars::new(kind)))
+    }
+
+    // Bars closed by this tick, oldest first
+    pub fn push(&mut self, tick: &TickRecord) -> Vec<Candle> {
+        match self {
+            BarBuilder::Time(builder) => builder.push(tick).into_iter().collect(),
+            BarBuilder::HeikinAshi { raw, previous } => {
+                let Some(candle) = raw.push(tick) else {
+                    return Vec::new();
+                };
+                let bar = heikin_ashi_bar(&candle, previous.as_ref());
+                *previous = Some(bar.clone());
+                vec![bar]
+            }
+            BarBuilder::Price(bars) => bars.push(
+                tick.timestamp_ms.div_euclid(1000),
+                tick.price(),
+                tick.volume_or_count(),
+            ),
+        }
+    }
+
+    pub fn current(&self) -> Option<Candle> {
+        match self {
+            BarBuilder::Time(builder) => builder.current().cloned(),
+            BarBuilder::HeikinAshi { raw, previous } => raw
+                .current()
+                .map(|candle| heikin_ashi_bar(candle, previous.as_ref())),
+            BarBuilder::Price(bars) => bars.current.clone(),
+        }
+    }
+}
+
+// Where price-driven bars get their prices from
+pub enum BarSource<'a> {
+    Ticks { app_dir: &'a Path, symbol: &'a str },
+    Minutes(&'a [Candle]),
+}
+
+// Every bar of the series, the still-forming last one included like time candles
+pub fn build_bars(
+    timeframe: &str,
+    bar_type: &BarType,
+    reference: &[Candle],
+    source: BarSource,
+) -> Result<Vec<Candle>, String> {
+    let mut builder = BarBuilder::new(timeframe, Some(bar_type), reference)?;
+    let mut bars = Vec::new();
+
+    match source {
+        BarSource::Ticks { app_dir, symbol } => for_each_tick(app_dir, symbol, None, |tick| {
+            bars.extend(builder.push(tick))
+        })?,
+        BarSource::Minutes(candles) => {
+            if bar_type.needs_ticks() {
+                return Err("Tick bars need stored tick data".to_string());
+            }
+            for candle in candles {
+                for tick in candle_path(candle) {
+                    bars.extend(builder.push(&tick));
+                }
+            }
+        }
+    }
+
+    bars.extend(builder.current());
+
+    Ok(bars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume: 4.0,
+            adj_close: None,
+        }
+    }
+
+    // (open, close) of each bar
+    fn bodies(bars: &[Candle]) -> Vec<(f64, f64)> {
+        bars.iter().map(|bar| (bar.open, bar.close)).collect()
+    }
+
+    #[test]
+    fn renko_reversals_clear_two_bricks() {
+        let mut renko = PriceBars::new(PriceBarKind::Renko { size: 1.0 });
+        assert!(renko.push(0, 10.0, 0.0).is_empty());
+        assert_eq!(bodies(&renko.push(1, 11.0, 0.0)), vec![(10.0, 11.0)]);
+
+        // One brick down is not enough to reverse an up brick
+        assert!(renko.push(2, 10.0, 0.0).is_empty());
+        assert!(renko.push(3, 9.5, 0.0).is_empty());
+
+        // The down brick opens at the bottom of the last up brick
+        assert_eq!(bodies(&renko.push(4, 9.0, 0.0)), vec![(10.0, 9.0)]);
+        // and continuing down only needs one more brick
+        assert_eq!(bodies(&renko.push(5, 8.0, 0.0)), vec![(9.0, 8.0)]);
+    }
+
+    #[test]
+    fn renko_gaps_close_one_brick_per_level() {
+        let mut renko = PriceBars::new(PriceBarKind::Renko { size: 1.0 });
+        renko.push(0, 10.0, 0.0);
+        let bricks = renko.push(60, 13.2, 0.0);
+
+        assert_eq!(
+            bodies(&bricks),
+            vec![(10.0, 11.0), (11.0, 12.0), (12.0, 13.0)]
+        );
+        // The first brick keeps the time it started forming, the others share the gap's
+        // time and are nudged apart to keep it increasing
+        let times: Vec<i64> = bricks.iter().map(|bar| bar.timestamp).collect();
+        assert_eq!(times, vec![0, 60, 61]);
+
+        let forming = renko.current.clone().unwrap();
+        assert_eq!((forming.open, forming.close), (13.0, 13.2));
+    }
+
+    #[test]
+    fn range_bars_close_at_their_boundaries() {
+        let mut range = PriceBars::new(PriceBarKind::Range { size: 1.0 });
+        range.push(0, 10.0, 1.0);
+        range.push(1, 10.6, 1.0);
+        assert!(range.push(2, 10.2, 1.0).is_empty());
+
+        let closed = range.push(3, 11.5, 1.0);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(
+            (
+                closed[0].open,
+                closed[0].high,
+                closed[0].low,
+                closed[0].close
+            ),
+            (10.0, 11.0, 10.0, 11.0)
+        );
+        assert_eq!(closed[0].volume, 3.0);
+
+        let forming = range.current.clone().unwrap();
+        assert_eq!(
+            (forming.open, forming.high, forming.close),
+            (11.0, 11.5, 11.5)
+        );
+
+        // A drop through several ranges closes one bar per boundary
+        let closed = range.push(4, 8.5, 1.0);
+        assert_eq!(bodies(&closed), vec![(11.0, 10.5), (10.5, 9.5)]);
+        assert!(closed.iter().all(|bar| bar.high - bar.low <= 1.0 + 1e-9));
+    }
+
+    #[test]
+    fn candle_path_visits_the_nearer_extreme_first() {
+        let prices = |path: [TickRecord; 4]| path.map(|tick| tick.bid);
+
+        let up = candle(60, 1.0, 3.0, 0.5, 2.0);
+        assert_eq!(prices(candle_path(&up)), [1.0, 0.5, 3.0, 2.0]);
+
+        let down = candle(60, 2.0, 3.0, 0.5, 1.0);
+        assert_eq!(prices(candle_path(&down)), [2.0, 3.0, 0.5, 1.0]);
+
+        let path = candle_path(&up);
+        let times: Vec<i64> = path.iter().map(|tick| tick.timestamp_ms).collect();
+        assert_eq!(times, vec![60_000, 75_000, 90_000, 105_000]);
+        assert!(path.iter().all(|tick| tick.volume == 1.0));
+    }
+
+    #[test]
+    fn huge_gaps_close_a_bounded_number_of_bars() {
+        let mut renko = PriceBars::new(PriceBarKind::Renko { size: 1e-6 });
+        renko.push(0, 1.0, 0.0);
+        assert_eq!(renko.push(1, 2.0, 0.0).len(), MAX_BARS_PER_PUSH);
+        // The rest of the gap is skipped, the next brick continues from the new price
+        assert_eq!(renko.push(2, 2.0 + 1.5e-6, 0.0).len(), 1);
+
+        let mut range = PriceBars::new(PriceBarKind::Range { size: 1e-6 });
+        range.push(0, 1.0, 0.0);
+        assert_eq!(range.push(1, 2.0, 0.0).len(), MAX_BARS_PER_PUSH);
+        assert_eq!(range.current.as_ref().map(|bar| bar.open), Some(2.0));
+    }
+}
@@ -0,0 +1,656 @@
+use reqwest;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use super::local_upload::{SymbolData, SymbolMetadata, TimeframeInfo};
+use super::merge::{finish_import, import_target, MergePolicy, MergeReport};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BinanceRequest {
+    pub symbol: String, // Binance pair, e.g. BTCUSDT
+    pub save_as: String,
+    pub market: String, // "spot" or "futures" (USDⓈ-M)
+    pub start_date: String,
+    pub end_date: String,
+    pub timeframes: Vec<String>,
+    #[serde(default)]
+    pub base_url: Option<String>, // Override for regional mirrors or a local stub
+    #[serde(default)]
+    pub merge: Option<MergePolicy>, // Splice into an existing symbol instead of refusing it
+}
+
+#[derive(Debug, Serialize)]
+pub struct BinanceResult {
+    pub success: bool,
+    pub message: String,
+    pub symbol: String,
+    pub timeframes_downloaded: Vec<String>,
+    pub total_candles: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge: Option<MergeReport>,
+}
+
+#[derive(Debug)]
+struct Kline {
+    open_time: i64, // Milliseconds
+    open: String,
+    high: String,
+    low: String,
+    close: String,
+    volume: String,
+    taker_buy_volume: String,
+}
+
+const SPOT_BASE_URL: &str = "https://api.binance.com";
+const FUTURES_BASE_URL: &str = "https://fapi.binance.com";
+
+// Binance returns at most 1000 bars per request
+const KLINES_PER_REQUEST: usize = 1000;
+
+// Back off once this much of the per-minute request weight is used
+// (spot allows 6000, USDⓈ-M futures 2400)
+const SPOT_WEIGHT_BUDGET: u64 = 5000;
+const FUTURES_WEIGHT_BUDGET: u64 = 2000;
+
+// 429s in a row before giving up, so a broken proxy can't stall an import forever
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+
+// Map Binance intervals to your timeframe format
+fn map_timeframe_to_binance(tf: &str) -> Option<&'static str> {
+    match tf {
+        "M1" => Some("1m"),
+        "M3" => Some("3m"),
+        "M5" => Some("5m"),
+        "M15" => Some("15m"),
+        "M30" => Some("30m"),
+        "H1" => Some("1h"),
+        "H2" => Some("2h"),
+        "H4" => Some("4h"),
+        "D1" => Some("1d"),
+        "W1" => Some("1w"),
+        "MN1" => Some("1M"),
+        _ => None,
+    }
+}
+
+fn convert_timeframe_to_display(timeframe: &str) -> String {
+    match timeframe {
+        "M1" => "1 min".to_string(),
+        "M3" => "3 min".to_string(),
+        "M5" => "5 min".to_string(),
+        "M15" => "15 min".to_string(),
+        "M30" => "30 min".to_string(),
+        "H1" => "1 hour".to_string(),
+        "H2" => "2 hour".to_string(),
+        "H4" => "4 hours".to_string(),
+        "D1" => "1 day".to_string(),
+        "W1" => "1 week".to_string(),
+        "MN1" => "1 month".to_string(),
+        _ => timeframe.to_string(),
+    }
+}
+
+fn klines_endpoint(market: &str, base_url: Option<&str>) -> Result<(String, u64), String> {
+    let (default_base, path, budget) = match market {
+        "spot" => (SPOT_BASE_URL, "/api/v3/klines", SPOT_WEIGHT_BUDGET),
+        "futures" => (FUTURES_BASE_URL, "/fapi/v1/klines", FUTURES_WEIGHT_BUDGET),
+        _ => return Err("Invalid market. Use 'spot' or 'futures'".to_string()),
+    };
+
+    let base = base_url
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or(default_base)
+        .trim_end_matches('/');
+
+    Ok((format!("{}{}", base, path), budget))
+}
+
+fn parse_kline(value: &serde_json::Value) -> Option<Kline> {
+    let field = |idx: usize| value[idx].as_str().map(|s| s.to_string());
+
+    Some(Kline {
+        open_time: value[0].as_i64()?,
+        open: field(1)?,
+        high: field(2)?,
+        low: field(3)?,
+        close: field(4)?,
+        volume: field(5)?,
+        taker_buy_volume: field(9)?,
+    })
+}
+
+// Weight resets every minute, so wait out the rest of it near the limit
+fn weight_wait(used_weight: u64, weight_budget: u64, now: i64) -> Option<u64> {
+    (used_weight >= weight_budget).then(|| 60 - now.rem_euclid(60) as u64 + 1)
+}
+
+async fn fetch_binance_klines(
+    endpoint: &str,
+    weight_budget: u64,
+    symbol: &str,
+    interval: &str,
+    start_ms: i64,
+    end_ms: i64,
+) -> Result<Vec<Kline>, String> {
+    let client = reqwest::Client::new();
+    let mut klines: Vec<Kline> = Vec::new();
+    let mut cursor = start_ms;
+    let mut retries = 0;
+
+    while cursor < end_ms {
+        let response = client
+            .get(endpoint)
+            .query(&[
+                ("symbol", symbol.to_string()),
+                ("interval", interval.to_string()),
+                ("startTime", cursor.to_string()),
+                ("endTime", (end_ms - 1).to_string()),
+                ("limit", KLINES_PER_REQUEST.to_string()),
+            ])
+            .header("User-Agent", "TraderAssist")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch klines: {}", e))?;
+
+        let status = response.status();
+
+        // 429 asks us to slow down, 418 means we ignored that and got banned for a while
+        if status.as_u16() == 429 || status.as_u16() == 418 {
+            let retry_after = response
+                .headers()
+                .get("Retry-After")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(60);
+
+            if status.as_u16() == 418 {
+                return Err(format!(
+                    "Binance temporarily banned this IP, retry in {} seconds",
+                    retry_after
+                ));
+            }
+
+            retries += 1;
+            if retries > MAX_RATE_LIMIT_RETRIES {
+                return Err(format!(
+                    "Still rate limited by Binance after {} retries",
+                    MAX_RATE_LIMIT_RETRIES
+                ));
+            }
+
+            println!("Rate limited by Binance, waiting {}s...", retry_after);
+            tokio::time::sleep(Duration::from_secs(retry_after)).await;
+            continue;
+        }
+        retries = 0;
+
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("API returned error: {} {}", status, body));
+        }
+
+        let used_weight = response
+            .headers()
+            .get("x-mbx-used-weight-1m")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0);
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+        let batch: Vec<Kline> = json
+            .as_array()
+            .ok_or("Invalid response structure")?
+            .iter()
+            .filter_map(parse_kline)
+            .collect();
+
+        let batch_len = batch.len();
+        let Some(last_open) = batch.last().map(|k| k.open_time) else {
+            break;
+        };
+
+        klines.extend(batch);
+
+        // Binance returns bars opening at or after startTime
+        cursor = last_open + 1;
+
+        if batch_len < KLINES_PER_REQUEST {
+            break;
+        }
+
+        if let Some(wait) = weight_wait(used_weight, weight_budget, chrono::Utc::now().timestamp())
+        {
+            println!("Binance weight {} used, waiting {}s...", used_weight, wait);
+            tokio::time::sleep(Duration::from_secs(wait)).await;
+        }
+    }
+
+    if klines.is_empty() {
+        return Err("No valid candle data found".to_string());
+    }
+
+    Ok(klines)
+}
+
+fn save_klines_to_csv(file_path: &Path, klines: &[Kline]) -> Result<(), String> {
+    let mut file = File::create(file_path).map_err(|e| format!("Failed to create file: {}", e))?;
+
+    // Write header
+    writeln!(
+        file,
+        "timestamp,open,high,low,close,volume,taker_buy_volume"
+    )
+    .map_err(|e| format!("Failed to write header: {}", e))?;
+
+    // Write data (timestamps in seconds like the other importers)
+    for k in klines {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{}",
+            k.open_time / 1000,
+            k.open,
+            k.high,
+            k.low,
+            k.close,
+            k.volume,
+            k.taker_buy_volume
+        )
+        .map_err(|e| format!("Failed to write data: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Binance pads prices with zeros ("42000.10000000"), so count significant decimals
+fn infer_price_precision(klines: &[Kline]) -> Option<u32> {
+    klines
+        .iter()
+        .take(500)
+        .map(|k| {
+            k.close
+                .split_once('.')
+                .map(|(_, decimals)| decimals.trim_end_matches('0').len() as u32)
+                .unwrap_or(0)
+        })
+        .max()
+}
+
+fn convert_date_to_millis(date_str: &str) -> Result<i64, String> {
+    use chrono::NaiveDate;
+
+    let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date format: {}", e))?;
+
+    let datetime = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or("Failed to create datetime")?;
+    Ok(datetime.and_utc().timestamp_millis())
+}
+
+#[tauri::command]
+pub async fn fetch_binance_data_command(
+    app_handle: AppHandle,
+    request: BinanceRequest,
+) -> Result<BinanceResult, String> {
+    let save_as = request.save_as.to_uppercase();
+    let pair = request.symbol.trim().to_uppercase();
+
+    // Validate symbol name
+    if save_as.is_empty() {
+        return Err("Symbol name cannot be empty".to_string());
+    }
+
+    if save_as.contains(|c: char| !c.is_alphanumeric() && c != '_') {
+        return Err("Symbol name can only contain letters, numbers, and underscores".to_string());
+    }
+
+    if pair.is_empty() {
+        return Err("Binance symbol cannot be empty".to_string());
+    }
+
+    let (endpoint, weight_budget) = klines_endpoint(&request.market, request.base_url.as_deref())?;
+
+    let start_ms = convert_date_to_millis(&request.start_date)?;
+    let end_ms = convert_date_to_millis(&request.end_date)?;
+
+    if end_ms <= start_ms {
+        return Err("End date must be after start date".to_string());
+    }
+
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    // Existing symbols are only written to through a merge
+    let target = import_target(&app_handle, &save_as, request.merge)?;
+
+    let data_dir = app_dir.join("data").join(&target);
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    let mut timeframe_infos = Vec::new();
+    let mut total_candles = 0;
+    let mut downloaded_timeframes = Vec::new();
+    let mut price_precision = None;
+    let mut last_error = None;
+
+    // Download data for each timeframe
+    for tf in &request.timeframes {
+        let interval =
+            map_timeframe_to_binance(tf).ok_or(format!("Unsupported timeframe: {}", tf))?;
+
+        println!("Fetching {} klines for {}...", tf, pair);
+
+        match fetch_binance_klines(&endpoint, weight_budget, &pair, interval, start_ms, end_ms)
+            .await
+        {
+            Ok(klines) => {
+                let csv_path = data_dir.join(format!("{}.csv", tf));
+                save_klines_to_csv(&csv_path, &klines)?;
+
+                price_precision = price_precision.or_else(|| infer_price_precision(&klines));
+
+                let display_name = convert_timeframe_to_display(tf);
+                let candle_count = klines.len();
+
+                timeframe_infos.push(TimeframeInfo {
+                    name: tf.clone(),
+                    display_name,
+                    candle_count,
+                    file_path: csv_path.to_string_lossy().to_string(),
+                    source_sha: None,
+                    format: None,
+                    coverage: None,
+                    source_timezone: Some("UTC".to_string()), // Epoch timestamps
+                });
+
+                total_candles += candle_count;
+                downloaded_timeframes.push(tf.clone());
+
+                println!("✓ {} - {} candles downloaded", tf, candle_count);
+            }
+            Err(e) => {
+                println!("✗ {} - Failed: {}", tf, e);
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if timeframe_infos.is_empty() {
+        // Clean up data directory
+        let _ = fs::remove_dir_all(&data_dir);
+        return Err(format!(
+            "Failed to download any timeframe data{}",
+            last_error.map(|e| format!(": {}", e)).unwrap_or_default()
+        ));
+    }
+
+    // Sort timeframes
+    timeframe_infos.sort_by(|a, b| {
+        let order = [
+            "M1", "M3", "M5", "M15", "M30", "H1", "H2", "H4", "D1", "W1", "MN1",
+        ];
+        let a_idx = order.iter().position(|&x| x == a.name).unwrap_or(999);
+        let b_idx = order.iter().position(|&x| x == b.name).unwrap_or(999);
+        a_idx.cmp(&b_idx)
+    });
+
+    let (exchange, instrument_type) = if request.market == "futures" {
+        ("Binance USDⓈ-M Futures", "FUTURE")
+    } else {
+        ("Binance", "CRYPTOCURRENCY")
+    };
+
+    let symbol_data = SymbolData {
+        symbol: target.clone(),
+        timeframes: timeframe_infos,
+        total_candles,
+        uploaded_at: chrono::Utc::now().to_rfc3339(),
+        metadata: Some(SymbolMetadata {
+            source: "binance".to_string(),
+            exchange: Some(exchange.to_string()),
+            exchange_timezone: Some("UTC".to_string()),
+            gmt_offset: Some(0),
+            currency: None,
+            instrument_type: Some(instrument_type.to_string()),
+            price_precision,
+            regular_session: None, // Trades around the clock
+        }),
+        ticks: None,
+    };
+
+    save_symbol_data(&app_handle, &symbol_data)?;
+
+    let merge = finish_import(&app_handle, &save_as, &target, request.merge, "binance")?;
+
+    Ok(BinanceResult {
+        success: true,
+        message: format!(
+            "Successfully downloaded {} timeframe{} with {} total candles",
+            downloaded_timeframes.len(),
+            if downloaded_timeframes.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            total_candles
+        ),
+        symbol: save_as,
+        timeframes_downloaded: downloaded_timeframes,
+        total_candles,
+        merge,
+    })
+}
+
+fn save_symbol_data(app_handle: &AppHandle, symbol_data: &SymbolData) -> Result<(), String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let symbols_dir = app_dir.join("symbols");
+    fs::create_dir_all(&symbols_dir)
+        .map_err(|e| format!("Failed to create symbols directory: {}", e))?;
+
+    let json_path = symbols_dir.join(format!("{}.json", symbol_data.symbol));
+    let json_content = serde_json::to_string_pretty(symbol_data)
+        .map_err(|e| format!("Failed to serialize symbol data: {}", e))?;
+
+    let mut file =
+        File::create(json_path).map_err(|e| format!("Failed to create JSON file: {}", e))?;
+
+    file.write_all(json_content.as_bytes())
+        .map_err(|e| format!("Failed to write JSON file: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_server::{query_param, serve, Response};
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    const MINUTE_MS: i64 = 60_000;
+
+    // One-minute klines from startTime up to endTime, at most `limit` of them
+    fn klines_json(target: &str) -> String {
+        let param = |name: &str| query_param(target, name).unwrap().parse::<i64>().unwrap();
+        let (start, end, limit) = (param("startTime"), param("endTime"), param("limit"));
+
+        // Bars open on whole minutes at or after startTime
+        let first = (start + MINUTE_MS - 1).div_euclid(MINUTE_MS) * MINUTE_MS;
+        let bars: Vec<String> = (0..limit)
+            .map(|i| first + i * MINUTE_MS)
+            .take_while(|open| *open <= end)
+            .map(|open| {
+                format!(
+                    r#"[{open}, "100.10000000", "101.00000000", "99.50000000", "100.50000000", "12.5", {close}, "1250.0", 42, "7.25", "725.0", "0"]"#,
+                    close = open + MINUTE_MS - 1
+                )
+            })
+            .collect();
+
+        format!("[{}]", bars.join(","))
+    }
+
+    fn endpoint(base_url: &str) -> String {
+        klines_endpoint("spot", Some(base_url)).unwrap().0
+    }
+
+    #[tokio::test]
+    async fn pages_until_a_short_batch() {
+        let server = serve(|target| {
+            Response::json(klines_json(target)).header("x-mbx-used-weight-1m", "10")
+        });
+        let end = 1500 * MINUTE_MS;
+
+        let klines = fetch_binance_klines(
+            &endpoint(&server.base_url),
+            SPOT_WEIGHT_BUDGET,
+            "BTCUSDT",
+            "1m",
+            0,
+            end,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(klines.len(), 1500);
+        assert!(klines
+            .windows(2)
+            .all(|pair| pair[1].open_time - pair[0].open_time == MINUTE_MS));
+
+        // The second page starts right after the last bar of the first
+        let requests = server.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("/api/v3/klines?"));
+        assert_eq!(
+            query_param(&requests[0], "symbol").as_deref(),
+            Some("BTCUSDT")
+        );
+        assert_eq!(query_param(&requests[0], "limit").as_deref(), Some("1000"));
+        assert_eq!(
+            query_param(&requests[1], "startTime"),
+            Some((999 * MINUTE_MS + 1).to_string())
+        );
+        assert_eq!(
+            query_param(&requests[1], "endTime"),
+            Some((end - 1).to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_after_rate_limit() {
+        let calls = Arc::new(AtomicU32::new(0));
+        let counter = calls.clone();
+        let server = serve(move |target| {
+            if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                Response::status(429).header("Retry-After", "0")
+            } else {
+                Response::json(klines_json(target))
+            }
+        });
+
+        let klines = fetch_binance_klines(
+            &endpoint(&server.base_url),
+            SPOT_WEIGHT_BUDGET,
+            "BTCUSDT",
+            "1m",
+            0,
+            10 * MINUTE_MS,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(klines.len(), 10);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_always_rate_limited() {
+        let server = serve(|_| Response::status(429).header("Retry-After", "0"));
+
+        let result = fetch_binance_klines(
+            &endpoint(&server.base_url),
+            SPOT_WEIGHT_BUDGET,
+            "BTCUSDT",
+            "1m",
+            0,
+            10 * MINUTE_MS,
+        )
+        .await;
+
+        assert!(result.unwrap_err().contains("rate limited"));
+        assert_eq!(
+            server.requests.lock().unwrap().len(),
+            MAX_RATE_LIMIT_RETRIES as usize + 1
+        );
+    }
+
+    #[tokio::test]
+    async fn ban_is_reported_with_its_wait() {
+        let server = serve(|_| Response::status(418).header("Retry-After", "120"));
+
+        let result = fetch_binance_klines(
+            &endpoint(&server.base_url),
+            SPOT_WEIGHT_BUDGET,
+            "BTCUSDT",
+            "1m",
+            0,
+            10 * MINUTE_MS,
+        )
+        .await;
+
+        assert!(result.unwrap_err().contains("retry in 120 seconds"));
+    }
+
+    #[test]
+    fn waits_for_the_next_minute_near_the_weight_budget() {
+        let minute = 1_000_020; // A whole minute
+        assert_eq!(weight_wait(4999, SPOT_WEIGHT_BUDGET, minute + 20), None);
+        assert_eq!(weight_wait(5000, SPOT_WEIGHT_BUDGET, minute), Some(61));
+        assert_eq!(weight_wait(5000, SPOT_WEIGHT_BUDGET, minute + 20), Some(41));
+        assert_eq!(
+            weight_wait(2400, FUTURES_WEIGHT_BUDGET, minute + 59),
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn klines_map_to_csv_rows() {
+        let value: serde_json::Value = serde_json::from_str(&klines_json(
+            "/api/v3/klines?startTime=60000&endTime=60000&limit=1",
+        ))
+        .unwrap();
+        let kline = parse_kline(&value[0]).unwrap();
+
+        assert_eq!(kline.open_time, 60_000);
+        assert_eq!(kline.volume, "12.5");
+        assert_eq!(kline.taker_buy_volume, "7.25");
+        assert_eq!(infer_price_precision(std::slice::from_ref(&kline)), Some(1));
+
+        // Bars missing a field are dropped rather than written with gaps
+        assert!(parse_kline(&serde_json::json!([60000, "1", "2", "0.5", "1.5"])).is_none());
+
+        let path = std::env::temp_dir().join(format!("klines-test-{}.csv", std::process::id()));
+        save_klines_to_csv(&path, &[kline]).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).ok();
+
+        assert_eq!(
+            csv,
+            "timestamp,open,high,low,close,volume,taker_buy_volume\n\
+             60,100.10000000,101.00000000,99.50000000,100.50000000,12.5,7.25\n"
+        );
+    }
+}
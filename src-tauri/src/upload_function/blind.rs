@@ -0,0 +1,516 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::settings::current_settings;
+
+use super::bars::BarType;
+use super::candles::{load_timeframe_candles, Candle};
+use super::local_upload::{get_available_symbols, SymbolData};
+use super::ticks::{start_tick_replay, step_tick_replay, stop_tick_replay, ReplayStep, TickRecord};
+
+// Dates are moved into this stretch, whole weeks at a time so weekdays and sessions still line up
+const FICTIONAL_EPOCH: i64 = 978_307_200; // 2001-01-01, a Monday
+const FICTIONAL_WEEKS: u64 = 520;
+const WEEK_SECONDS: i64 = 7 * 86400;
+
+const DEFAULT_LOOKBACK: usize = 200;
+const DEFAULT_FORWARD: usize = 100;
+
+// Blind sessions keyed by their replay session id
+#[derive(Default)]
+pub struct BlindReplays {
+    sessions: Mutex<HashMap<u32, BlindSession>>,
+}
+
+// Maps real prices and times to what the user sees: price * scale + offset, time + shift
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct Disguise {
+    pub price_scale: f64,
+    pub price_offset: f64,
+    pub time_shift: i64, // Seconds
+}
+
+impl Disguise {
+    fn price(&self, price: f64) -> f64 {
+        price * self.price_scale + self.price_offset
+    }
+
+    fn real_price(&self, price: f64) -> f64 {
+        (price - self.price_offset) / self.price_scale
+    }
+
+    fn candle(&self, candle: &mut Candle) {
+        candle.timestamp += self.time_shift;
+        candle.open = self.price(candle.open);
+        candle.high = self.price(candle.high);
+        candle.low = self.price(candle.low);
+        candle.close = self.price(candle.close);
+        candle.adj_close = None;
+    }
+
+    // Missing quote sides stay 0
+    fn tick(&self, tick: &mut TickRecord) {
+        tick.timestamp_ms += self.time_shift * 1000;
+        for price in [&mut tick.bid, &mut tick.ask, &mut tick.last] {
+            if *price != 0.0 {
+                *price = self.price(*price);
+            }
+        }
+    }
+
+    fn step(&self, step: &mut ReplayStep) {
+        step.ticks.iter_mut().for_each(|tick| self.tick(tick));
+        step.completed.iter_mut().for_each(|bar| self.candle(bar));
+        if let Some(bar) = &mut step.forming {
+            self.candle(bar);
+        }
+        step.bid = step.bid.map(|bid| self.price(bid));
+        step.ask = step.ask.map(|ask| self.price(ask));
+        step.spread = step.spread.map(|spread| spread * self.price_scale);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+// A practice trade as the user saw it, disguised prices and times
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlindTrade {
+    pub side: TradeSide,
+    pub size: f64,
+    pub entry_time: i64,
+    pub entry_price: f64,
+    #[serde(default)]
+    pub exit_time: Option<i64>,
+    #[serde(default)]
+    pub exit_price: Option<f64>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct RevealedTrade {
+    #[serde(flatten)]
+    pub trade: BlindTrade,
+    pub real_entry_time: i64,
+    pub real_entry_price: f64,
+    pub real_exit_time: Option<i64>,
+    pub real_exit_price: Option<f64>,
+    pub pnl: Option<f64>, // Real price units * size
+}
+
+struct BlindSession {
+    alias: String,
+    symbol: String,
+    timeframe: String,
+    real_start: i64,
+    last_time: Option<i64>, // Last played tick, real unix seconds
+    disguise: Disguise,
+    trades: Vec<BlindTrade>,
+    started_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BlindRequest {
+    pub timeframe: String,
+    #[serde(default)]
+    pub symbols: Option<Vec<String>>, // Pool to draw from, every stored symbol by default
+    #[serde(default)]
+    pub min_lookback: Option<usize>, // Bars of history shown before the start
+    #[serde(default)]
+    pub min_forward: Option<usize>, // Bars that must remain to be replayed
+    #[serde(default)]
+    pub scale_prices: bool,
+    #[serde(default)]
+    pub offset_prices: bool,
+    #[serde(default)]
+    pub bar_type: Option<BarType>, // The default chart type's bars when missing
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlindStart {
+    pub session_id: u32,
+    pub alias: String,
+    pub timeframe: String,
+    pub history: Vec<Candle>, // Disguised lookback bars before the start
+    pub first: ReplayStep,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BlindReveal {
+    pub alias: String,
+    pub symbol: String,
+    pub timeframe: String,
+    pub real_start: i64,
+    pub real_end: Option<i64>, // Where the replay was stopped
+    pub disguise: Disguise,
+    pub started_at: String,
+    pub ended_at: String,
+    pub trades: Vec<RevealedTrade>,
+}
+
+fn random_alias() -> String {
+    let letters: String = (0..4)
+        .map(|_| fastrand::alphabetic().to_ascii_uppercase())
+        .collect();
+    format!("BLIND-{}", letters)
+}
+
+// Span in unix seconds that a replay of this symbol plays from: its ticks whenever it has any,
+// as `start_tick_replay` prefers them, otherwise its M1 candles
+fn replay_range(symbol_data: &SymbolData) -> Result<Option<(i64, i64)>, String> {
+    if let Some(ticks) = &symbol_data.ticks {
+        return Ok(Some((
+            ticks.first_ms.div_euclid(1000),
+            ticks.last_ms.div_euclid(1000),
+        )));
+    }
+
+    let minutes = load_timeframe_candles(symbol_data, "M1")?;
+    Ok(minutes
+        .first()
+        .zip(minutes.last())
+        .map(|(first, last)| (first.timestamp, last.timestamp)))
+}
+
+// Bars that can start a blind replay: inside `range`, with `lookback` bars of history before them
+// and `forward` bars after them that are still inside `range`
+fn start_bounds(
+    candles: &[Candle],
+    range: (i64, i64),
+    lookback: usize,
+    forward: usize,
+) -> std::ops::Range<usize> {
+    let covered_from = candles.partition_point(|c| c.timestamp < range.0);
+    let covered_to = candles.partition_point(|c| c.timestamp <= range.1);
+
+    lookback.max(covered_from)..covered_to.saturating_sub(forward)
+}
+
+// Pick a random symbol and start, then replay it under an alias with disguised prices and dates
+#[tauri::command]
+pub async fn start_blind_replay(
+    app_handle: AppHandle,
+    request: BlindRequest,
+) -> Result<BlindStart, String> {
+    let lookback = request.min_lookback.unwrap_or(DEFAULT_LOOKBACK);
+    let forward = request.min_forward.unwrap_or(DEFAULT_FORWARD).max(1);
+
+    // Replays need ticks or M1 candles, the start needs enough bars around it
+    let candidates: Vec<_> = get_available_symbols(app_handle.clone())
+        .await?
+        .into_iter()
+        .filter(|data| {
+            request
+                .symbols
+                .as_ref()
+                .is_none_or(|pool| pool.contains(&data.symbol))
+        })
+        .filter(|data| data.ticks.is_some() || data.timeframes.iter().any(|tf| tf.name == "M1"))
+        .filter(|data| {
+            data.timeframes
+                .iter()
+                .any(|tf| tf.name == request.timeframe && tf.candle_count > lookback + forward)
+        })
+        .collect();
+
+    if candidates.is_empty() {
+        return Err(format!(
+            "No symbol has {} {} bars and ticks or M1 data to replay",
+            lookback + forward + 1,
+            request.timeframe
+        ));
+    }
+
+    // The start must also lie where the replay has data, which can be much shorter than the
+    // candle history, so try the candidates in random order until one fits
+    let mut candidates = candidates;
+    fastrand::shuffle(&mut candidates);
+
+    let mut picked = None;
+    for symbol_data in &candidates {
+        let Some(range) = replay_range(symbol_data)? else {
+            continue;
+        };
+        let candles = load_timeframe_candles(symbol_data, &request.timeframe)?;
+        let bounds = start_bounds(&candles, range, lookback, forward);
+
+        if !bounds.is_empty() {
+            picked = Some((symbol_data, candles, bounds));
+            break;
+        }
+    }
+
+    let Some((symbol_data, candles, bounds)) = picked else {
+        return Err(format!(
+            "No symbol has {} {} bars within the range its ticks or M1 data cover",
+            lookback + forward + 1,
+            request.timeframe
+        ));
+    };
+
+    let start = fastrand::usize(bounds);
+    let real_start = candles[start].timestamp;
+
+    let target = FICTIONAL_EPOCH + fastrand::u64(..FICTIONAL_WEEKS) as i64 * WEEK_SECONDS;
+    let reference = candles[start].open;
+    let price_scale = if request.scale_prices {
+        0.5 + fastrand::f64() * 1.5
+    } else {
+        1.0
+    };
+    let disguise = Disguise {
+        price_scale,
+        price_offset: if request.offset_prices {
+            reference * price_scale * (fastrand::f64() - 0.5) * 0.6
+        } else {
+            0.0
+        },
+        time_shift: (target - real_start).div_euclid(WEEK_SECONDS) * WEEK_SECONDS,
+    };
+
+    let mut history = candles[start - lookback..start].to_vec();
+    history.iter_mut().for_each(|bar| disguise.candle(bar));
+
+    let mut first = start_tick_replay(
+        app_handle.clone(),
+        symbol_data.symbol.clone(),
+        request.timeframe.clone(),
+        Some(real_start * 1000),
+        match request.bar_type {
+            Some(bar_type) => Some(bar_type),
+            None => current_settings(&app_handle)?.general.bar_type(),
+        },
+        None,
+        None,
+    )
+    .await?;
+    disguise.step(&mut first);
+
+    let alias = random_alias();
+    let session = BlindSession {
+        alias: alias.clone(),
+        symbol: symbol_data.symbol.clone(),
+        timeframe: request.timeframe.clone(),
+        real_start,
+        last_time: None,
+        disguise,
+        trades: Vec::new(),
+        started_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    app_handle
+        .state::<BlindReplays>()
+        .sessions
+        .lock()
+        .map_err(|_| "Blind replay state is unavailable".to_string())?
+        .insert(first.session_id, session);
+
+    println!("✓ Blind replay {} started", alias);
+
+    Ok(BlindStart {
+        session_id: first.session_id,
+        alias,
+        timeframe: request.timeframe,
+        history,
+        first,
+    })
+}
+
+fn with_session<T>(
+    app_handle: &AppHandle,
+    session_id: u32,
+    f: impl FnOnce(&mut BlindSession) -> T,
+) -> Result<T, String> {
+    let state = app_handle.state::<BlindReplays>();
+    let mut sessions = state
+        .sessions
+        .lock()
+        .map_err(|_| "Blind replay state is unavailable".to_string())?;
+
+    let session = sessions
+        .get_mut(&session_id)
+        .ok_or(format!("Blind replay session {} not found", session_id))?;
+
+    Ok(f(session))
+}
+
+// Same as step_tick_replay, `until_ms` is in the disguised time the user sees
+#[tauri::command]
+pub async fn step_blind_replay(
+    app_handle: AppHandle,
+    session_id: u32,
+    count: Option<usize>,
+    until_ms: Option<i64>,
+) -> Result<ReplayStep, String> {
+    let disguise = with_session(&app_handle, session_id, |session| session.disguise)?;
+
+    let mut step = step_tick_replay(
+        app_handle.clone(),
+        session_id,
+        count,
+        until_ms.map(|until| until - disguise.time_shift * 1000),
+    )
+    .await?;
+
+    if let Some(tick) = step.ticks.last() {
+        let last = tick.timestamp_ms.div_euclid(1000);
+        with_session(&app_handle, session_id, |session| {
+            session.last_time = Some(last)
+        })?;
+    }
+
+    disguise.step(&mut step);
+
+    Ok(step)
+}
+
+// Trades are kept with the session and revealed with it
+#[tauri::command]
+pub async fn record_blind_trade(
+    app_handle: AppHandle,
+    session_id: u32,
+    trade: BlindTrade,
+) -> Result<usize, String> {
+    if trade.size <= 0.0 {
+        return Err("Trade size must be positive".to_string());
+    }
+
+    let max_orders = current_settings(&app_handle)?.trade.max_orders as usize;
+
+    with_session(&app_handle, session_id, |session| {
+        let open = session
+            .trades
+            .iter()
+            .filter(|t| t.exit_time.is_none())
+            .count();
+        if trade.exit_time.is_none() && open >= max_orders {
+            return Err(format!("Already {} open trades, the most allowed", open));
+        }
+
+        session.trades.push(trade);
+        Ok(session.trades.len())
+    })?
+}
+
+// End the session, reveal the real symbol and dates, and log them with the trades
+#[tauri::command]
+pub async fn end_blind_replay(
+    app_handle: AppHandle,
+    session_id: u32,
+) -> Result<BlindReveal, String> {
+    let session = app_handle
+        .state::<BlindReplays>()
+        .sessions
+        .lock()
+        .map_err(|_| "Blind replay state is unavailable".to_string())?
+        .remove(&session_id)
+        .ok_or(format!("Blind replay session {} not found", session_id))?;
+
+    stop_tick_replay(app_handle.clone(), session_id).await?;
+
+    let disguise = session.disguise;
+    let trades = session
+        .trades
+        .into_iter()
+        .map(|trade| {
+            let real_entry_price = disguise.real_price(trade.entry_price);
+            let real_exit_price = trade.exit_price.map(|price| disguise.real_price(price));
+            let direction = match trade.side {
+                TradeSide::Buy => 1.0,
+                TradeSide::Sell => -1.0,
+            };
+
+            RevealedTrade {
+                real_entry_time: trade.entry_time - disguise.time_shift,
+                real_entry_price,
+                real_exit_time: trade.exit_time.map(|time| time - disguise.time_shift),
+                real_exit_price,
+                pnl: real_exit_price.map(|exit| (exit - real_entry_price) * direction * trade.size),
+                trade,
+            }
+        })
+        .collect();
+
+    let reveal = BlindReveal {
+        alias: session.alias,
+        symbol: session.symbol,
+        timeframe: session.timeframe,
+        real_start: session.real_start,
+        real_end: session.last_time,
+        disguise,
+        started_at: session.started_at,
+        ended_at: chrono::Utc::now().to_rfc3339(),
+        trades,
+    };
+
+    // One JSON line per session, next to the symbol data
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    std::fs::create_dir_all(&app_dir)
+        .map_err(|e| format!("Failed to create app data directory: {}", e))?;
+
+    let line = serde_json::to_string(&reveal)
+        .map_err(|e| format!("Failed to serialize blind session: {}", e))?;
+    let mut log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(app_dir.join("blind_sessions.jsonl"))
+        .map_err(|e| format!("Failed to open blind session log: {}", e))?;
+    writeln!(log, "{}", line).map_err(|e| format!("Failed to write blind session log: {}", e))?;
+
+    println!(
+        "✓ Blind replay {} was {} {} from {}, {} trades",
+        reveal.alias,
+        reveal.symbol,
+        reveal.timeframe,
+        reveal.real_start,
+        reveal.trades.len()
+    );
+
+    Ok(reveal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candles(timestamps: impl Iterator<Item = i64>) -> Vec<Candle> {
+        timestamps
+            .map(|timestamp| Candle {
+                timestamp,
+                open: 1.0,
+                high: 1.0,
+                low: 1.0,
+                close: 1.0,
+                volume: 0.0,
+                adj_close: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn starts_stay_inside_the_replayed_range() {
+        // Hourly bars 0..100, replay data only for bars 40..=60
+        let bars = candles((0..100).map(|i| i * 3600));
+        let range = (40 * 3600, 60 * 3600);
+
+        assert_eq!(start_bounds(&bars, range, 10, 5), 40..56);
+        // Not enough history before the covered range
+        assert_eq!(start_bounds(&bars, range, 50, 5), 50..56);
+        // Nothing left to replay
+        assert!(start_bounds(&bars, range, 10, 30).is_empty());
+        // Full coverage keeps the old bounds
+        assert_eq!(start_bounds(&bars, (0, 99 * 3600), 10, 5), 10..95);
+    }
+}
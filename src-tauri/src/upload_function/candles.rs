@@ -0,0 +1,592 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use super::bars::{build_bars, heikin_ashi, BarSource, BarType};
+use super::corporate_actions::{
+    apply_adjustment, load_corporate_actions, CorporateActions, PriceAdjustment,
+};
+use super::local_upload::{load_symbol_data, CsvFormat, SymbolData};
+use super::timezone::SourceTimezone;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Candle {
+    pub timestamp: i64, // Unix seconds
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    #[serde(skip)]
+    pub adj_close: Option<f64>, // Dividend-adjusted close, when the source provides one
+}
+
+// Column positions of a candle file, worked out from its header line
+#[derive(Debug, Clone)]
+pub struct CsvLayout {
+    pub delimiter: char,
+    pub date_col: usize,
+    pub time_col: Option<usize>,
+    pub open_col: usize,
+    pub high_col: usize,
+    pub low_col: usize,
+    pub close_col: usize,
+    pub volume_col: Option<usize>,
+    pub adj_close_col: Option<usize>,
+}
+
+impl CsvLayout {
+    pub fn detect(header_line: &str) -> Result<Self, String> {
+        let delimiter = detect_delimiter(header_line)
+            .ok_or("Could not detect CSV delimiter (expected tab, comma, or semicolon)")?;
+
+        Ok(Self::with_delimiter(header_line, delimiter))
+    }
+
+    // Same as `detect` for a known delimiter, e.g. ';' in files with comma decimals
+    pub fn with_delimiter(header_line: &str, delimiter: char) -> Self {
+        let headers: Vec<String> = header_line
+            .split(delimiter)
+            .map(|h| {
+                h.trim()
+                    .trim_matches(|c| c == '<' || c == '>')
+                    .to_lowercase()
+            })
+            .collect();
+
+        let find = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+
+        // Named columns (Yahoo and MT5 style headers)
+        if let (Some(open_col), Some(high_col), Some(low_col), Some(close_col)) = (
+            find(&["open"]),
+            find(&["high"]),
+            find(&["low"]),
+            find(&["close"]),
+        ) {
+            let date_col =
+                find(&["timestamp", "datetime", "date", "dtyyyymmdd", "time"]).unwrap_or(0);
+            let time_col = find(&["time"]).filter(|&col| col != date_col);
+
+            return CsvLayout {
+                delimiter,
+                date_col,
+                time_col,
+                open_col,
+                high_col,
+                low_col,
+                close_col,
+                volume_col: find(&["volume", "tickvol", "vol"]),
+                adj_close_col: find(&["adjclose", "adj close", "adj_close"]),
+            };
+        }
+
+        // Fall back to the positional layouts accepted by the local importer
+        if headers.len() >= 8 {
+            // DATE, TIME, OPEN, HIGH, LOW, CLOSE, TICKVOL, VOL, SPREAD
+            CsvLayout {
+                delimiter,
+                date_col: 0,
+                time_col: Some(1),
+                open_col: 2,
+                high_col: 3,
+                low_col: 4,
+                close_col: 5,
+                volume_col: Some(6),
+                adj_close_col: None,
+            }
+        } else {
+            // DATE, OPEN, HIGH, LOW, CLOSE, [VOLUME]
+            CsvLayout {
+                delimiter,
+                date_col: 0,
+                time_col: None,
+                open_col: 1,
+                high_col: 2,
+                low_col: 3,
+                close_col: 4,
+                volume_col: if headers.len() > 5 { Some(5) } else { None },
+                adj_close_col: None,
+            }
+        }
+    }
+
+    pub fn parse_line(&self, line: &str) -> Option<Candle> {
+        let fields: Vec<&str> = line.split(self.delimiter).map(str::trim).collect();
+        let price = |col: usize| fields.get(col).and_then(|v| v.parse::<f64>().ok());
+
+        let time = self.time_col.and_then(|col| fields.get(col).copied());
+        let timestamp = parse_timestamp(fields.get(self.date_col)?, time)?;
+
+        Some(Candle {
+            timestamp,
+            open: price(self.open_col)?,
+            high: price(self.high_col)?,
+            low: price(self.low_col)?,
+            close: price(self.close_col)?,
+            volume: self.volume_col.and_then(price).unwrap_or(0.0),
+            adj_close: self.adj_close_col.and_then(price),
+        })
+    }
+
+    // Whether the line's time names an instant, which no source timezone applies to
+    pub fn has_absolute_time(&self, line: &str) -> bool {
+        line.split(self.delimiter)
+            .nth(self.date_col)
+            .is_some_and(is_absolute_timestamp)
+    }
+}
+
+// Read every parsable candle from a stored file, oldest first.
+// The layout is detected from the first line unless `format` pins it.
+pub fn read_candles(path: &Path, format: Option<CsvFormat>) -> Result<Vec<Candle>, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    parse_candles(&content, format)
+}
+
+// Same as `read_candles`, for CSV content already in memory
+pub fn parse_candles(content: &str, format: Option<CsvFormat>) -> Result<Vec<Candle>, String> {
+    let first_line = content.lines().next().unwrap_or("");
+    let format = format.unwrap_or_else(|| CsvFormat::detect(first_line));
+    let layout = format.layout(first_line)?;
+
+    let mut candles: Vec<Candle> = content
+        .lines()
+        .skip(if format.has_header() { 1 } else { 0 })
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| layout.parse_line(line))
+        .collect();
+
+    candles.sort_by_key(|c| c.timestamp);
+
+    Ok(candles)
+}
+
+// Header of the app's own layout: unix seconds (UTC) and '.' decimals
+pub const CSV_HEADER: &str = "timestamp,open,high,low,close,volume";
+
+// One candle as a row of the app's own layout, without the line break
+pub fn candle_row(c: &Candle) -> String {
+    format!(
+        "{},{},{},{},{},{}",
+        c.timestamp, c.open, c.high, c.low, c.close, c.volume
+    )
+}
+
+// Candles in the app's own layout
+pub fn candles_to_csv(candles: &[Candle]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+
+    for c in candles {
+        csv.push_str(&candle_row(c));
+        csv.push('\n');
+    }
+
+    csv
+}
+
+pub fn write_candles(path: &Path, candles: &[Candle]) -> Result<(), String> {
+    fs::write(path, candles_to_csv(candles)).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+// Length of a fixed-size timeframe in seconds (MN1 varies, so it has none)
+pub fn timeframe_seconds(timeframe: &str) -> Option<i64> {
+    match timeframe {
+        "M1" => Some(60),
+        "M2" => Some(2 * 60),
+        "M3" => Some(3 * 60),
+        "M4" => Some(4 * 60),
+        "M5" => Some(5 * 60),
+        "M10" => Some(10 * 60),
+        "M15" => Some(15 * 60),
+        "M30" => Some(30 * 60),
+        "H1" => Some(3600),
+        "H2" => Some(2 * 3600),
+        "H3" => Some(3 * 3600),
+        "H4" => Some(4 * 3600),
+        "D1" => Some(86_400),
+        "W1" => Some(7 * 86_400),
+        _ => None,
+    }
+}
+
+// Open time of the bar that `timestamp` falls into. Weeks start on Monday 00:00 UTC
+// and months on the 1st, everything else is aligned to the Unix epoch.
+pub fn bar_start(timestamp: i64, timeframe: &str) -> Option<i64> {
+    const MONDAY_OFFSET: i64 = 4 * 86_400; // 1970-01-01 was a Thursday
+
+    match timeframe {
+        "MN1" => {
+            let dt = chrono::DateTime::from_timestamp(timestamp, 0)?;
+            let first = NaiveDate::from_ymd_opt(dt.year(), dt.month(), 1)?;
+            Some(first.and_hms_opt(0, 0, 0)?.and_utc().timestamp())
+        }
+        "W1" => {
+            let week = 7 * 86_400;
+            Some((timestamp - MONDAY_OFFSET).div_euclid(week) * week + MONDAY_OFFSET)
+        }
+        _ => {
+            let seconds = timeframe_seconds(timeframe)?;
+            Some(timestamp.div_euclid(seconds) * seconds)
+        }
+    }
+}
+
+// Build higher-timeframe bars from sorted lower-timeframe candles
+pub fn resample(candles: &[Candle], timeframe: &str) -> Result<Vec<Candle>, String> {
+    let mut bars: Vec<Candle> = Vec::new();
+
+    for candle in candles {
+        let start = bar_start(candle.timestamp, timeframe)
+            .ok_or(format!("Unsupported timeframe: {}", timeframe))?;
+
+        match bars.last_mut() {
+            Some(bar) if bar.timestamp == start => {
+                bar.high = bar.high.max(candle.high);
+                bar.low = bar.low.min(candle.low);
+                bar.close = candle.close;
+                bar.volume += candle.volume;
+            }
+            _ => bars.push(Candle {
+                timestamp: start,
+                adj_close: None,
+                ..candle.clone()
+            }),
+        }
+    }
+
+    Ok(bars)
+}
+
+// Detect the delimiter from a header or data line (tab, comma, semicolon, then spaces)
+pub fn detect_delimiter(line: &str) -> Option<char> {
+    if line.matches('\t').count() >= 4 {
+        Some('\t')
+    } else if line.matches(',').count() >= 4 {
+        Some(',')
+    } else if line.matches(';').count() >= 4 {
+        Some(';')
+    } else if line.matches(' ').count() >= 7 {
+        Some(' ')
+    } else {
+        None
+    }
+}
+
+// Compact YYYYMMDD dates (HistData, MetaStock, NinjaTrader) would otherwise pass as numbers
+fn is_compact_date(date: &str) -> bool {
+    date.len() == 8 && date.chars().all(|c| c.is_ascii_digit())
+}
+
+// Epoch numbers and RFC 3339 times with an offset, as opposed to wall-clock dates
+pub fn is_absolute_timestamp(date: &str) -> bool {
+    let date = date.trim();
+
+    (!is_compact_date(date) && date.parse::<i64>().is_ok())
+        || chrono::DateTime::parse_from_rfc3339(date).is_ok()
+}
+
+// Parse a date column (and optional separate time column) into a Unix timestamp in seconds.
+// Accepts YYYY.MM.DD / YYYY-MM-DD / YYYYMMDD with or without time, ISO 8601 with an offset,
+// and raw Unix timestamps (s or ms).
+pub fn parse_timestamp(date: &str, time: Option<&str>) -> Option<i64> {
+    let date = date.trim();
+
+    if !is_compact_date(date) {
+        if let Ok(value) = date.parse::<i64>() {
+            // Millisecond timestamps are 13 digits, seconds are 10
+            return Some(if value > 100_000_000_000 {
+                value / 1000
+            } else {
+                value
+            });
+        }
+    }
+
+    if let Ok(dt) = chrono::DateTime::parse_from_rfc3339(date) {
+        return Some(dt.timestamp());
+    }
+
+    let combined = match time.map(str::trim) {
+        Some(t) if t.contains(':') => format!("{} {}", date, t),
+        // HHMM or HHMMSS next to a compact date
+        Some(t)
+            if is_compact_date(date)
+                && (t.len() == 4 || t.len() == 6)
+                && t.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            format!("{} {}", date, t)
+        }
+        _ => date.to_string(),
+    };
+
+    let datetime_formats = [
+        "%Y.%m.%d %H:%M:%S",
+        "%Y.%m.%d %H:%M",
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y%m%d %H%M%S",
+        "%Y%m%d %H%M",
+    ];
+
+    for format in datetime_formats {
+        if let Ok(dt) = NaiveDateTime::parse_from_str(&combined, format) {
+            return Some(dt.and_utc().timestamp());
+        }
+    }
+
+    for format in ["%Y.%m.%d", "%Y-%m-%d", "%Y%m%d"] {
+        if let Ok(d) = NaiveDate::parse_from_str(&combined, format) {
+            return d.and_hms_opt(0, 0, 0).map(|dt| dt.and_utc().timestamp());
+        }
+    }
+
+    None
+}
+
+// Timestamp of a single CSV line, using the date column and the time column if present
+pub fn line_timestamp(line: &str, delimiter: char) -> Option<i64> {
+    let mut fields = line
+        .split(delimiter)
+        .map(str::trim)
+        .filter(|f| !f.is_empty());
+    let date = fields.next()?;
+    parse_timestamp(date, fields.next())
+}
+
+// Append the data rows that are newer than the last candle already stored at `path`, as they
+// are read. Both must share the same layout. Returns the number of rows appended.
+pub fn append_new_rows(
+    path: &Path,
+    rows: impl Iterator<Item = Result<String, String>>,
+) -> Result<usize, String> {
+    let existing =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read stored file: {}", e))?;
+
+    let delimiter = detect_delimiter(existing.lines().next().unwrap_or(""))
+        .ok_or("Could not detect delimiter of stored file")?;
+
+    let last_timestamp = existing
+        .lines()
+        .skip(1)
+        .filter_map(|line| line_timestamp(line, delimiter))
+        .max()
+        .ok_or("Could not determine the last stored candle")?;
+
+    let mut file: Option<File> = None;
+    let mut appended = 0;
+
+    for row in rows {
+        let row = row?;
+        if !matches!(line_timestamp(&row, delimiter), Some(ts) if ts > last_timestamp) {
+            continue;
+        }
+
+        // Only opened once there is something to add
+        let file = match &mut file {
+            Some(file) => file,
+            None => {
+                let mut opened = OpenOptions::new()
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| format!("Failed to open stored file: {}", e))?;
+                if !existing.ends_with('\n') {
+                    writeln!(opened).map_err(|e| format!("Failed to write stored file: {}", e))?;
+                }
+                file.insert(opened)
+            }
+        };
+
+        writeln!(file, "{}", row).map_err(|e| format!("Failed to write stored file: {}", e))?;
+        appended += 1;
+    }
+
+    Ok(appended)
+}
+
+// Stored candles of one timeframe, as written by the importers
+pub fn load_timeframe_candles(
+    symbol_data: &SymbolData,
+    timeframe: &str,
+) -> Result<Vec<Candle>, String> {
+    let info = symbol_data
+        .timeframes
+        .iter()
+        .find(|tf| tf.name == timeframe)
+        .ok_or(format!(
+            "Timeframe {} not found for {}",
+            timeframe, symbol_data.symbol
+        ))?;
+
+    read_candles(Path::new(&info.file_path), info.format)
+}
+
+fn no_corporate_actions(symbol: &str) -> String {
+    format!(
+        "No corporate actions are stored for {}, download it with events to adjust its prices",
+        symbol
+    )
+}
+
+// Adjust one timeframe's candles. Without stored corporate actions only a fully adjusted series
+// can still be built, from the adj_close column.
+fn adjust_candles(
+    candles: &mut [Candle],
+    symbol: &str,
+    actions: Option<&CorporateActions>,
+    adjustment: PriceAdjustment,
+) -> Result<(), String> {
+    match actions {
+        Some(actions) => apply_adjustment(candles, actions, adjustment),
+        None => {
+            if adjustment != PriceAdjustment::FullyAdjusted
+                || !candles.iter().any(|c| c.adj_close.is_some())
+            {
+                return Err(no_corporate_actions(symbol));
+            }
+            apply_adjustment(candles, &CorporateActions::default(), adjustment);
+        }
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_candles(
+    app_handle: AppHandle,
+    symbol: String,
+    timeframe: String,
+    adjustment: Option<PriceAdjustment>,
+    display_timezone: Option<String>,
+    bar_type: Option<BarType>,
+) -> Result<Vec<Candle>, String> {
+    let symbol_data = load_symbol_data(&app_handle, &symbol)?;
+
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    // Without stored corporate actions only an adj_close column can still adjust prices
+    let actions = match adjustment {
+        Some(adjustment) => Some((
+            load_corporate_actions(&app_dir, &symbol_data.symbol)?,
+            adjustment,
+        )),
+        None => None,
+    };
+    if matches!(actions, Some((None, adjustment)) if adjustment != PriceAdjustment::FullyAdjusted) {
+        return Err(no_corporate_actions(&symbol_data.symbol));
+    }
+
+    let load = |timeframe: &str| -> Result<Vec<Candle>, String> {
+        let mut candles = load_timeframe_candles(&symbol_data, timeframe)?;
+        if let Some((actions, adjustment)) = &actions {
+            adjust_candles(
+                &mut candles,
+                &symbol_data.symbol,
+                actions.as_ref(),
+                *adjustment,
+            )?;
+        }
+        Ok(candles)
+    };
+
+    let mut candles = match bar_type {
+        None => load(&timeframe)?,
+        Some(BarType::HeikinAshi) => heikin_ashi(&load(&timeframe)?),
+        // Price-driven bars prefer ticks, M1 candles are the fallback
+        Some(bar_type) => {
+            let reference = if bar_type.uses_atr() {
+                load(&timeframe)?
+            } else {
+                Vec::new()
+            };
+
+            if symbol_data.ticks.is_some() {
+                let source = BarSource::Ticks {
+                    app_dir: &app_dir,
+                    symbol: &symbol_data.symbol,
+                };
+                build_bars(&timeframe, &bar_type, &reference, source)?
+            } else if symbol_data.timeframes.iter().any(|tf| tf.name == "M1") {
+                let minutes = load("M1")?;
+                build_bars(
+                    &timeframe,
+                    &bar_type,
+                    &reference,
+                    BarSource::Minutes(&minutes),
+                )?
+            } else {
+                return Err(format!(
+                    "{} has no ticks or M1 candles to build bars from",
+                    symbol
+                ));
+            }
+        }
+    };
+
+    // Charts plot unix time as UTC, so shift to the wall-clock time of the display zone
+    if let Some(display_timezone) = display_timezone {
+        SourceTimezone::parse(&display_timezone)?.shift_from_utc(&mut candles);
+    }
+
+    Ok(candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(timestamp: i64, close: f64, adj_close: Option<f64>) -> Candle {
+        Candle {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 100.0,
+            adj_close,
+        }
+    }
+
+    #[test]
+    fn adjusting_without_stored_events() {
+        let mut plain = vec![candle(100, 50.0, None)];
+        for adjustment in [
+            PriceAdjustment::Raw,
+            PriceAdjustment::SplitAdjusted,
+            PriceAdjustment::FullyAdjusted,
+        ] {
+            let error = adjust_candles(&mut plain, "AAPL", None, adjustment).unwrap_err();
+            assert_eq!(error, no_corporate_actions("AAPL"));
+        }
+        assert_eq!(plain[0].close, 50.0);
+
+        // An adj_close column is enough for a fully adjusted series, but not for the others
+        let mut with_adj_close = vec![candle(100, 50.0, Some(49.0))];
+        assert!(adjust_candles(&mut with_adj_close, "AAPL", None, PriceAdjustment::Raw).is_err());
+        adjust_candles(
+            &mut with_adj_close,
+            "AAPL",
+            None,
+            PriceAdjustment::FullyAdjusted,
+        )
+        .unwrap();
+        assert_eq!(with_adj_close[0].close, 49.0);
+
+        // Stored but empty events leave the prices as they are
+        let mut stored = vec![candle(100, 50.0, None)];
+        adjust_candles(
+            &mut stored,
+            "AAPL",
+            Some(&CorporateActions::default()),
+            PriceAdjustment::Raw,
+        )
+        .unwrap();
+        assert_eq!(stored[0].close, 50.0);
+    }
+}
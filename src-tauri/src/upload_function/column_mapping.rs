@@ -0,0 +1,774 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+
+use super::candles::{detect_delimiter, Candle, CsvLayout};
+use super::local_upload::CsvFormat;
+use super::timezone::SourceTimezone;
+use super::validation::ParsedRow;
+
+// Explicit column layout for files the format registry cannot read on its own
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ColumnMapping {
+    #[serde(default)]
+    pub delimiter: Option<char>, // Detected from the first line when missing
+    pub has_header: bool,
+    pub date_col: usize,
+    #[serde(default)]
+    pub time_col: Option<usize>,
+    // chrono format for the date (joined with the time column by a space when there is one),
+    // or "unix" / "unix_ms" for epoch timestamps
+    pub date_format: String,
+    pub open_col: usize,
+    pub high_col: usize,
+    pub low_col: usize,
+    pub close_col: usize,
+    #[serde(default)]
+    pub volume_col: Option<usize>,
+    #[serde(default)]
+    pub timezone: Option<String>, // See SourceTimezone::parse, UTC when missing
+    #[serde(default = "default_decimal_separator")]
+    pub decimal_separator: char,
+}
+
+fn default_decimal_separator() -> char {
+    '.'
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImportProfile {
+    pub name: String,
+    pub mapping: ColumnMapping,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CsvSample {
+    pub success: bool,
+    pub message: String,
+    pub delimiter: Option<char>,
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+    pub column_kinds: Vec<String>, // "timestamp", "date", "time", "number" or "text" per column
+    pub candidates: Vec<CsvFormat>,
+    pub suggested: Option<ColumnMapping>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MappingPreview {
+    pub success: bool,
+    pub message: String,
+    pub rows: Vec<Candle>,
+    pub errors: Vec<String>, // "Row N: ..." for sample rows that failed to parse
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileResult {
+    pub success: bool,
+    pub message: String,
+    pub name: String,
+}
+
+const SAMPLE_ROWS: usize = 20;
+
+// Date layouts tried when suggesting a mapping, most specific first
+const DATE_FORMATS: [&str; 14] = [
+    "%Y.%m.%d %H:%M:%S",
+    "%Y.%m.%d %H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%d/%m/%Y %H:%M:%S",
+    "%m/%d/%Y %H:%M:%S",
+    "%d/%m/%Y %H:%M",
+    "%m/%d/%Y %H:%M",
+    "%Y%m%d %H%M%S",
+    "%Y.%m.%d",
+    "%Y-%m-%d",
+    "%d/%m/%Y",
+    "%m/%d/%Y",
+    "%d.%m.%Y",
+];
+
+impl ColumnMapping {
+    pub fn source_timezone(&self) -> Result<SourceTimezone, String> {
+        SourceTimezone::parse(self.timezone.as_deref().unwrap_or(""))
+    }
+
+    // Check the parts of a mapping that do not depend on the file, returning its timezone
+    pub fn validate(&self) -> Result<SourceTimezone, String> {
+        if let Some(delimiter) = self.delimiter {
+            self.check_separators(delimiter)?;
+        }
+
+        self.source_timezone()
+    }
+
+    // A decimal separator equal to the delimiter would split every price in two
+    fn check_separators(&self, delimiter: char) -> Result<(), String> {
+        if delimiter == self.decimal_separator {
+            return Err(format!(
+                "Decimal separator '{}' cannot also be the delimiter",
+                self.decimal_separator
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn parse_number(&self, value: &str) -> Option<f64> {
+        let value = value.trim().trim_matches('"');
+
+        if self.decimal_separator == '.' {
+            value.parse().ok()
+        } else {
+            // "1.234,56" style: drop grouping dots, then swap the separator
+            value
+                .replace('.', "")
+                .replace(self.decimal_separator, ".")
+                .parse()
+                .ok()
+        }
+    }
+
+    // Epoch timestamps are UTC already, wall-clock dates are converted from `timezone`
+    fn parse_datetime(
+        &self,
+        date: &str,
+        time: Option<&str>,
+        timezone: &SourceTimezone,
+    ) -> Option<i64> {
+        let date = date.trim().trim_matches('"');
+
+        match self.date_format.as_str() {
+            "unix" => return date.parse().ok(),
+            "unix_ms" => return date.parse::<i64>().ok().map(|ms| ms / 1000),
+            _ => {}
+        }
+
+        let combined = match time.map(|t| t.trim().trim_matches('"')) {
+            Some(t) if !t.is_empty() => format!("{} {}", date, t),
+            _ => date.to_string(),
+        };
+
+        let local = NaiveDateTime::parse_from_str(&combined, &self.date_format)
+            .ok()
+            .or_else(|| {
+                NaiveDate::parse_from_str(&combined, &self.date_format)
+                    .ok()
+                    .and_then(|d| d.and_hms_opt(0, 0, 0))
+            })?;
+
+        Some(timezone.local_to_utc(local))
+    }
+
+    // Parse one data line, converting the timestamp to UTC from the mapping's resolved timezone
+    pub fn parse_line(
+        &self,
+        line: &str,
+        delimiter: char,
+        timezone: &SourceTimezone,
+    ) -> Result<Candle, String> {
+        let fields: Vec<&str> = line.split(delimiter).collect();
+
+        let field = |col: usize, name: &str| {
+            fields
+                .get(col)
+                .copied()
+                .ok_or(format!("missing {} column {}", name, col + 1))
+        };
+        let number = |col: usize, name: &str| {
+            let value = field(col, name)?;
+            self.parse_number(value)
+                .ok_or(format!("invalid {} '{}'", name, value.trim()))
+        };
+
+        let date = field(self.date_col, "date")?;
+        let time = match self.time_col {
+            Some(col) => Some(field(col, "time")?),
+            None => None,
+        };
+
+        let timestamp = self.parse_datetime(date, time, timezone).ok_or(format!(
+            "date '{}' does not match '{}'",
+            date.trim(),
+            self.date_format
+        ))?;
+
+        Ok(Candle {
+            timestamp,
+            open: number(self.open_col, "open")?,
+            high: number(self.high_col, "high")?,
+            low: number(self.low_col, "low")?,
+            close: number(self.close_col, "close")?,
+            volume: match self.volume_col {
+                Some(col) => number(col, "volume")?,
+                None => 0.0,
+            },
+            adj_close: None,
+        })
+    }
+
+    pub fn resolve_delimiter(&self, first_line: &str) -> Result<char, String> {
+        let delimiter = self
+            .delimiter
+            .or_else(|| detect_delimiter(first_line))
+            .ok_or("Could not detect CSV delimiter, please set one in the mapping".to_string())?;
+
+        self.check_separators(delimiter)?;
+
+        Ok(delimiter)
+    }
+
+    // Parse every data row of a file, keeping failures for the validation report
+    pub fn read_rows(&self, file_path: &Path) -> Result<Vec<ParsedRow>, String> {
+        let file = File::open(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+        let mut lines = BufReader::new(file).lines().enumerate();
+
+        let timezone = self.validate()?;
+        let mut rows = Vec::new();
+        let mut delimiter = self.delimiter;
+
+        if self.has_header {
+            lines.next();
+        }
+
+        for (index, line) in lines {
+            let line = line.map_err(|e| format!("Failed to read file: {}", e))?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let delim = match delimiter {
+                Some(d) => d,
+                None => *delimiter.insert(self.resolve_delimiter(&line)?),
+            };
+
+            rows.push(ParsedRow {
+                line: index + 1,
+                candle: self.parse_line(&line, delim, &timezone),
+            });
+        }
+
+        Ok(rows)
+    }
+}
+
+fn read_sample_lines(file_path: &Path) -> Result<Vec<String>, String> {
+    let file = File::open(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .take(SAMPLE_ROWS + 1)
+        .collect())
+}
+
+fn column_kind(value: &str) -> &'static str {
+    let value = value.trim().trim_matches('"');
+
+    if value.is_empty() {
+        "text"
+    } else if DATE_FORMATS.iter().any(|f| {
+        NaiveDateTime::parse_from_str(value, f).is_ok()
+            || NaiveDate::parse_from_str(value, f).is_ok()
+    }) {
+        "date"
+    } else if NaiveTime::parse_from_str(value, "%H:%M:%S").is_ok()
+        || NaiveTime::parse_from_str(value, "%H:%M").is_ok()
+    {
+        "time"
+    } else if value.len() >= 10 && value.chars().all(|c| c.is_ascii_digit()) {
+        "timestamp"
+    } else if value.replace(',', ".").parse::<f64>().is_ok() {
+        "number"
+    } else {
+        "text"
+    }
+}
+
+// First date format that reads every sampled value, which settles day/month order
+// whenever one of the samples has a day above 12
+fn guess_date_format(values: &[String]) -> Option<String> {
+    if values
+        .iter()
+        .all(|v| v.len() >= 10 && v.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Some(if values.iter().any(|v| v.len() >= 13) {
+            "unix_ms".to_string()
+        } else {
+            "unix".to_string()
+        });
+    }
+
+    DATE_FORMATS
+        .iter()
+        .find(|f| {
+            values.iter().all(|v| {
+                NaiveDateTime::parse_from_str(v, f).is_ok()
+                    || NaiveDate::parse_from_str(v, f).is_ok()
+            })
+        })
+        .map(|f| f.to_string())
+}
+
+fn suggest_mapping(
+    lines: &[String],
+    delimiter: char,
+    looks_like_header: bool,
+) -> Option<ColumnMapping> {
+    let first_line = lines.first()?;
+    let format = CsvFormat::detect(first_line);
+
+    // The generic layout assumes a header and guesses its own delimiter, trust the sample instead
+    let (layout, has_header) = if format == CsvFormat::Generic {
+        (
+            CsvLayout::with_delimiter(first_line, delimiter),
+            looks_like_header,
+        )
+    } else {
+        (format.layout(first_line).ok()?, format.has_header())
+    };
+
+    let dates: Vec<String> = lines
+        .iter()
+        .skip(if has_header { 1 } else { 0 })
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(delimiter).map(str::trim).collect();
+            let date = fields.get(layout.date_col)?;
+            Some(match layout.time_col.and_then(|col| fields.get(col)) {
+                Some(time) => format!("{} {}", date, time),
+                None => date.to_string(),
+            })
+        })
+        .collect();
+
+    // "1,0701" prices next to a non-comma delimiter
+    let sample = lines.get(if has_header { 1 } else { 0 })?;
+    let close = sample.split(delimiter).nth(layout.close_col)?.trim();
+    let decimal_separator = if delimiter != ',' && close.contains(',') && !close.contains('.') {
+        ','
+    } else {
+        '.'
+    };
+
+    Some(ColumnMapping {
+        delimiter: Some(delimiter),
+        has_header,
+        date_col: layout.date_col,
+        time_col: layout.time_col,
+        date_format: guess_date_format(&dates)?,
+        open_col: layout.open_col,
+        high_col: layout.high_col,
+        low_col: layout.low_col,
+        close_col: layout.close_col,
+        volume_col: layout.volume_col,
+        timezone: None,
+        decimal_separator,
+    })
+}
+
+// First step of the mapping wizard: raw sample rows, what each column looks like,
+// which registry formats accept the file, and a suggested mapping when one is obvious
+#[tauri::command]
+pub async fn sample_csv_file(file_path: String) -> Result<CsvSample, String> {
+    let path = Path::new(&file_path);
+    let lines = read_sample_lines(path)?;
+
+    let Some(first_line) = lines.first() else {
+        return Err("File is empty".to_string());
+    };
+
+    let delimiter = detect_delimiter(first_line).or_else(|| {
+        [',', ';', '\t', '|']
+            .into_iter()
+            .max_by_key(|d| first_line.matches(*d).count())
+            .filter(|d| first_line.contains(*d))
+    });
+
+    let split = |line: &String| -> Vec<String> {
+        match delimiter {
+            Some(d) => line.split(d).map(|f| f.trim().to_string()).collect(),
+            None => vec![line.trim().to_string()],
+        }
+    };
+
+    let first_fields = split(first_line);
+    let looks_like_header = first_fields.iter().all(|f| column_kind(f) == "text");
+
+    let headers = if looks_like_header {
+        Some(first_fields)
+    } else {
+        None
+    };
+
+    let rows: Vec<Vec<String>> = lines
+        .iter()
+        .skip(if looks_like_header { 1 } else { 0 })
+        .map(split)
+        .collect();
+
+    let column_kinds = rows
+        .first()
+        .map(|row| row.iter().map(|v| column_kind(v).to_string()).collect())
+        .unwrap_or_default();
+
+    let suggested = delimiter.and_then(|d| suggest_mapping(&lines, d, looks_like_header));
+
+    Ok(CsvSample {
+        success: true,
+        message: format!(
+            "{} sample row{}{}",
+            rows.len(),
+            if rows.len() == 1 { "" } else { "s" },
+            if suggested.is_some() {
+                ", mapping suggested"
+            } else {
+                ""
+            }
+        ),
+        delimiter,
+        headers,
+        rows,
+        column_kinds,
+        candidates: CsvFormat::candidates(first_line),
+        suggested,
+    })
+}
+
+// Apply a mapping to the sample rows so the user can check it before importing
+#[tauri::command]
+pub async fn preview_column_mapping(
+    file_path: String,
+    mapping: ColumnMapping,
+) -> Result<MappingPreview, String> {
+    let lines = read_sample_lines(Path::new(&file_path))?;
+
+    let Some(first_line) = lines.first() else {
+        return Err("File is empty".to_string());
+    };
+
+    let timezone = mapping.validate()?;
+    let delimiter = mapping.resolve_delimiter(first_line)?;
+
+    let mut rows = Vec::new();
+    let mut errors = Vec::new();
+
+    let skip = if mapping.has_header { 1 } else { 0 };
+    for (index, line) in lines.iter().enumerate().skip(skip) {
+        match mapping.parse_line(line, delimiter, &timezone) {
+            Ok(candle) => rows.push(candle),
+            Err(e) => errors.push(format!("Row {}: {}", index + 1, e)),
+        }
+    }
+
+    Ok(MappingPreview {
+        success: errors.is_empty() && !rows.is_empty(),
+        message: format!(
+            "{} of {} rows parsed",
+            rows.len(),
+            rows.len() + errors.len()
+        ),
+        rows,
+        errors,
+    })
+}
+
+// The SQL plugin's settings.db, so backend data lives next to the frontend settings
+pub fn open_settings_db(app_handle: &AppHandle) -> Result<Connection, String> {
+    let config_dir = app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to get app config directory: {}", e))?;
+
+    std::fs::create_dir_all(&config_dir)
+        .map_err(|e| format!("Failed to create config directory: {}", e))?;
+
+    Connection::open(config_dir.join("settings.db"))
+        .map_err(|e| format!("Failed to open settings database: {}", e))
+}
+
+// Same schema as the v2 migration, in case the frontend has not loaded the db yet
+pub fn create_profiles_table(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS import_profiles (
+            name TEXT PRIMARY KEY,
+            mapping TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        )",
+        [],
+    )
+    .map_err(|e| format!("Failed to create import_profiles table: {}", e))?;
+
+    Ok(())
+}
+
+fn open_profiles_db(app_handle: &AppHandle) -> Result<Connection, String> {
+    let conn = open_settings_db(app_handle)?;
+    create_profiles_table(&conn)?;
+
+    Ok(conn)
+}
+
+pub fn load_import_profile(app_handle: &AppHandle, name: &str) -> Result<ColumnMapping, String> {
+    let conn = open_profiles_db(app_handle)?;
+
+    let mapping: String = conn
+        .query_row(
+            "SELECT mapping FROM import_profiles WHERE name = ?1",
+            params![name],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Import profile '{}' not found", name))?;
+
+    serde_json::from_str(&mapping).map_err(|e| format!("Failed to parse import profile: {}", e))
+}
+
+pub fn read_import_profiles(conn: &Connection) -> Result<Vec<ImportProfile>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name, mapping, updated_at FROM import_profiles ORDER BY name")
+        .map_err(|e| format!("Failed to read import profiles: {}", e))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to read import profiles: {}", e))?;
+
+    let mut profiles = Vec::new();
+
+    for row in rows {
+        let (name, mapping, updated_at) =
+            row.map_err(|e| format!("Failed to read import profile: {}", e))?;
+
+        match serde_json::from_str(&mapping) {
+            Ok(mapping) => profiles.push(ImportProfile {
+                name,
+                mapping,
+                updated_at,
+            }),
+            Err(e) => println!("✗ Skipping import profile '{}': {}", name, e),
+        }
+    }
+
+    Ok(profiles)
+}
+
+// Insert or overwrite a profile whose mapping has been validated
+pub fn write_import_profile(
+    conn: &Connection,
+    name: &str,
+    mapping: &ColumnMapping,
+) -> Result<(), String> {
+    let json = serde_json::to_string(mapping)
+        .map_err(|e| format!("Failed to serialize mapping: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO import_profiles (name, mapping, updated_at)
+         VALUES (?1, ?2, CURRENT_TIMESTAMP)
+         ON CONFLICT(name) DO UPDATE SET
+         mapping = ?2,
+         updated_at = CURRENT_TIMESTAMP",
+        params![name, json],
+    )
+    .map_err(|e| format!("Failed to save import profile: {}", e))?;
+
+    Ok(())
+}
+
+// Whether a profile of that name was there to delete
+pub fn remove_import_profile(conn: &Connection, name: &str) -> Result<bool, String> {
+    let deleted = conn
+        .execute("DELETE FROM import_profiles WHERE name = ?1", params![name])
+        .map_err(|e| format!("Failed to delete import profile: {}", e))?;
+
+    Ok(deleted > 0)
+}
+
+#[tauri::command]
+pub async fn list_import_profiles(app_handle: AppHandle) -> Result<Vec<ImportProfile>, String> {
+    read_import_profiles(&open_profiles_db(&app_handle)?)
+}
+
+#[tauri::command]
+pub async fn save_import_profile(
+    app_handle: AppHandle,
+    name: String,
+    mapping: ColumnMapping,
+) -> Result<ProfileResult, String> {
+    let name = name.trim().to_string();
+
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+
+    mapping.validate()?;
+    write_import_profile(&open_profiles_db(&app_handle)?, &name, &mapping)?;
+
+    Ok(ProfileResult {
+        success: true,
+        message: format!("Import profile '{}' saved", name),
+        name,
+    })
+}
+
+#[tauri::command]
+pub async fn delete_import_profile(
+    app_handle: AppHandle,
+    name: String,
+) -> Result<ProfileResult, String> {
+    if !remove_import_profile(&open_profiles_db(&app_handle)?, &name)? {
+        return Err(format!("Import profile '{}' not found", name));
+    }
+
+    Ok(ProfileResult {
+        success: true,
+        message: format!("Import profile '{}' deleted", name),
+        name,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|r| r.to_string()).collect()
+    }
+
+    fn mapping(date_format: &str, decimal_separator: char) -> ColumnMapping {
+        ColumnMapping {
+            delimiter: Some(';'),
+            has_header: false,
+            date_col: 0,
+            time_col: None,
+            date_format: date_format.to_string(),
+            open_col: 1,
+            high_col: 2,
+            low_col: 3,
+            close_col: 4,
+            volume_col: Some(5),
+            timezone: None,
+            decimal_separator,
+        }
+    }
+
+    #[test]
+    fn date_formats_are_guessed_from_every_sample() {
+        // Ambiguous on its own, the 13th settles day before month
+        let dates = lines(&["02/01/2023 17:00", "13/01/2023 17:00"]);
+        assert_eq!(guess_date_format(&dates).as_deref(), Some("%d/%m/%Y %H:%M"));
+
+        let dates = lines(&["01/02/2023 17:00:00", "01/13/2023 17:00:00"]);
+        assert_eq!(
+            guess_date_format(&dates).as_deref(),
+            Some("%m/%d/%Y %H:%M:%S")
+        );
+
+        assert_eq!(
+            guess_date_format(&lines(&["2023.01.02"])).as_deref(),
+            Some("%Y.%m.%d")
+        );
+        assert_eq!(
+            guess_date_format(&lines(&["1672678800", "1672682400"])).as_deref(),
+            Some("unix")
+        );
+        assert_eq!(
+            guess_date_format(&lines(&["1672678800000"])).as_deref(),
+            Some("unix_ms")
+        );
+        assert_eq!(guess_date_format(&lines(&["yesterday"])), None);
+    }
+
+    #[test]
+    fn comma_decimals_are_parsed() {
+        let comma = mapping("%d.%m.%Y", ',');
+        assert_eq!(comma.parse_number("1,0701"), Some(1.0701));
+        assert_eq!(comma.parse_number("\"1.234,56\""), Some(1234.56));
+        assert_eq!(
+            mapping("%d.%m.%Y", '.').parse_number("1.0701"),
+            Some(1.0701)
+        );
+
+        let candle = comma
+            .parse_line(
+                "02.01.2023;1,0701;1,0710;1,0695;1,0705;1.250",
+                ';',
+                &SourceTimezone::Utc,
+            )
+            .unwrap();
+        assert_eq!(candle.timestamp, 1672617600);
+        assert_eq!(
+            (candle.open, candle.high, candle.low, candle.close),
+            (1.0701, 1.071, 1.0695, 1.0705)
+        );
+        assert_eq!(candle.volume, 1250.0);
+
+        let error = comma
+            .parse_line("02.01.2023;1,07;x;1,06;1,07;1", ';', &SourceTimezone::Utc)
+            .unwrap_err();
+        assert_eq!(error, "invalid high 'x'");
+    }
+
+    #[test]
+    fn decimal_separator_cannot_be_the_delimiter() {
+        let mut clash = mapping("%d.%m.%Y", ',');
+        clash.delimiter = Some(',');
+        assert!(clash
+            .validate()
+            .unwrap_err()
+            .contains("cannot also be the delimiter"));
+
+        // Also caught when the delimiter is only detected from the file
+        clash.delimiter = None;
+        assert!(clash.validate().is_ok());
+        assert!(clash.resolve_delimiter("02.01.2023,1,1,1,1,1").is_err());
+    }
+
+    #[test]
+    fn headerless_files_get_a_suggested_mapping() {
+        let sample = lines(&[
+            "02/01/2023 17:00;1,0701;1,0710;1,0695;1,0705;120",
+            "13/01/2023 17:00;1,0705;1,0720;1,0700;1,0712;95",
+        ]);
+
+        let suggested = suggest_mapping(&sample, ';', false).unwrap();
+        assert!(!suggested.has_header);
+        assert_eq!(suggested.date_format, "%d/%m/%Y %H:%M");
+        assert_eq!(suggested.decimal_separator, ',');
+        assert_eq!((suggested.date_col, suggested.time_col), (0, None));
+        assert_eq!(
+            (
+                suggested.open_col,
+                suggested.high_col,
+                suggested.low_col,
+                suggested.close_col,
+                suggested.volume_col
+            ),
+            (1, 2, 3, 4, Some(5))
+        );
+
+        // The suggestion reads its own sample
+        let timezone = suggested.validate().unwrap();
+        let candle = suggested.parse_line(&sample[1], ';', &timezone).unwrap();
+        assert_eq!(candle.timestamp, 1673629200);
+        assert_eq!(candle.close, 1.0712);
+
+        // MT4 exports are headerless with a separate time column
+        let mt4 = lines(&["2023.01.02,17:00,1.0701,1.0710,1.0695,1.0705,120"]);
+        let suggested = suggest_mapping(&mt4, ',', false).unwrap();
+        assert!(!suggested.has_header);
+        assert_eq!(suggested.time_col, Some(1));
+        assert_eq!(suggested.date_format, "%Y.%m.%d %H:%M");
+        assert_eq!(suggested.decimal_separator, '.');
+    }
+}
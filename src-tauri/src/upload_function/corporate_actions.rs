@@ -0,0 +1,300 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use super::candles::Candle;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Dividend {
+    pub timestamp: i64, // Ex-dividend date
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Split {
+    pub timestamp: i64,
+    pub numerator: f64, // 4:1 split => numerator 4, denominator 1
+    pub denominator: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CorporateActions {
+    pub dividends: Vec<Dividend>,
+    pub splits: Vec<Split>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceAdjustment {
+    Raw,
+    SplitAdjusted,
+    FullyAdjusted,
+}
+
+impl CorporateActions {
+    pub fn is_empty(&self) -> bool {
+        self.dividends.is_empty() && self.splits.is_empty()
+    }
+
+    // Merge events from another response, keeping one entry per date
+    pub fn merge(&mut self, other: CorporateActions) {
+        for dividend in other.dividends {
+            if !self
+                .dividends
+                .iter()
+                .any(|d| d.timestamp == dividend.timestamp)
+            {
+                self.dividends.push(dividend);
+            }
+        }
+
+        for split in other.splits {
+            if !self.splits.iter().any(|s| s.timestamp == split.timestamp) {
+                self.splits.push(split);
+            }
+        }
+
+        self.dividends.sort_by_key(|d| d.timestamp);
+        self.splits.sort_by_key(|s| s.timestamp);
+    }
+}
+
+fn events_path(app_dir: &Path, symbol: &str) -> PathBuf {
+    app_dir.join("data").join(symbol).join("events.json")
+}
+
+pub fn load_corporate_actions(
+    app_dir: &Path,
+    symbol: &str,
+) -> Result<Option<CorporateActions>, String> {
+    let path = events_path(app_dir, symbol);
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read events file: {}", e))?;
+
+    let actions: CorporateActions = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse events file: {}", e))?;
+
+    Ok(Some(actions))
+}
+
+pub fn save_corporate_actions(
+    app_dir: &Path,
+    symbol: &str,
+    actions: &CorporateActions,
+) -> Result<(), String> {
+    let path = events_path(app_dir, symbol);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create data directory: {}", e))?;
+    }
+
+    let json_content = serde_json::to_string_pretty(actions)
+        .map_err(|e| format!("Failed to serialize events: {}", e))?;
+
+    let mut file =
+        File::create(path).map_err(|e| format!("Failed to create events file: {}", e))?;
+
+    file.write_all(json_content.as_bytes())
+        .map_err(|e| format!("Failed to write events file: {}", e))?;
+
+    Ok(())
+}
+
+// Stored Yahoo candles are already split-adjusted (that is what the chart API returns),
+// so `Raw` scales bars before each split back up and `FullyAdjusted` also applies the
+// dividend factor. Dividend factors use the stored adjclose column when present and
+// otherwise the usual `1 - dividend / previous close` for every bar before the ex-date.
+pub fn apply_adjustment(
+    candles: &mut [Candle],
+    actions: &CorporateActions,
+    adjustment: PriceAdjustment,
+) {
+    match adjustment {
+        PriceAdjustment::SplitAdjusted => {}
+        PriceAdjustment::Raw => {
+            for split in &actions.splits {
+                if split.numerator <= 0.0 || split.denominator <= 0.0 {
+                    continue;
+                }
+                let ratio = split.numerator / split.denominator;
+
+                for candle in candles.iter_mut().filter(|c| c.timestamp < split.timestamp) {
+                    candle.open *= ratio;
+                    candle.high *= ratio;
+                    candle.low *= ratio;
+                    candle.close *= ratio;
+                    candle.volume /= ratio;
+                }
+            }
+        }
+        PriceAdjustment::FullyAdjusted => {
+            let has_adj_close = candles.iter().any(|c| c.adj_close.is_some());
+
+            let factors: Vec<f64> = if has_adj_close {
+                candles
+                    .iter()
+                    .map(|c| match c.adj_close {
+                        Some(adj) if c.close != 0.0 => adj / c.close,
+                        _ => 1.0,
+                    })
+                    .collect()
+            } else {
+                let mut factors = vec![1.0; candles.len()];
+
+                for dividend in &actions.dividends {
+                    // Close of the last bar before the ex-date
+                    let Some(prev_close) = candles
+                        .iter()
+                        .rev()
+                        .find(|c| c.timestamp < dividend.timestamp)
+                        .map(|c| c.close)
+                    else {
+                        continue;
+                    };
+
+                    if prev_close <= 0.0 {
+                        continue;
+                    }
+
+                    let factor = 1.0 - dividend.amount / prev_close;
+
+                    for (candle, f) in candles.iter().zip(factors.iter_mut()) {
+                        if candle.timestamp < dividend.timestamp {
+                            *f *= factor;
+                        }
+                    }
+                }
+
+                factors
+            };
+
+            for (candle, factor) in candles.iter_mut().zip(factors) {
+                candle.open *= factor;
+                candle.high *= factor;
+                candle.low *= factor;
+                candle.close *= factor;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(timestamp: i64, close: f64, adj_close: Option<f64>) -> Candle {
+        Candle {
+            timestamp,
+            open: close,
+            high: close + 1.0,
+            low: close - 1.0,
+            close,
+            volume: 100.0,
+            adj_close,
+        }
+    }
+
+    fn closes(candles: &[Candle]) -> Vec<f64> {
+        candles.iter().map(|c| c.close).collect()
+    }
+
+    fn split(timestamp: i64, numerator: f64, denominator: f64) -> CorporateActions {
+        CorporateActions {
+            dividends: Vec::new(),
+            splits: vec![Split {
+                timestamp,
+                numerator,
+                denominator,
+            }],
+        }
+    }
+
+    #[test]
+    fn raw_prices_undo_a_split() {
+        // Yahoo serves split-adjusted bars, so bars before a 2:1 split are doubled back
+        let actions = split(300, 2.0, 1.0);
+        let series = || {
+            vec![
+                candle(100, 50.0, None),
+                candle(200, 51.0, None),
+                candle(300, 52.0, None),
+            ]
+        };
+
+        let mut raw = series();
+        apply_adjustment(&mut raw, &actions, PriceAdjustment::Raw);
+        assert_eq!(closes(&raw), vec![100.0, 102.0, 52.0]);
+        assert_eq!((raw[0].high, raw[0].low), (102.0, 98.0));
+        assert_eq!(raw[0].volume, 50.0);
+        assert_eq!(raw[2].volume, 100.0);
+
+        let mut adjusted = series();
+        apply_adjustment(&mut adjusted, &actions, PriceAdjustment::SplitAdjusted);
+        assert_eq!(closes(&adjusted), closes(&series()));
+
+        // Malformed ratios are ignored rather than zeroing prices
+        let mut ignored = series();
+        apply_adjustment(&mut ignored, &split(300, 0.0, 1.0), PriceAdjustment::Raw);
+        assert_eq!(closes(&ignored), closes(&series()));
+    }
+
+    #[test]
+    fn fully_adjusted_prices_follow_adjclose() {
+        let mut candles = vec![
+            candle(100, 100.0, Some(98.0)),
+            candle(200, 50.0, Some(50.0)),
+        ];
+
+        // The dividend is ignored, adjclose already accounts for it
+        let actions = CorporateActions {
+            dividends: vec![Dividend {
+                timestamp: 200,
+                amount: 10.0,
+            }],
+            splits: Vec::new(),
+        };
+        apply_adjustment(&mut candles, &actions, PriceAdjustment::FullyAdjusted);
+
+        assert_eq!(closes(&candles), vec![98.0, 50.0]);
+        assert_eq!((candles[0].open, candles[0].high), (98.0, 98.98));
+        assert_eq!(candles[0].volume, 100.0);
+    }
+
+    #[test]
+    fn fully_adjusted_prices_from_dividends() {
+        let mut candles = vec![
+            candle(100, 40.0, None),
+            candle(200, 50.0, None),
+            candle(300, 50.0, None),
+            candle(400, 25.0, None),
+        ];
+        let actions = CorporateActions {
+            dividends: vec![
+                // 1 / 50 off everything before 300, then 0.5 / 50 before 400
+                Dividend {
+                    timestamp: 300,
+                    amount: 1.0,
+                },
+                Dividend {
+                    timestamp: 400,
+                    amount: 0.5,
+                },
+            ],
+            splits: Vec::new(),
+        };
+
+        apply_adjustment(&mut candles, &actions, PriceAdjustment::FullyAdjusted);
+
+        let expected = [40.0 * 0.98 * 0.99, 50.0 * 0.98 * 0.99, 50.0 * 0.99, 25.0];
+        for (candle, expected) in candles.iter().zip(expected) {
+            assert!((candle.close - expected).abs() < 1e-9, "{}", candle.close);
+        }
+    }
+}
@@ -0,0 +1,566 @@
+use chrono::{Datelike, NaiveDate, Timelike};
+use reqwest;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::candles::{resample, timeframe_seconds, write_candles, Candle};
+use super::local_upload::{load_symbol_data, SymbolData, SymbolMetadata, TimeframeInfo};
+use super::merge::{finish_import, import_target, MergePolicy, MergeReport};
+use super::ticks::{store_ticks, TickRecord, TICK_FLAG_ASK, TICK_FLAG_BID};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DukascopyRequest {
+    pub instrument: String, // Dukascopy instrument code, e.g. EURUSD or XAUUSD
+    pub save_as: String,
+    pub start_date: String,
+    pub end_date: String,
+    pub timeframes: Vec<String>,
+    #[serde(default)]
+    pub local_dir: Option<String>, // Read .bi5 files from disk instead of downloading
+    #[serde(default)]
+    pub point_scale: Option<f64>, // Overrides the per-instrument default divisor
+    #[serde(default)]
+    pub keep_ticks: bool, // Also store the raw bid/ask ticks
+    #[serde(default)]
+    pub merge: Option<MergePolicy>, // Splice into an existing symbol instead of refusing it
+}
+
+#[derive(Debug, Serialize)]
+pub struct DukascopyResult {
+    pub success: bool,
+    pub message: String,
+    pub symbol: String,
+    pub timeframes_downloaded: Vec<String>,
+    pub total_candles: usize,
+    pub total_ticks: usize,
+    pub hours_missing: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge: Option<MergeReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Tick {
+    pub timestamp_ms: i64,
+    pub bid: f64,
+    pub ask: f64,
+    pub bid_volume: f64,
+    pub ask_volume: f64,
+}
+
+const DATAFEED_URL: &str = "https://datafeed.dukascopy.com/datafeed";
+
+// Each tick record is 20 bytes, big-endian:
+// u32 ms since the hour, u32 ask points, u32 bid points, f32 ask volume, f32 bid volume
+const TICK_RECORD_SIZE: usize = 20;
+
+const MS_PER_HOUR: i64 = 3_600_000;
+
+// Prices are stored as integers, divided by 10^digits of the instrument.
+// JPY crosses, metals and most indices use 3 digits, the rest of FX uses 5.
+fn default_point_scale(instrument: &str) -> f64 {
+    let upper = instrument.to_uppercase();
+
+    if upper.starts_with("BTC") || upper.starts_with("ETH") {
+        1e1
+    } else if upper.contains("JPY")
+        || upper.starts_with("XAU")
+        || upper.starts_with("XAG")
+        || upper.ends_with("IDXUSD")
+        || upper.ends_with("IDXEUR")
+        || upper.ends_with("IDXGBP")
+        || upper.ends_with("IDXJPY")
+        || upper.ends_with("CMDUSD")
+    {
+        1e3
+    } else {
+        1e5
+    }
+}
+
+fn convert_timeframe_to_display(timeframe: &str) -> String {
+    match timeframe {
+        "M1" => "1 min".to_string(),
+        "M5" => "5 min".to_string(),
+        "M15" => "15 min".to_string(),
+        "M30" => "30 min".to_string(),
+        "H1" => "1 hour".to_string(),
+        "H4" => "4 hours".to_string(),
+        "D1" => "1 day".to_string(),
+        "W1" => "1 week".to_string(),
+        "MN1" => "1 month".to_string(),
+        _ => timeframe.to_string(),
+    }
+}
+
+// Decode one hourly .bi5 file. Weekend and holiday hours come back as empty files.
+fn decode_bi5(bytes: &[u8], hour_start_ms: i64, point_scale: f64) -> Result<Vec<Tick>, String> {
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut raw = Vec::new();
+    lzma_rs::lzma_decompress(&mut Cursor::new(bytes), &mut raw)
+        .map_err(|e| format!("Failed to decompress .bi5 data: {:?}", e))?;
+
+    let ticks = raw
+        .chunks_exact(TICK_RECORD_SIZE)
+        .map(|record| {
+            let u32_at = |i: usize| {
+                u32::from_be_bytes([record[i], record[i + 1], record[i + 2], record[i + 3]])
+            };
+            let f32_at = |i: usize| {
+                f32::from_be_bytes([record[i], record[i + 1], record[i + 2], record[i + 3]])
+            };
+
+            Tick {
+                timestamp_ms: hour_start_ms + u32_at(0) as i64,
+                ask: u32_at(4) as f64 / point_scale,
+                bid: u32_at(8) as f64 / point_scale,
+                ask_volume: f32_at(12) as f64,
+                bid_volume: f32_at(16) as f64,
+            }
+        })
+        .collect();
+
+    Ok(ticks)
+}
+
+// Dukascopy months are zero-based in the URL: January is "00"
+fn hour_url(instrument: &str, hour_start_ms: i64) -> Result<String, String> {
+    let dt = chrono::DateTime::from_timestamp_millis(hour_start_ms).ok_or("Invalid timestamp")?;
+
+    Ok(format!(
+        "{}/{}/{}/{:02}/{:02}/{:02}h_ticks.bi5",
+        DATAFEED_URL,
+        instrument,
+        dt.year(),
+        dt.month0(),
+        dt.day(),
+        dt.hour()
+    ))
+}
+
+async fn download_ticks(
+    instrument: &str,
+    start_ms: i64,
+    end_ms: i64,
+    point_scale: f64,
+) -> Result<(Vec<Tick>, usize), String> {
+    let client = reqwest::Client::new();
+    let mut ticks = Vec::new();
+    let mut hours_missing = 0;
+    let mut hour = start_ms;
+
+    while hour < end_ms {
+        let url = hour_url(instrument, hour)?;
+
+        let response = client
+            .get(&url)
+            .header("User-Agent", "TraderAssist")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+
+        let status = response.status();
+
+        if status.as_u16() == 404 {
+            // Not published (yet), treat like an empty hour
+            hours_missing += 1;
+        } else if !status.is_success() {
+            return Err(format!("Datafeed returned error: {} for {}", status, url));
+        } else {
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to read {}: {}", url, e))?;
+
+            ticks.extend(decode_bi5(&bytes, hour, point_scale)?);
+        }
+
+        if (hour - start_ms) % (24 * MS_PER_HOUR) == 0 {
+            let day = chrono::DateTime::from_timestamp_millis(hour)
+                .map(|d| d.format("%Y-%m-%d").to_string())
+                .unwrap_or_default();
+            println!("Fetching {} ticks for {}...", instrument, day);
+        }
+
+        hour += MS_PER_HOUR;
+    }
+
+    Ok((ticks, hours_missing))
+}
+
+// Hour start of a local file laid out like the datafeed: .../YYYY/MM/DD/HHh_ticks.bi5
+fn local_hour_start(path: &Path) -> Option<i64> {
+    let hour: u32 = path
+        .file_name()?
+        .to_str()?
+        .strip_suffix("h_ticks.bi5")?
+        .parse()
+        .ok()?;
+    let day_dir = path.parent()?;
+    let month_dir = day_dir.parent()?;
+    let year_dir = month_dir.parent()?;
+
+    let day: u32 = day_dir.file_name()?.to_str()?.parse().ok()?;
+    let month0: u32 = month_dir.file_name()?.to_str()?.parse().ok()?;
+    let year: i32 = year_dir.file_name()?.to_str()?.parse().ok()?;
+
+    let date = NaiveDate::from_ymd_opt(year, month0 + 1, day)?;
+    Some(date.and_hms_opt(hour, 0, 0)?.and_utc().timestamp_millis())
+}
+
+fn collect_bi5_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_bi5_files(&path, files)?;
+        } else if path.extension().and_then(|s| s.to_str()) == Some("bi5") {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_local_ticks(
+    dir: &Path,
+    start_ms: i64,
+    end_ms: i64,
+    point_scale: f64,
+) -> Result<(Vec<Tick>, usize), String> {
+    if !dir.is_dir() {
+        return Err(format!("Directory not found: {}", dir.display()));
+    }
+
+    let mut files = Vec::new();
+    collect_bi5_files(dir, &mut files)?;
+
+    let mut hours: Vec<(i64, PathBuf)> = files
+        .into_iter()
+        .filter_map(|path| local_hour_start(&path).map(|hour| (hour, path)))
+        .filter(|(hour, _)| *hour >= start_ms && *hour < end_ms)
+        .collect();
+    hours.sort_by_key(|(hour, _)| *hour);
+
+    if hours.is_empty() {
+        return Err(
+            "No .bi5 files found in the date range (expected YYYY/MM/DD/HHh_ticks.bi5)".to_string(),
+        );
+    }
+
+    let expected_hours = ((end_ms - start_ms) / MS_PER_HOUR) as usize;
+    let hours_missing = expected_hours.saturating_sub(hours.len());
+
+    let mut ticks = Vec::new();
+    for (hour, path) in hours {
+        let bytes = fs::read(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+
+        let decoded = decode_bi5(&bytes, hour, point_scale)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        ticks.extend(decoded);
+    }
+
+    Ok((ticks, hours_missing))
+}
+
+// One-minute bid candles; tick volume is the traded bid volume (in millions of units)
+fn build_minute_candles(ticks: &[Tick]) -> Vec<Candle> {
+    let mut candles: Vec<Candle> = Vec::new();
+
+    for tick in ticks {
+        let minute = tick.timestamp_ms.div_euclid(60_000) * 60;
+
+        match candles.last_mut() {
+            Some(candle) if candle.timestamp == minute => {
+                candle.high = candle.high.max(tick.bid);
+                candle.low = candle.low.min(tick.bid);
+                candle.close = tick.bid;
+                candle.volume += tick.bid_volume;
+            }
+            _ => candles.push(Candle {
+                timestamp: minute,
+                open: tick.bid,
+                high: tick.bid,
+                low: tick.bid,
+                close: tick.bid,
+                volume: tick.bid_volume,
+                adj_close: None,
+            }),
+        }
+    }
+
+    candles
+}
+
+fn convert_date_to_millis(date_str: &str) -> Result<i64, String> {
+    let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date format: {}", e))?;
+
+    let datetime = date
+        .and_hms_opt(0, 0, 0)
+        .ok_or("Failed to create datetime")?;
+    Ok(datetime.and_utc().timestamp_millis())
+}
+
+#[tauri::command]
+pub async fn fetch_dukascopy_data_command(
+    app_handle: AppHandle,
+    request: DukascopyRequest,
+) -> Result<DukascopyResult, String> {
+    let save_as = request.save_as.to_uppercase();
+    let instrument = request.instrument.trim().to_uppercase();
+
+    // Validate symbol name
+    if save_as.is_empty() {
+        return Err("Symbol name cannot be empty".to_string());
+    }
+
+    if save_as.contains(|c: char| !c.is_alphanumeric() && c != '_') {
+        return Err("Symbol name can only contain letters, numbers, and underscores".to_string());
+    }
+
+    if instrument.is_empty() {
+        return Err("Dukascopy instrument cannot be empty".to_string());
+    }
+
+    for tf in &request.timeframes {
+        if tf != "MN1" && timeframe_seconds(tf).is_none() {
+            return Err(format!("Unsupported timeframe: {}", tf));
+        }
+    }
+
+    let start_ms = convert_date_to_millis(&request.start_date)?;
+    let end_ms = convert_date_to_millis(&request.end_date)?;
+
+    if end_ms <= start_ms {
+        return Err("End date must be after start date".to_string());
+    }
+
+    let point_scale = match request.point_scale {
+        Some(scale) if scale > 0.0 => scale,
+        Some(_) => return Err("Point scale must be positive".to_string()),
+        None => default_point_scale(&instrument),
+    };
+
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    // Existing symbols are only written to through a merge
+    let target = import_target(&app_handle, &save_as, request.merge)?;
+
+    let (ticks, hours_missing) = match request.local_dir.as_deref() {
+        Some(dir) if !dir.trim().is_empty() => {
+            read_local_ticks(Path::new(dir), start_ms, end_ms, point_scale)?
+        }
+        _ => download_ticks(&instrument, start_ms, end_ms, point_scale).await?,
+    };
+
+    if ticks.is_empty() {
+        return Err("No ticks found in the selected range".to_string());
+    }
+
+    println!(
+        "✓ {} ticks decoded ({} hours missing)",
+        ticks.len(),
+        hours_missing
+    );
+
+    let minute_candles = build_minute_candles(&ticks);
+
+    let data_dir = app_dir.join("data").join(&target);
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    let mut timeframe_infos = Vec::new();
+    let mut total_candles = 0;
+    let mut downloaded_timeframes = Vec::new();
+
+    for tf in &request.timeframes {
+        let candles = if tf == "M1" {
+            minute_candles.clone()
+        } else {
+            resample(&minute_candles, tf)?
+        };
+
+        let csv_path = data_dir.join(format!("{}.csv", tf));
+        write_candles(&csv_path, &candles)?;
+
+        let candle_count = candles.len();
+
+        timeframe_infos.push(TimeframeInfo {
+            name: tf.clone(),
+            display_name: convert_timeframe_to_display(tf),
+            candle_count,
+            file_path: csv_path.to_string_lossy().to_string(),
+            source_sha: None,
+            format: None,
+            coverage: None,
+            source_timezone: Some("UTC".to_string()), // Epoch timestamps
+        });
+
+        total_candles += candle_count;
+        downloaded_timeframes.push(tf.clone());
+
+        println!("✓ {} - {} candles built", tf, candle_count);
+    }
+
+    if timeframe_infos.is_empty() && !request.keep_ticks {
+        let _ = fs::remove_dir_all(&data_dir);
+        return Err("No timeframes selected".to_string());
+    }
+
+    // Sort timeframes
+    timeframe_infos.sort_by(|a, b| {
+        let order = ["M1", "M5", "M15", "M30", "H1", "H4", "D1", "W1", "MN1"];
+        let a_idx = order.iter().position(|&x| x == a.name).unwrap_or(999);
+        let b_idx = order.iter().position(|&x| x == b.name).unwrap_or(999);
+        a_idx.cmp(&b_idx)
+    });
+
+    let symbol_data = SymbolData {
+        symbol: target.clone(),
+        timeframes: timeframe_infos,
+        total_candles,
+        uploaded_at: chrono::Utc::now().to_rfc3339(),
+        metadata: Some(SymbolMetadata {
+            source: "dukascopy".to_string(),
+            exchange: Some("Dukascopy".to_string()),
+            exchange_timezone: Some("UTC".to_string()),
+            gmt_offset: Some(0),
+            currency: None,
+            instrument_type: None,
+            price_precision: Some(point_scale.log10().round() as u32),
+            regular_session: None,
+        }),
+        ticks: None,
+    };
+
+    save_symbol_data(&app_handle, &symbol_data)?;
+
+    let merge = finish_import(&app_handle, &save_as, &target, request.merge, "dukascopy")?;
+
+    // Ticks always go next to the symbol's own candles, staging is gone after a merge
+    if request.keep_ticks {
+        let mut records: Vec<TickRecord> = ticks
+            .iter()
+            .map(|t| TickRecord {
+                timestamp_ms: t.timestamp_ms,
+                bid: t.bid,
+                ask: t.ask,
+                last: 0.0,
+                volume: t.bid_volume + t.ask_volume,
+                flags: TICK_FLAG_BID | TICK_FLAG_ASK,
+            })
+            .collect();
+
+        let mut symbol_data = load_symbol_data(&app_handle, &save_as)?;
+        symbol_data.ticks = store_ticks(&app_dir, &save_as, &mut records)?;
+        save_symbol_data(&app_handle, &symbol_data)?;
+        println!("✓ Raw ticks saved");
+    }
+
+    Ok(DukascopyResult {
+        success: true,
+        message: format!(
+            "Built {} timeframe{} with {} total candles from {} ticks",
+            downloaded_timeframes.len(),
+            if downloaded_timeframes.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            total_candles,
+            ticks.len()
+        ),
+        symbol: save_as,
+        timeframes_downloaded: downloaded_timeframes,
+        total_candles,
+        total_ticks: ticks.len(),
+        hours_missing,
+        merge,
+    })
+}
+
+fn save_symbol_data(app_handle: &AppHandle, symbol_data: &SymbolData) -> Result<(), String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let symbols_dir = app_dir.join("symbols");
+    fs::create_dir_all(&symbols_dir)
+        .map_err(|e| format!("Failed to create symbols directory: {}", e))?;
+
+    let json_path = symbols_dir.join(format!("{}.json", symbol_data.symbol));
+    let json_content = serde_json::to_string_pretty(symbol_data)
+        .map_err(|e| format!("Failed to serialize symbol data: {}", e))?;
+
+    let mut file =
+        File::create(json_path).map_err(|e| format!("Failed to create JSON file: {}", e))?;
+
+    file.write_all(json_content.as_bytes())
+        .map_err(|e| format!("Failed to write JSON file: {}", e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Two ticks, LZMA "alone" format like the datafeed serves:
+    // (1500 ms, ask 108123, bid 108120, 1.5, 2.25) and (3599999 ms, ask 108200, bid 108190, 0.75, 1.0)
+    const FIXTURE: [u8; 57] = [
+        0x5d, 0x00, 0x00, 0x10, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00,
+        0x00, 0x01, 0x34, 0x8f, 0x84, 0xd8, 0x36, 0x47, 0x45, 0x01, 0x4e, 0xa9, 0x75, 0x62, 0x22,
+        0x3a, 0xf9, 0x90, 0x80, 0x7b, 0x73, 0x2e, 0x95, 0x37, 0x60, 0x63, 0x52, 0x16, 0x1f, 0x16,
+        0x5e, 0x9d, 0x88, 0x2b, 0x58, 0xd6, 0x23, 0x40, 0xfc, 0x76, 0xc3, 0xc0,
+    ];
+
+    fn hour_ms(year: i32, month: u32, day: u32, hour: u32) -> i64 {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, 0, 0))
+            .unwrap()
+            .and_utc()
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn decodes_a_bi5_hour() {
+        let hour = hour_ms(2024, 1, 5, 13);
+        let ticks = decode_bi5(&FIXTURE, hour, 1e5).unwrap();
+
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].timestamp_ms, hour + 1500);
+        assert_eq!(ticks[0].ask, 1.08123);
+        assert_eq!(ticks[0].bid, 1.0812);
+        assert_eq!(ticks[0].ask_volume, 1.5);
+        assert_eq!(ticks[0].bid_volume, 2.25);
+        assert_eq!(ticks[1].timestamp_ms, hour + 3_599_999);
+        assert_eq!(ticks[1].bid, 1.0819);
+
+        // Hours without trading are empty files
+        assert!(decode_bi5(&[], hour, 1e5).unwrap().is_empty());
+        assert!(decode_bi5(&FIXTURE[..20], hour, 1e5).is_err());
+    }
+
+    #[test]
+    fn hour_urls_use_zero_based_months() {
+        assert_eq!(
+            hour_url("EURUSD", hour_ms(2024, 1, 5, 13)).unwrap(),
+            "https://datafeed.dukascopy.com/datafeed/EURUSD/2024/00/05/13h_ticks.bi5"
+        );
+        assert_eq!(
+            hour_url("XAUUSD", hour_ms(2023, 12, 31, 0)).unwrap(),
+            "https://datafeed.dukascopy.com/datafeed/XAUUSD/2023/11/31/00h_ticks.bi5"
+        );
+    }
+}
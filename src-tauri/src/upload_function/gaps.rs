@@ -0,0 +1,515 @@
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveTime, Timelike, Weekday};
+use chrono_tz::Tz;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+use tauri::AppHandle;
+
+use super::candles::{read_candles, timeframe_seconds};
+use super::local_upload::{load_symbol_data, save_symbol_data, SymbolMetadata};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Gap {
+    pub start: i64, // Open time of the first missing bar
+    pub end: i64,   // Open time of the next bar present
+    pub duration_seconds: i64,
+    pub missing_bars: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GapReport {
+    pub timeframe: String,
+    pub calendar: String,
+    pub first_bar: Option<i64>,
+    pub last_bar: Option<i64>,
+    pub actual_bars: usize,
+    pub expected_bars: usize,
+    pub missing_bars: usize,
+    pub coverage_percent: f64,
+    pub gaps: Vec<Gap>, // Chronological, first MAX_REPORTED_GAPS only
+    pub truncated: bool,
+}
+
+// Enough gaps to shade a chart without flooding the frontend
+const MAX_REPORTED_GAPS: usize = 1000;
+
+// When the market is expected to print bars
+enum Hours {
+    AlwaysOpen, // Crypto
+    // FX and CFDs. Closed from Friday 21:00 UTC to Monday 00:00 UTC, which covers both the
+    // UTC close/open (21:00/22:00 depending on DST) and broker files stamped in server time.
+    Weekdays,
+    // Exchange-traded instruments: the regular session in the exchange's timezone, Mon-Fri
+    Session {
+        tz: Tz,
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+}
+
+pub struct TradingCalendar {
+    hours: Hours,
+    holidays: HashSet<NaiveDate>,
+}
+
+impl TradingCalendar {
+    pub fn from_metadata(metadata: Option<&SymbolMetadata>, holidays: &[NaiveDate]) -> Self {
+        let hours = match metadata {
+            Some(m)
+                if m.source == "binance"
+                    || m.instrument_type.as_deref() == Some("CRYPTOCURRENCY") =>
+            {
+                Hours::AlwaysOpen
+            }
+            Some(m) => {
+                let tz = m
+                    .exchange_timezone
+                    .as_deref()
+                    .and_then(|tz| tz.parse::<Tz>().ok());
+                let session = m.regular_session.as_ref().and_then(|s| {
+                    Some((
+                        NaiveTime::parse_from_str(&s.start, "%H:%M").ok()?,
+                        NaiveTime::parse_from_str(&s.end, "%H:%M").ok()?,
+                    ))
+                });
+
+                match (tz, session) {
+                    (Some(tz), Some((start, end))) if start < end => {
+                        Hours::Session { tz, start, end }
+                    }
+                    _ => Hours::Weekdays,
+                }
+            }
+            None => Hours::Weekdays,
+        };
+
+        TradingCalendar {
+            hours,
+            holidays: holidays.iter().copied().collect(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        match &self.hours {
+            Hours::AlwaysOpen => "24/7".to_string(),
+            Hours::Weekdays => "24/5".to_string(),
+            Hours::Session { tz, start, end } => format!(
+                "{}-{} {}",
+                start.format("%H:%M"),
+                end.format("%H:%M"),
+                tz.name()
+            ),
+        }
+    }
+
+    // New Year's Day and Christmas close every non-crypto market, anything else comes from the caller
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        (date.month() == 1 && date.day() == 1)
+            || (date.month() == 12 && date.day() == 25)
+            || self.holidays.contains(&date)
+    }
+
+    // Whether a bar opening at `timestamp` is expected to exist
+    pub fn is_open(&self, timestamp: i64, timeframe: &str) -> bool {
+        // A missing week or month is always a gap
+        if timeframe == "W1" || timeframe == "MN1" {
+            return true;
+        }
+
+        let Some(utc) = DateTime::from_timestamp(timestamp, 0) else {
+            return false;
+        };
+
+        let is_weekday = |weekday: Weekday| !matches!(weekday, Weekday::Sat | Weekday::Sun);
+
+        match &self.hours {
+            Hours::AlwaysOpen => true,
+            Hours::Weekdays => {
+                let date = utc.date_naive();
+                if self.is_holiday(date) {
+                    return false;
+                }
+
+                if timeframe == "D1" {
+                    return is_weekday(utc.weekday());
+                }
+
+                match utc.weekday() {
+                    Weekday::Sat | Weekday::Sun => false,
+                    Weekday::Fri => utc.hour() < 21,
+                    _ => true,
+                }
+            }
+            Hours::Session { tz, start, end } => {
+                let local = utc.with_timezone(tz);
+                let date = local.date_naive();
+
+                if !is_weekday(local.weekday()) || self.is_holiday(date) {
+                    return false;
+                }
+
+                if timeframe == "D1" {
+                    return true;
+                }
+
+                let time = local.time();
+                time >= *start && time < *end
+            }
+        }
+    }
+
+    // Start of the trading day `timestamp` belongs to: the UTC day for 24/7 and 24/5 markets,
+    // the local date's regular open for exchange sessions (pre/post market included)
+    pub fn session_start(&self, timestamp: i64) -> i64 {
+        let day = timestamp.div_euclid(86_400) * 86_400;
+
+        match &self.hours {
+            Hours::AlwaysOpen | Hours::Weekdays => day,
+            Hours::Session { tz, start, .. } => DateTime::from_timestamp(timestamp, 0)
+                .and_then(|utc| {
+                    utc.with_timezone(tz)
+                        .date_naive()
+                        .and_time(*start)
+                        .and_local_timezone(*tz)
+                        .earliest()
+                })
+                .map(|open| open.timestamp())
+                .unwrap_or(day),
+        }
+    }
+}
+
+// Open time of the bar after `timestamp`
+fn next_bar(timestamp: i64, timeframe: &str, step: i64) -> i64 {
+    if timeframe == "MN1" {
+        if let Some(next) = DateTime::from_timestamp(timestamp, 0)
+            .and_then(|dt| dt.checked_add_months(Months::new(1)))
+        {
+            return next.timestamp();
+        }
+    }
+    timestamp + step
+}
+
+// Compare sorted bar times against the cadence of `timeframe` and list the holes that fall
+// inside trading hours
+pub fn find_gaps(
+    timestamps: &[i64],
+    timeframe: &str,
+    calendar: &TradingCalendar,
+) -> Result<GapReport, String> {
+    let step = match timeframe {
+        "MN1" => 30 * 86_400, // Only used for the tolerance below
+        _ => timeframe_seconds(timeframe).ok_or(format!("Unsupported timeframe: {}", timeframe))?,
+    };
+
+    // Bars may drift by an hour against the step (daily bars across a DST change),
+    // so a slot within half a bar of the next actual bar is that bar
+    let tolerance = step / 2;
+
+    let mut gaps = Vec::new();
+    let mut missing_total = 0;
+    let mut truncated = false;
+
+    for pair in timestamps.windows(2) {
+        let (current, next) = (pair[0], pair[1]);
+        let gap_start = next_bar(current, timeframe, step);
+
+        let mut slot = gap_start;
+        let mut missing = 0;
+
+        while next - slot > tolerance {
+            if calendar.is_open(slot, timeframe) {
+                missing += 1;
+            }
+            slot = next_bar(slot, timeframe, step);
+        }
+
+        if missing == 0 {
+            continue;
+        }
+
+        missing_total += missing;
+
+        if gaps.len() < MAX_REPORTED_GAPS {
+            gaps.push(Gap {
+                start: gap_start,
+                end: next,
+                duration_seconds: next - gap_start,
+                missing_bars: missing,
+            });
+        } else {
+            truncated = true;
+        }
+    }
+
+    let actual = timestamps.len();
+    let expected = actual + missing_total;
+
+    Ok(GapReport {
+        timeframe: timeframe.to_string(),
+        calendar: calendar.name(),
+        first_bar: timestamps.first().copied(),
+        last_bar: timestamps.last().copied(),
+        actual_bars: actual,
+        expected_bars: expected,
+        missing_bars: missing_total,
+        coverage_percent: if expected == 0 {
+            0.0
+        } else {
+            (actual as f64 / expected as f64 * 10_000.0).round() / 100.0
+        },
+        gaps,
+        truncated,
+    })
+}
+
+// List the gaps of one or all timeframes of a symbol and store each timeframe's coverage
+#[tauri::command]
+pub async fn analyze_gaps(
+    app_handle: AppHandle,
+    symbol: String,
+    timeframe: Option<String>,
+    holidays: Option<Vec<String>>,
+) -> Result<Vec<GapReport>, String> {
+    let mut symbol_data = load_symbol_data(&app_handle, &symbol)?;
+
+    let holidays = holidays
+        .unwrap_or_default()
+        .iter()
+        .map(|d| {
+            NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d")
+                .map_err(|e| format!("Invalid holiday date '{}': {}", d, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let calendar = TradingCalendar::from_metadata(symbol_data.metadata.as_ref(), &holidays);
+    let mut reports = Vec::new();
+
+    for info in symbol_data
+        .timeframes
+        .iter_mut()
+        .filter(|tf| timeframe.as_ref().is_none_or(|wanted| &tf.name == wanted))
+    {
+        let candles = read_candles(Path::new(&info.file_path), info.format)?;
+        let timestamps: Vec<i64> = candles.iter().map(|c| c.timestamp).collect();
+
+        let report = find_gaps(&timestamps, &info.name, &calendar)?;
+
+        println!(
+            "✓ {} - {:.2}% coverage, {} gap(s)",
+            info.name,
+            report.coverage_percent,
+            report.gaps.len()
+        );
+
+        info.coverage = Some(report.coverage_percent);
+        reports.push(report);
+    }
+
+    if reports.is_empty() {
+        return Err(format!(
+            "Timeframe {} not found for {}",
+            timeframe.unwrap_or_default(),
+            symbol
+        ));
+    }
+
+    save_symbol_data(&app_handle, &symbol_data)?;
+
+    Ok(reports)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::upload_function::local_upload::SessionHours;
+
+    fn ts(text: &str) -> i64 {
+        DateTime::parse_from_rfc3339(text).unwrap().timestamp()
+    }
+
+    fn fx() -> TradingCalendar {
+        TradingCalendar::from_metadata(None, &[])
+    }
+
+    fn new_york() -> TradingCalendar {
+        let metadata = SymbolMetadata {
+            source: "yfinance".to_string(),
+            exchange_timezone: Some("America/New_York".to_string()),
+            regular_session: Some(SessionHours {
+                start: "09:30".to_string(),
+                end: "16:00".to_string(),
+            }),
+            ..Default::default()
+        };
+        TradingCalendar::from_metadata(Some(&metadata), &[])
+    }
+
+    #[test]
+    fn fx_closes_friday_21_utc_until_monday() {
+        let calendar = fx();
+        assert_eq!(calendar.name(), "24/5");
+
+        // 2024-03-08 is a Friday
+        assert!(calendar.is_open(ts("2024-03-08T20:00:00Z"), "H1"));
+        assert!(!calendar.is_open(ts("2024-03-08T21:00:00Z"), "H1"));
+        assert!(!calendar.is_open(ts("2024-03-09T12:00:00Z"), "H1"));
+        assert!(!calendar.is_open(ts("2024-03-10T23:00:00Z"), "H1"));
+        assert!(calendar.is_open(ts("2024-03-11T00:00:00Z"), "H1"));
+
+        // Friday's last bar to Monday's first is not a gap, the hours around it are
+        let report = find_gaps(
+            &[ts("2024-03-08T20:00:00Z"), ts("2024-03-11T00:00:00Z")],
+            "H1",
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 0);
+        assert_eq!(report.coverage_percent, 100.0);
+
+        let report = find_gaps(
+            &[ts("2024-03-08T19:00:00Z"), ts("2024-03-11T01:00:00Z")],
+            "H1",
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 2);
+        assert_eq!(report.expected_bars, 4);
+        assert_eq!(report.coverage_percent, 50.0);
+        assert_eq!(report.gaps[0].start, ts("2024-03-08T20:00:00Z"));
+        assert_eq!(report.gaps[0].end, ts("2024-03-11T01:00:00Z"));
+
+        // Daily bars skip the weekend too
+        let report = find_gaps(
+            &[ts("2024-03-08T00:00:00Z"), ts("2024-03-11T00:00:00Z")],
+            "D1",
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 0);
+    }
+
+    #[test]
+    fn holidays_are_not_gaps() {
+        // 2024-01-01 is a Monday, New Year's Day is always closed
+        let report = find_gaps(
+            &[ts("2023-12-29T20:00:00Z"), ts("2024-01-02T00:00:00Z")],
+            "H1",
+            &fx(),
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 0);
+
+        // Other holidays only when the caller lists them
+        let bars = [ts("2024-07-03T00:00:00Z"), ts("2024-07-05T00:00:00Z")];
+        assert_eq!(find_gaps(&bars, "D1", &fx()).unwrap().missing_bars, 1);
+
+        let july_4th = NaiveDate::from_ymd_opt(2024, 7, 4).unwrap();
+        let calendar = TradingCalendar::from_metadata(None, &[july_4th]);
+        assert_eq!(find_gaps(&bars, "D1", &calendar).unwrap().missing_bars, 0);
+    }
+
+    #[test]
+    fn exchange_sessions_follow_local_time() {
+        let calendar = new_york();
+        assert_eq!(calendar.name(), "09:30-16:00 America/New_York");
+
+        // 09:30 New York is 14:30 UTC in winter and 13:30 UTC in summer
+        assert!(!calendar.is_open(ts("2024-01-09T14:00:00Z"), "M30"));
+        assert!(calendar.is_open(ts("2024-01-09T14:30:00Z"), "M30"));
+        assert!(calendar.is_open(ts("2024-01-09T20:30:00Z"), "M30"));
+        assert!(!calendar.is_open(ts("2024-01-09T21:00:00Z"), "M30"));
+        assert!(!calendar.is_open(ts("2024-07-09T13:00:00Z"), "M30"));
+        assert!(calendar.is_open(ts("2024-07-09T13:30:00Z"), "M30"));
+        assert!(!calendar.is_open(ts("2024-07-09T20:00:00Z"), "M30"));
+
+        // Weekdays are New York's: Saturday 00:00 UTC is still Friday there, Monday 00:00 UTC
+        // is Sunday
+        assert!(!calendar.is_open(ts("2024-01-13T15:00:00Z"), "H1"));
+        assert!(calendar.is_open(ts("2024-01-13T00:00:00Z"), "D1"));
+        assert!(!calendar.is_open(ts("2024-01-15T00:00:00Z"), "D1"));
+
+        // Tuesday's last bar to Wednesday's first is overnight, not a gap
+        let report = find_gaps(
+            &[ts("2024-01-09T20:30:00Z"), ts("2024-01-10T14:30:00Z")],
+            "M30",
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 0);
+
+        let report = find_gaps(
+            &[ts("2024-01-09T20:30:00Z"), ts("2024-01-10T15:30:00Z")],
+            "M30",
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 2);
+        assert_eq!(report.gaps[0].start, ts("2024-01-09T21:00:00Z"));
+    }
+
+    #[test]
+    fn crypto_is_always_open() {
+        let metadata = SymbolMetadata {
+            source: "binance".to_string(),
+            ..Default::default()
+        };
+        let calendar = TradingCalendar::from_metadata(Some(&metadata), &[]);
+        assert_eq!(calendar.name(), "24/7");
+        assert!(calendar.is_open(ts("2024-03-09T12:00:00Z"), "H1"));
+        assert!(calendar.is_open(ts("2024-01-01T00:00:00Z"), "D1"));
+    }
+
+    #[test]
+    fn monthly_bars_step_by_calendar_month() {
+        let months = [
+            ts("2024-01-01T00:00:00Z"),
+            ts("2024-02-01T00:00:00Z"),
+            ts("2024-03-01T00:00:00Z"),
+            ts("2024-04-01T00:00:00Z"),
+        ];
+        let report = find_gaps(&months, "MN1", &fx()).unwrap();
+        assert_eq!(report.missing_bars, 0);
+
+        // Missing March and April, including April's 1st that falls on a Monday
+        let report = find_gaps(
+            &[ts("2024-02-01T00:00:00Z"), ts("2024-05-01T00:00:00Z")],
+            "MN1",
+            &fx(),
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 2);
+        assert_eq!(report.gaps[0].start, ts("2024-03-01T00:00:00Z"));
+    }
+
+    #[test]
+    fn bars_drifting_an_hour_across_dst_are_not_gaps() {
+        let metadata = SymbolMetadata {
+            source: "binance".to_string(),
+            ..Default::default()
+        };
+        let calendar = TradingCalendar::from_metadata(Some(&metadata), &[]);
+
+        // Daily bars move from midnight to 23:00 UTC when the source's clock springs forward
+        let days = [
+            ts("2024-03-29T00:00:00Z"),
+            ts("2024-03-30T00:00:00Z"),
+            ts("2024-03-30T23:00:00Z"),
+            ts("2024-03-31T23:00:00Z"),
+        ];
+        let report = find_gaps(&days, "D1", &calendar).unwrap();
+        assert_eq!(report.missing_bars, 0);
+        assert_eq!(report.coverage_percent, 100.0);
+
+        // A missing day is still found when the next bar is an hour late
+        let report = find_gaps(
+            &[ts("2024-03-30T00:00:00Z"), ts("2024-04-01T01:00:00Z")],
+            "D1",
+            &calendar,
+        )
+        .unwrap();
+        assert_eq!(report.missing_bars, 1);
+        assert_eq!(report.coverage_percent, 66.67);
+    }
+}
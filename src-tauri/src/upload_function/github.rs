@@ -1,10 +1,15 @@
 use reqwest;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{BufRead, Write};
+use std::path::Path;
 use tauri::{AppHandle, Manager};
 
-use super::local_upload::{SymbolData, TimeframeInfo};
+use super::archive::{for_each_csv, ArchiveKind};
+use super::candles::{append_new_rows, candle_row, CSV_HEADER};
+use super::local_upload::{CsvFormat, SymbolData, TimeframeInfo};
+use super::merge::{finish_import, import_target, MergePolicy, MergeReport};
+use super::timezone::{resolve_timezone, SourceTimezone};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GitHubRequest {
@@ -12,6 +17,41 @@ pub struct GitHubRequest {
     pub branch: String,
     pub structure_type: String,      // "multi" or "single"
     pub symbol_name: Option<String>, // For multi-symbol repos, which symbol to download
+    #[serde(default)]
+    pub sync: bool, // Update already-imported symbols instead of skipping them
+    #[serde(default)]
+    pub timezone: Option<String>, // Zone the CSV timestamps are written in, UTC when missing
+    #[serde(default)]
+    pub merge: Option<MergePolicy>, // Splice into an existing symbol instead of refusing it
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubAssetRequest {
+    pub repo_url: String,
+    pub asset_name: String,
+    pub download_url: String,
+    pub symbol_name: String,
+    #[serde(default)]
+    pub sync: bool,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub merge: Option<MergePolicy>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubRelease {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub published_at: Option<String>,
+    pub assets: Vec<GitHubReleaseAsset>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitHubReleaseAsset {
+    pub name: String,
+    pub size: u64,
+    pub browser_download_url: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -20,12 +60,16 @@ pub struct GitHubResult {
     pub message: String,
     pub symbols_processed: Vec<String>,
     pub total_timeframes: usize,
+    pub candles_added: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub merges: Vec<MergeReport>, // One per symbol that was merged into
 }
 
 #[derive(Debug, Deserialize)]
 struct GitHubContent {
     name: String,
     path: String,
+    sha: String,
     #[serde(rename = "type")]
     content_type: String,
     download_url: Option<String>,
@@ -148,14 +192,214 @@ fn is_valid_timeframe(name: &str) -> bool {
     )
 }
 
-fn count_csv_lines(content: &str) -> usize {
-    content
+async fn download_to_file(url: &str, path: &Path) -> Result<(), String> {
+    let client = reqwest::Client::new();
+    let mut response = client
+        .get(url)
+        .header("User-Agent", "TraderAssist")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to download file: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Failed to download file: {}", response.status()));
+    }
+
+    let mut file = File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+
+    // Write chunks as they arrive so large archives never sit in memory
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Failed to read file content: {}", e))?
+    {
+        file.write_all(&chunk)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+    }
+
+    Ok(())
+}
+
+// Store one timeframe CSV for a symbol, copying it line by line as it is read. When the
+// timeframe is already stored the new candles are appended, otherwise the file is written fresh.
+// Returns the candles added. CSVs with wall-clock times in a zone other than UTC are converted
+// to the app's own layout first, which needs all of their candles in memory to sort them.
+fn store_timeframe_csv(
+    data_dir: &Path,
+    timeframe_infos: &mut Vec<TimeframeInfo>,
+    timeframe: &str,
+    content: &mut dyn BufRead,
+    sha: Option<&str>,
+    timezone: &SourceTimezone,
+) -> Result<usize, String> {
+    let mut lines = content
         .lines()
-        .filter(|line| !line.trim().is_empty())
-        .count()
-        .saturating_sub(1) // Subtract header
+        .map(|line| line.map_err(|e| format!("Failed to read CSV: {}", e)));
+    let first_line = lines.next().transpose()?.unwrap_or_default();
+    let format = CsvFormat::detect(&first_line);
+    let layout = format.layout(&first_line)?;
+
+    // Headerless layouts start with a data row
+    let (header, first_row) = if format.has_header() {
+        (Some(first_line), None)
+    } else {
+        (None, Some(first_line))
+    };
+    let mut rows = first_row
+        .map(Ok)
+        .into_iter()
+        .chain(lines)
+        .filter(|row| !matches!(row, Ok(row) if row.trim().is_empty()))
+        .peekable();
+
+    let shift = !timezone.is_utc()
+        && !matches!(rows.peek(), Some(Ok(row)) if layout.has_absolute_time(row));
+
+    let (header, rows): (_, Box<dyn Iterator<Item = Result<String, String>>>) = if shift {
+        let mut candles = Vec::new();
+        for row in rows {
+            candles.extend(layout.parse_line(&row?));
+        }
+        timezone.shift_to_utc(&mut candles);
+        (
+            Some(CSV_HEADER.to_string()),
+            Box::new(candles.into_iter().map(|c| Ok(candle_row(&c)))),
+        )
+    } else {
+        (header, Box::new(rows))
+    };
+
+    let stored = timeframe_infos
+        .iter()
+        .position(|tf| tf.name == timeframe && Path::new(&tf.file_path).exists());
+
+    if let Some(idx) = stored {
+        let info = &mut timeframe_infos[idx];
+
+        // Appending rows from another zone would mix layouts and clock times in one file
+        let stored_zone = info.source_timezone.as_deref().unwrap_or("UTC");
+        if SourceTimezone::parse(stored_zone)? != *timezone {
+            return Err(format!(
+                "{} was imported from {} time, re-import it to use {}",
+                timeframe, stored_zone, timezone
+            ));
+        }
+
+        let appended = append_new_rows(Path::new(&info.file_path), rows)?;
+
+        info.candle_count += appended;
+        info.source_sha = sha.map(|s| s.to_string());
+        println!("✓ {} - {} new candles", timeframe, appended);
+
+        return Ok(appended);
+    }
+
+    let csv_path = data_dir.join(format!("{}.csv", timeframe));
+    let mut f = File::create(&csv_path).map_err(|e| format!("Failed to create CSV file: {}", e))?;
+
+    if let Some(header) = header {
+        writeln!(f, "{}", header).map_err(|e| format!("Failed to write CSV file: {}", e))?;
+    }
+
+    let mut candle_count = 0;
+    for row in rows {
+        writeln!(f, "{}", row?).map_err(|e| format!("Failed to write CSV file: {}", e))?;
+        candle_count += 1;
+    }
+    let display_name = convert_timeframe_to_display(timeframe);
+
+    // Replace a stale entry whose file has gone missing
+    timeframe_infos.retain(|tf| tf.name != timeframe);
+    timeframe_infos.push(TimeframeInfo {
+        name: timeframe.to_string(),
+        display_name,
+        candle_count,
+        file_path: csv_path.to_string_lossy().to_string(),
+        source_sha: sha.map(|s| s.to_string()),
+        format: None,
+        coverage: None,
+        source_timezone: Some(timezone.to_string()),
+    });
+
+    println!("✓ {} - {} candles", timeframe, candle_count);
+
+    Ok(candle_count)
+}
+
+// Whether the blob with this SHA was stored by an earlier sync, so it can be skipped. Archives
+// pass no timeframe, every timeframe extracted from one carries the archive's SHA.
+fn already_stored(timeframe_infos: &[TimeframeInfo], timeframe: Option<&str>, sha: &str) -> bool {
+    timeframe_infos.iter().any(|tf| {
+        tf.source_sha.as_deref() == Some(sha) && timeframe.is_none_or(|name| tf.name == name)
+    })
+}
+
+// Feed every `<TF>.csv` inside a downloaded archive through `store_timeframe_csv`
+fn store_archive_csvs(
+    archive_path: &Path,
+    kind: ArchiveKind,
+    data_dir: &Path,
+    timeframe_infos: &mut Vec<TimeframeInfo>,
+    sha: Option<&str>,
+    timezone: &SourceTimezone,
+) -> Result<usize, String> {
+    let mut candles_added = 0;
+
+    let csv_count = for_each_csv(archive_path, kind, |timeframe, content| {
+        if !is_valid_timeframe(timeframe) {
+            println!("Skipping invalid timeframe: {}.csv", timeframe);
+            return Ok(());
+        }
+
+        candles_added +=
+            store_timeframe_csv(data_dir, timeframe_infos, timeframe, content, sha, timezone)?;
+        Ok(())
+    })?;
+
+    if csv_count == 0 {
+        return Err("Archive does not contain any CSV files".to_string());
+    }
+
+    Ok(candles_added)
 }
 
+fn save_timeframes(
+    app_handle: &AppHandle,
+    symbol_name: &str,
+    mut timeframe_infos: Vec<TimeframeInfo>,
+) -> Result<SymbolData, String> {
+    if timeframe_infos.is_empty() {
+        return Err("No valid timeframe files were downloaded".to_string());
+    }
+
+    // Sort timeframes
+    timeframe_infos.sort_by(|a, b| {
+        let order = [
+            "M1", "M2", "M3", "M4", "M5", "M10", "M15", "M30", "H1", "H2", "H3", "H4", "D1", "W1",
+            "MN1",
+        ];
+        let a_idx = order.iter().position(|&x| x == a.name).unwrap_or(999);
+        let b_idx = order.iter().position(|&x| x == b.name).unwrap_or(999);
+        a_idx.cmp(&b_idx)
+    });
+
+    let total_candles = timeframe_infos.iter().map(|tf| tf.candle_count).sum();
+
+    let symbol_data = SymbolData {
+        symbol: symbol_name.to_uppercase(),
+        timeframes: timeframe_infos,
+        total_candles,
+        uploaded_at: chrono::Utc::now().to_rfc3339(),
+        metadata: None,
+        ticks: None,
+    };
+
+    save_symbol_data(app_handle, &symbol_data)?;
+
+    Ok(symbol_data)
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn process_symbol_folder(
     owner: &str,
     repo: &str,
@@ -163,15 +407,22 @@ async fn process_symbol_folder(
     symbol_name: &str,
     branch: &str,
     app_handle: &AppHandle,
-) -> Result<SymbolData, String> {
+    existing: Option<SymbolData>,
+    timezone: &SourceTimezone,
+) -> Result<(SymbolData, usize), String> {
     let contents = fetch_github_contents(owner, repo, symbol_path, branch).await?;
 
-    let csv_files: Vec<&GitHubContent> = contents
+    // Plain CSVs plus zipped CSVs committed to the tree
+    let data_files: Vec<&GitHubContent> = contents
         .iter()
-        .filter(|item| item.content_type == "file" && item.name.to_uppercase().ends_with(".CSV"))
+        .filter(|item| {
+            item.content_type == "file"
+                && (item.name.to_uppercase().ends_with(".CSV")
+                    || ArchiveKind::from_name(&item.name).is_some())
+        })
         .collect();
 
-    if csv_files.is_empty() {
+    if data_files.is_empty() {
         return Err(format!("No CSV files found in {}", symbol_path));
     }
 
@@ -180,13 +431,53 @@ async fn process_symbol_folder(
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data directory: {}", e))?;
 
+    let data_dir = app_dir.join("data").join(symbol_name);
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
     let temp_dir = app_dir.join("temp").join(symbol_name);
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("Failed to create temp directory: {}", e))?;
 
-    let mut timeframe_infos = Vec::new();
-    let mut total_candles = 0;
+    // In sync mode, start from what is already stored and only touch changed files
+    let mut timeframe_infos = existing.map(|data| data.timeframes).unwrap_or_default();
+    let mut candles_added = 0;
+
+    for file in data_files {
+        if let Some(kind) = ArchiveKind::from_name(&file.name) {
+            if already_stored(&timeframe_infos, None, &file.sha) {
+                println!("= {} - unchanged", file.name);
+                continue;
+            }
+
+            let Some(download_url) = &file.download_url else {
+                continue;
+            };
+
+            println!("Downloading {} for {}...", file.name, symbol_name);
+
+            fs::create_dir_all(&temp_dir)
+                .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+            let archive_path = temp_dir.join(&file.name);
+
+            let result = match download_to_file(download_url, &archive_path).await {
+                Ok(()) => store_archive_csvs(
+                    &archive_path,
+                    kind,
+                    &data_dir,
+                    &mut timeframe_infos,
+                    Some(&file.sha),
+                    timezone,
+                ),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(added) => candles_added += added,
+                Err(e) => println!("✗ {} - Failed: {}", file.name, e),
+            }
+
+            let _ = fs::remove_file(&archive_path);
+            continue;
+        }
 
-    for file in csv_files {
         let timeframe = file
             .name
             .trim_end_matches(".csv")
@@ -198,65 +489,167 @@ async fn process_symbol_folder(
             continue;
         }
 
+        if already_stored(&timeframe_infos, Some(&timeframe), &file.sha) {
+            println!("= {} - unchanged", timeframe);
+            continue;
+        }
+
         if let Some(download_url) = &file.download_url {
             println!("Downloading {} for {}...", timeframe, symbol_name);
 
-            match download_csv_file(download_url).await {
-                Ok(content) => {
-                    let csv_path = temp_dir.join(format!("{}.csv", timeframe));
-                    let mut f = File::create(&csv_path)
-                        .map_err(|e| format!("Failed to create CSV file: {}", e))?;
-                    f.write_all(content.as_bytes())
-                        .map_err(|e| format!("Failed to write CSV file: {}", e))?;
-
-                    let candle_count = count_csv_lines(&content);
-                    let display_name = convert_timeframe_to_display(&timeframe);
-
-                    timeframe_infos.push(TimeframeInfo {
-                        name: timeframe.clone(),
-                        display_name,
-                        candle_count,
-                        file_path: csv_path.to_string_lossy().to_string(),
-                    });
-
-                    total_candles += candle_count;
-                    println!("✓ {} - {} candles", timeframe, candle_count);
-                }
-                Err(e) => {
-                    println!("✗ {} - Failed: {}", timeframe, e);
-                }
+            // Same as archives: a file that fails is reported and the rest still import
+            let result = match download_csv_file(download_url).await {
+                Ok(content) => store_timeframe_csv(
+                    &data_dir,
+                    &mut timeframe_infos,
+                    &timeframe,
+                    &mut content.as_bytes(),
+                    Some(&file.sha),
+                    timezone,
+                ),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(added) => candles_added += added,
+                Err(e) => println!("✗ {} - Failed: {}", timeframe, e),
             }
         }
     }
 
-    if timeframe_infos.is_empty() {
-        return Err("No valid timeframe files were downloaded".to_string());
+    // Clean up temp directory
+    let _ = fs::remove_dir_all(&temp_dir);
+
+    let symbol_data = save_timeframes(app_handle, symbol_name, timeframe_infos)?;
+
+    Ok((symbol_data, candles_added))
+}
+
+#[tauri::command]
+pub async fn list_github_releases(repo_url: String) -> Result<Vec<GitHubRelease>, String> {
+    let (owner, repo) = parse_github_url(&repo_url)?;
+
+    let url = format!("https://api.github.com/repos/{}/{}/releases", owner, repo);
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(&url)
+        .header("User-Agent", "TraderAssist")
+        .header("Accept", "application/vnd.github.v3+json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch GitHub releases: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "GitHub API error: {}. Make sure the repository is public.",
+            response.status()
+        ));
     }
 
-    // Sort timeframes
-    timeframe_infos.sort_by(|a, b| {
-        let order = [
-            "M1", "M2", "M3", "M4", "M5", "M10", "M15", "M30", "H1", "H2", "H3", "H4", "D1", "W1",
-            "MN1",
-        ];
-        let a_idx = order.iter().position(|&x| x == a.name).unwrap_or(999);
-        let b_idx = order.iter().position(|&x| x == b.name).unwrap_or(999);
-        a_idx.cmp(&b_idx)
-    });
+    let mut releases: Vec<GitHubRelease> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse GitHub response: {}", e))?;
 
-    let symbol_data = SymbolData {
-        symbol: symbol_name.to_uppercase(),
-        timeframes: timeframe_infos,
-        total_candles,
-        uploaded_at: chrono::Utc::now().to_rfc3339(),
-    };
+    // Only archives can be imported, so hide everything else
+    for release in &mut releases {
+        release
+            .assets
+            .retain(|asset| ArchiveKind::from_name(&asset.name).is_some());
+    }
+    releases.retain(|release| !release.assets.is_empty());
 
-    save_symbol_data(app_handle, &symbol_data)?;
+    Ok(releases)
+}
+
+#[tauri::command]
+pub async fn fetch_github_release_asset_command(
+    app_handle: AppHandle,
+    request: GitHubAssetRequest,
+) -> Result<GitHubResult, String> {
+    let (owner, repo) = parse_github_url(&request.repo_url)?;
+
+    // The name becomes a path under temp/, so only a bare file name is accepted
+    let asset_name = Path::new(&request.asset_name)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| *name == request.asset_name)
+        .ok_or("Invalid asset name")?;
+
+    let kind = ArchiveKind::from_name(asset_name)
+        .ok_or("Unsupported asset type. Expected a .zip or .tar.gz archive")?;
+    let timezone = resolve_timezone(request.timezone.as_deref(), SourceTimezone::Utc)?;
+
+    let expected_prefix = format!("https://github.com/{}/{}/releases/download/", owner, repo);
+    if !request.download_url.starts_with(&expected_prefix) {
+        return Err("Asset does not belong to this repository".to_string());
+    }
+
+    let symbol_name = request.symbol_name.trim().to_uppercase();
+
+    if symbol_name.is_empty() {
+        return Err("Symbol name cannot be empty".to_string());
+    }
+
+    if symbol_name.contains(|c: char| !c.is_alphanumeric() && c != '_') {
+        return Err("Symbol name can only contain letters, numbers, and underscores".to_string());
+    }
+
+    let (target, existing) =
+        import_destination(&app_handle, &symbol_name, request.sync, request.merge)?;
+
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let data_dir = app_dir.join("data").join(&target);
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    let temp_dir = app_dir.join("temp").join(&target);
+    fs::create_dir_all(&temp_dir).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let archive_path = temp_dir.join(asset_name);
+
+    println!("Downloading {} for {}...", asset_name, symbol_name);
+
+    let mut timeframe_infos = existing.map(|data| data.timeframes).unwrap_or_default();
+
+    let result = match download_to_file(&request.download_url, &archive_path).await {
+        Ok(()) => store_archive_csvs(
+            &archive_path,
+            kind,
+            &data_dir,
+            &mut timeframe_infos,
+            None,
+            &timezone,
+        ),
+        Err(e) => Err(e),
+    };
 
     // Clean up temp directory
     let _ = fs::remove_dir_all(&temp_dir);
 
-    Ok(symbol_data)
+    let candles_added = result?;
+    let symbol_data = save_timeframes(&app_handle, &target, timeframe_infos)?;
+    let total_timeframes = symbol_data.timeframes.len();
+    let merge = finish_import(&app_handle, &symbol_name, &target, request.merge, "github")?;
+
+    Ok(GitHubResult {
+        success: true,
+        message: format!(
+            "Imported {} from {} with {} timeframe{} ({} new candles)",
+            symbol_name,
+            asset_name,
+            total_timeframes,
+            if total_timeframes == 1 { "" } else { "s" },
+            candles_added
+        ),
+        symbols_processed: vec![symbol_name],
+        total_timeframes,
+        candles_added,
+        merges: merge.into_iter().collect(),
+    })
 }
 
 #[tauri::command]
@@ -265,6 +658,7 @@ pub async fn fetch_github_data_command(
     request: GitHubRequest,
 ) -> Result<GitHubResult, String> {
     let (owner, repo) = parse_github_url(&request.repo_url)?;
+    let timezone = resolve_timezone(request.timezone.as_deref(), SourceTimezone::Utc)?;
     let branch = if request.branch.is_empty() {
         "main".to_string()
     } else {
@@ -273,6 +667,8 @@ pub async fn fetch_github_data_command(
 
     let mut symbols_processed = Vec::new();
     let mut total_timeframes = 0;
+    let mut candles_added = 0;
+    let mut merges = Vec::new();
 
     match request.structure_type.as_str() {
         "single" => {
@@ -282,34 +678,62 @@ pub async fn fetch_github_data_command(
                 .unwrap_or_else(|| repo.clone())
                 .to_uppercase();
 
-            // Check if symbol already exists
-            check_symbol_exists(&app_handle, &symbol_name)?;
-
-            let symbol_data =
-                process_symbol_folder(&owner, &repo, "", &symbol_name, &branch, &app_handle)
-                    .await?;
-
-            symbols_processed.push(symbol_data.symbol);
+            // Existing symbols are only written to through a sync or a merge
+            let (target, existing) =
+                import_destination(&app_handle, &symbol_name, request.sync, request.merge)?;
+
+            let (symbol_data, added) = process_symbol_folder(
+                &owner,
+                &repo,
+                "",
+                &target,
+                &branch,
+                &app_handle,
+                existing,
+                &timezone,
+            )
+            .await?;
+
+            merges.extend(finish_import(
+                &app_handle,
+                &symbol_name,
+                &target,
+                request.merge,
+                "github",
+            )?);
+            symbols_processed.push(symbol_name);
             total_timeframes = symbol_data.timeframes.len();
+            candles_added = added;
         }
         "multi" => {
             if let Some(symbol_name) = request.symbol_name {
                 // Download specific symbol from multi-symbol repo
                 let symbol_upper = symbol_name.to_uppercase();
-                check_symbol_exists(&app_handle, &symbol_upper)?;
+                let (target, existing) =
+                    import_destination(&app_handle, &symbol_upper, request.sync, request.merge)?;
 
-                let symbol_data = process_symbol_folder(
+                let (symbol_data, added) = process_symbol_folder(
                     &owner,
                     &repo,
                     &symbol_name,
-                    &symbol_upper,
+                    &target,
                     &branch,
                     &app_handle,
+                    existing,
+                    &timezone,
                 )
                 .await?;
 
-                symbols_processed.push(symbol_data.symbol);
+                merges.extend(finish_import(
+                    &app_handle,
+                    &symbol_upper,
+                    &target,
+                    request.merge,
+                    "github",
+                )?);
+                symbols_processed.push(symbol_upper);
                 total_timeframes = symbol_data.timeframes.len();
+                candles_added = added;
             } else {
                 // Download all symbols from multi-symbol repo
                 let contents = fetch_github_contents(&owner, &repo, "", &branch).await?;
@@ -326,25 +750,49 @@ pub async fn fetch_github_data_command(
                 for folder in symbol_folders {
                     let symbol_name = folder.name.to_uppercase();
 
-                    // Skip if already exists
-                    if check_symbol_exists(&app_handle, &symbol_name).is_err() {
-                        println!("Skipping {} (already exists)", symbol_name);
-                        continue;
-                    }
+                    // Skip if already exists (unless syncing or merging)
+                    let (target, existing) = match import_destination(
+                        &app_handle,
+                        &symbol_name,
+                        request.sync,
+                        request.merge,
+                    ) {
+                        Ok(destination) => destination,
+                        Err(_) => {
+                            println!("Skipping {} (already exists)", symbol_name);
+                            continue;
+                        }
+                    };
 
-                    match process_symbol_folder(
+                    let result = match process_symbol_folder(
                         &owner,
                         &repo,
                         &folder.path,
-                        &symbol_name,
+                        &target,
                         &branch,
                         &app_handle,
+                        existing,
+                        &timezone,
                     )
                     .await
                     {
-                        Ok(symbol_data) => {
-                            symbols_processed.push(symbol_data.symbol);
+                        Ok((symbol_data, added)) => finish_import(
+                            &app_handle,
+                            &symbol_name,
+                            &target,
+                            request.merge,
+                            "github",
+                        )
+                        .map(|merge| (symbol_data, added, merge)),
+                        Err(e) => Err(e),
+                    };
+
+                    match result {
+                        Ok((symbol_data, added, merge)) => {
+                            merges.extend(merge);
+                            symbols_processed.push(symbol_name);
                             total_timeframes += symbol_data.timeframes.len();
+                            candles_added += added;
                         }
                         Err(e) => {
                             println!("Failed to process {}: {}", symbol_name, e);
@@ -362,9 +810,20 @@ pub async fn fetch_github_data_command(
         }
     }
 
-    Ok(GitHubResult {
-        success: true,
-        message: format!(
+    let message = if request.sync {
+        format!(
+            "Synced {} symbol{}: {} new candle{} added",
+            symbols_processed.len(),
+            if symbols_processed.len() == 1 {
+                ""
+            } else {
+                "s"
+            },
+            candles_added,
+            if candles_added == 1 { "" } else { "s" }
+        )
+    } else {
+        format!(
             "Successfully downloaded {} symbol{} with {} total timeframe{}",
             symbols_processed.len(),
             if symbols_processed.len() == 1 {
@@ -374,13 +833,31 @@ pub async fn fetch_github_data_command(
             },
             total_timeframes,
             if total_timeframes == 1 { "" } else { "s" }
-        ),
+        )
+    };
+
+    Ok(GitHubResult {
+        success: true,
+        message,
         symbols_processed,
         total_timeframes,
+        candles_added,
+        merges,
     })
 }
 
-fn check_symbol_exists(app_handle: &AppHandle, symbol: &str) -> Result<(), String> {
+// Where an import writes: the stored symbol itself when syncing (along with what is already
+// stored), otherwise `import_target`'s choice, which refuses existing symbols unless merging
+fn import_destination(
+    app_handle: &AppHandle,
+    symbol: &str,
+    sync: bool,
+    merge: Option<MergePolicy>,
+) -> Result<(String, Option<SymbolData>), String> {
+    if !sync {
+        return Ok((import_target(app_handle, symbol, merge)?, None));
+    }
+
     let app_dir = app_handle
         .path()
         .app_data_dir()
@@ -388,14 +865,17 @@ fn check_symbol_exists(app_handle: &AppHandle, symbol: &str) -> Result<(), Strin
 
     let symbol_json = app_dir.join("symbols").join(format!("{}.json", symbol));
 
-    if symbol_json.exists() {
-        return Err(format!(
-            "Symbol '{}' already exists. Please delete it first.",
-            symbol
-        ));
+    if !symbol_json.exists() {
+        return Ok((symbol.to_string(), None));
     }
 
-    Ok(())
+    let content = fs::read_to_string(&symbol_json)
+        .map_err(|e| format!("Failed to read symbol file: {}", e))?;
+
+    let symbol_data: SymbolData = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse symbol data: {}", e))?;
+
+    Ok((symbol.to_string(), Some(symbol_data)))
 }
 
 fn save_symbol_data(app_handle: &AppHandle, symbol_data: &SymbolData) -> Result<(), String> {
@@ -420,3 +900,224 @@ fn save_symbol_data(app_handle: &AppHandle, symbol_data: &SymbolData) -> Result<
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_rows_are_written_then_appended_as_they_are_read() {
+        let data_dir = std::env::temp_dir().join(format!("github_store_{}", std::process::id()));
+        fs::create_dir_all(&data_dir).unwrap();
+        let mut infos = Vec::new();
+        let utc = SourceTimezone::Utc;
+
+        let first = "timestamp,open,high,low,close,volume\n60,1,2,0.5,1.5,10\n120,1,2,0.5,1.5,10\n";
+        let added = store_timeframe_csv(
+            &data_dir,
+            &mut infos,
+            "M1",
+            &mut first.as_bytes(),
+            Some("a"),
+            &utc,
+        )
+        .unwrap();
+        assert_eq!(added, 2);
+        assert_eq!(infos[0].candle_count, 2);
+
+        // Only the bar after the stored ones is appended
+        let second =
+            "timestamp,open,high,low,close,volume\n120,1,2,0.5,1.5,10\n\n180,1,2,0.5,1.5,10\n";
+        let added = store_timeframe_csv(
+            &data_dir,
+            &mut infos,
+            "M1",
+            &mut second.as_bytes(),
+            Some("b"),
+            &utc,
+        )
+        .unwrap();
+        assert_eq!(added, 1);
+        assert_eq!(infos[0].candle_count, 3);
+        assert_eq!(
+            fs::read_to_string(data_dir.join("M1.csv")).unwrap(),
+            "timestamp,open,high,low,close,volume\n60,1,2,0.5,1.5,10\n120,1,2,0.5,1.5,10\n180,1,2,0.5,1.5,10\n"
+        );
+
+        fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn files_with_a_stored_sha_are_skipped() {
+        let data_dir = std::env::temp_dir().join(format!("github_sha_{}", std::process::id()));
+        fs::create_dir_all(&data_dir).unwrap();
+        let mut infos = Vec::new();
+        let csv = "timestamp,open,high,low,close,volume\n60,1,2,0.5,1.5,10\n";
+
+        store_timeframe_csv(
+            &data_dir,
+            &mut infos,
+            "M1",
+            &mut csv.as_bytes(),
+            Some("sha-m1"),
+            &SourceTimezone::Utc,
+        )
+        .unwrap();
+
+        assert!(already_stored(&infos, Some("M1"), "sha-m1"));
+        // A new blob, or the same blob under another timeframe, is downloaded again
+        assert!(!already_stored(&infos, Some("M1"), "sha-m1-v2"));
+        assert!(!already_stored(&infos, Some("H1"), "sha-m1"));
+        // Archives match on the SHA alone
+        assert!(already_stored(&infos, None, "sha-m1"));
+        assert!(!already_stored(&infos, None, "sha-zip"));
+
+        // Files stored before SHAs were recorded always sync
+        infos[0].source_sha = None;
+        assert!(!already_stored(&infos, Some("M1"), "sha-m1"));
+
+        fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn only_rows_after_the_last_stored_bar_are_appended() {
+        let data_dir = std::env::temp_dir().join(format!("github_append_{}", std::process::id()));
+        fs::create_dir_all(&data_dir).unwrap();
+        let mut infos = Vec::new();
+        let utc = SourceTimezone::Utc;
+
+        let stored = "timestamp,open,high,low,close,volume\n60,1,1,1,1,1\n120,2,2,2,2,1\n";
+        store_timeframe_csv(
+            &data_dir,
+            &mut infos,
+            "M1",
+            &mut stored.as_bytes(),
+            Some("a"),
+            &utc,
+        )
+        .unwrap();
+
+        // Older and duplicate rows are dropped wherever they appear, even a revised stored bar
+        let update = "timestamp,open,high,low,close,volume\n\
+                      30,9,9,9,9,1\n180,3,3,3,3,1\n120,9,9,9,9,1\n90,9,9,9,9,1\n240,4,4,4,4,1\n";
+        let added = store_timeframe_csv(
+            &data_dir,
+            &mut infos,
+            "M1",
+            &mut update.as_bytes(),
+            Some("b"),
+            &utc,
+        )
+        .unwrap();
+
+        assert_eq!(added, 2);
+        assert_eq!(infos[0].candle_count, 4);
+        assert_eq!(infos[0].source_sha.as_deref(), Some("b"));
+        assert_eq!(
+            fs::read_to_string(data_dir.join("M1.csv")).unwrap(),
+            "timestamp,open,high,low,close,volume\n\
+             60,1,1,1,1,1\n120,2,2,2,2,1\n180,3,3,3,3,1\n240,4,4,4,4,1\n"
+        );
+
+        // Nothing newer leaves the file untouched
+        let added = store_timeframe_csv(
+            &data_dir,
+            &mut infos,
+            "M1",
+            &mut stored.as_bytes(),
+            Some("c"),
+            &utc,
+        )
+        .unwrap();
+        assert_eq!(added, 0);
+        assert_eq!(infos[0].candle_count, 4);
+
+        fs::remove_dir_all(&data_dir).ok();
+    }
+
+    #[test]
+    fn zip_and_tar_gz_archives_are_extracted() {
+        let dir = std::env::temp_dir().join(format!("github_archives_{}", std::process::id()));
+        let data_dir = dir.join("data");
+        fs::create_dir_all(&data_dir).unwrap();
+        let utc = SourceTimezone::Utc;
+        let m1 = "timestamp,open,high,low,close,volume\n60,1,2,0.5,1.5,10\n120,1,2,0.5,1.5,10\n";
+        let h1 = "timestamp,open,high,low,close,volume\n3600,1,2,0.5,1.5,10\n";
+
+        let zip_path = dir.join("EURUSD.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        zip.start_file("EURUSD/m1.csv", options).unwrap();
+        zip.write_all(m1.as_bytes()).unwrap();
+        zip.start_file("EURUSD/README.txt", options).unwrap();
+        zip.write_all(b"not a timeframe").unwrap();
+        zip.finish().unwrap();
+
+        let tar_path = dir.join("EURUSD.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            File::create(&tar_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut tar = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(h1.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, "EURUSD/H1.csv", h1.as_bytes())
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let mut infos = Vec::new();
+        let added = store_archive_csvs(
+            &zip_path,
+            ArchiveKind::Zip,
+            &data_dir,
+            &mut infos,
+            Some("zip-sha"),
+            &utc,
+        )
+        .unwrap();
+        assert_eq!(added, 2);
+
+        let added = store_archive_csvs(
+            &tar_path,
+            ArchiveKind::TarGz,
+            &data_dir,
+            &mut infos,
+            Some("tar-sha"),
+            &utc,
+        )
+        .unwrap();
+        assert_eq!(added, 1);
+
+        let stored: Vec<(&str, usize, Option<&str>)> = infos
+            .iter()
+            .map(|tf| (tf.name.as_str(), tf.candle_count, tf.source_sha.as_deref()))
+            .collect();
+        assert_eq!(
+            stored,
+            vec![("M1", 2, Some("zip-sha")), ("H1", 1, Some("tar-sha"))]
+        );
+        assert_eq!(fs::read_to_string(data_dir.join("M1.csv")).unwrap(), m1);
+        assert_eq!(fs::read_to_string(data_dir.join("H1.csv")).unwrap(), h1);
+
+        // An archive without any timeframe CSV is an error, like a folder without one
+        let empty_path = dir.join("empty.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&empty_path).unwrap());
+        zip.start_file("README.txt", options).unwrap();
+        zip.write_all(b"nothing here").unwrap();
+        zip.finish().unwrap();
+        assert!(store_archive_csvs(
+            &empty_path,
+            ArchiveKind::Zip,
+            &data_dir,
+            &mut infos,
+            None,
+            &utc,
+        )
+        .is_err());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,16 +1,31 @@
-use csv::ReaderBuilder;
+use chrono::{NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::Write;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 
+use super::candles::{detect_delimiter, write_candles, Candle, CsvLayout};
+use super::column_mapping::{load_import_profile, ColumnMapping};
+use super::merge::{clear_provenance, finish_import, import_target, MergePolicy, MergeReport};
+use super::ticks::TickSummary;
+use super::timezone::SourceTimezone;
+use super::validation::{validate_rows, InvalidRowPolicy, ParsedRow, ValidationReport};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TimeframeInfo {
     pub name: String,
     pub display_name: String,
     pub candle_count: usize,
     pub file_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_sha: Option<String>, // Git blob SHA the file was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<CsvFormat>, // Layout chosen at import, detected again when missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<f64>, // Percent of expected bars present, from the last gap analysis
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_timezone: Option<String>, // Zone the source was written in, stored times are UTC
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,6 +34,30 @@ pub struct SymbolData {
     pub timeframes: Vec<TimeframeInfo>,
     pub total_candles: usize,
     pub uploaded_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<SymbolMetadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticks: Option<TickSummary>,
+}
+
+// Exchange and instrument details reported by the data source
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SymbolMetadata {
+    pub source: String,
+    pub exchange: Option<String>,
+    pub exchange_timezone: Option<String>, // IANA name, e.g. "America/New_York"
+    pub gmt_offset: Option<i64>,           // Seconds east of UTC when imported
+    pub currency: Option<String>,
+    pub instrument_type: Option<String>, // EQUITY, CURRENCY, FUTURE, CRYPTOCURRENCY...
+    pub price_precision: Option<u32>,    // Decimal places used for quotes
+    pub regular_session: Option<SessionHours>,
+}
+
+// Regular trading session in exchange-local time
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionHours {
+    pub start: String, // "HH:MM"
+    pub end: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -28,6 +67,37 @@ pub struct ProcessResult {
     pub symbol: Option<String>,
     pub timeframes_processed: Vec<String>,
     pub total_candles: usize,
+    pub reports: Vec<ValidationReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge: Option<MergeReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FolderValidation {
+    pub success: bool,
+    pub message: String,
+    pub symbol: String,
+    pub reports: Vec<ValidationReport>,
+}
+
+// Detected layout of one CSV file and the first rows it parses to
+#[derive(Debug, Serialize)]
+pub struct FilePreview {
+    pub timeframe: String,
+    pub file_path: String,
+    pub format: Option<CsvFormat>,
+    pub format_name: Option<String>,
+    pub candidates: Vec<CsvFormat>, // Every layout whose detector accepts the file
+    pub rows: Vec<Candle>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FolderPreview {
+    pub success: bool,
+    pub message: String,
+    pub symbol: String,
+    pub files: Vec<FilePreview>,
 }
 
 #[derive(Debug, Serialize)]
@@ -45,10 +115,407 @@ pub struct RenameResult {
     pub new_symbol: String,
 }
 
+// Chart export layouts the local importer understands
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CsvFormat {
+    Mt4,           // 2023.01.02,17:00,O,H,L,C,V (History Center export, no header)
+    Mt5,           // <DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>
+    MetaStock,     // <TICKER>,<DTYYYYMMDD>,<TIME>,<OPEN>,<HIGH>,<LOW>,<CLOSE>,<VOL>
+    HistDataAscii, // 20230102 170000;O;H;L;C;V (EST without DST, no header)
+    NinjaTrader,   // 20230102 170000;O;H;L;C;V or 20230102;O;H;L;C;V for daily bars
+    TradingView,   // time,open,high,low,close[,Volume, indicator columns...]
+    YahooCsv,      // Date,Open,High,Low,Close,Adj Close,Volume
+    Generic,       // Any header naming OHLC columns, or DATE,[TIME,]O,H,L,C positionally
+}
+
+// Detection order: specific headers first, then headerless layouts, then the generic fallback.
+// HistData and NinjaTrader intraday files are identical, so HistData wins unless overridden.
+const CSV_FORMATS: [CsvFormat; 8] = [
+    CsvFormat::Mt5,
+    CsvFormat::MetaStock,
+    CsvFormat::YahooCsv,
+    CsvFormat::TradingView,
+    CsvFormat::Mt4,
+    CsvFormat::HistDataAscii,
+    CsvFormat::NinjaTrader,
+    CsvFormat::Generic,
+];
+
+impl CsvFormat {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            CsvFormat::Mt4 => "MetaTrader 4 export",
+            CsvFormat::Mt5 => "MetaTrader 5 export",
+            CsvFormat::MetaStock => "MetaStock ASCII",
+            CsvFormat::HistDataAscii => "HistData generic ASCII",
+            CsvFormat::NinjaTrader => "NinjaTrader export",
+            CsvFormat::TradingView => "TradingView export",
+            CsvFormat::YahooCsv => "Yahoo Finance CSV",
+            CsvFormat::Generic => "Generic CSV",
+        }
+    }
+
+    // Zone a file is assumed to be in when the import doesn't name one. Broker exports
+    // (MT4/MT5) vary by broker, so only HistData's fixed EST is assumed.
+    pub fn default_timezone(&self) -> SourceTimezone {
+        match self {
+            CsvFormat::HistDataAscii => {
+                SourceTimezone::parse("-05:00").unwrap_or(SourceTimezone::Utc)
+            }
+            _ => SourceTimezone::Utc,
+        }
+    }
+
+    pub fn has_header(&self) -> bool {
+        !matches!(
+            self,
+            CsvFormat::Mt4 | CsvFormat::HistDataAscii | CsvFormat::NinjaTrader
+        )
+    }
+
+    // Whether the first line of a file looks like this layout
+    pub fn matches(&self, first_line: &str) -> bool {
+        let lower = first_line.trim().to_lowercase();
+        let fields: Vec<&str> = first_line.split([',', ';', '\t']).map(str::trim).collect();
+
+        match self {
+            CsvFormat::Mt5 => lower.contains("<date>") && lower.contains("<open>"),
+            CsvFormat::MetaStock => lower.contains("<dtyyyymmdd>"),
+            CsvFormat::YahooCsv => lower.starts_with("date,") && lower.contains("adj close"),
+            CsvFormat::TradingView => lower.starts_with("time,") && lower.contains(",open"),
+            CsvFormat::Mt4 => {
+                detect_delimiter(first_line) == Some(',')
+                    && fields.len() >= 7
+                    && NaiveDate::parse_from_str(fields[0], "%Y.%m.%d").is_ok()
+                    && NaiveTime::parse_from_str(fields[1], "%H:%M").is_ok()
+            }
+            CsvFormat::HistDataAscii => {
+                detect_delimiter(first_line) == Some(';') && is_compact_datetime(fields[0])
+            }
+            CsvFormat::NinjaTrader => {
+                detect_delimiter(first_line) == Some(';')
+                    && (is_compact_datetime(fields[0]) || is_compact_date(fields[0]))
+            }
+            CsvFormat::Generic => CsvLayout::detect(first_line).is_ok(),
+        }
+    }
+
+    // Column layout for a file in this format, given its first line
+    pub fn layout(&self, first_line: &str) -> Result<CsvLayout, String> {
+        match self {
+            CsvFormat::Mt4 => Ok(CsvLayout {
+                delimiter: ',',
+                date_col: 0,
+                time_col: Some(1),
+                open_col: 2,
+                high_col: 3,
+                low_col: 4,
+                close_col: 5,
+                volume_col: Some(6),
+                adj_close_col: None,
+            }),
+            // Date and time share the first column
+            CsvFormat::HistDataAscii | CsvFormat::NinjaTrader => Ok(CsvLayout {
+                delimiter: ';',
+                date_col: 0,
+                time_col: None,
+                open_col: 1,
+                high_col: 2,
+                low_col: 3,
+                close_col: 4,
+                volume_col: Some(5),
+                adj_close_col: None,
+            }),
+            CsvFormat::Mt5
+            | CsvFormat::MetaStock
+            | CsvFormat::TradingView
+            | CsvFormat::YahooCsv
+            | CsvFormat::Generic => CsvLayout::detect(first_line),
+        }
+    }
+
+    // First registered format that accepts the file, falling back to the generic layout
+    pub fn detect(first_line: &str) -> CsvFormat {
+        CSV_FORMATS
+            .into_iter()
+            .find(|format| format.matches(first_line))
+            .unwrap_or(CsvFormat::Generic)
+    }
+
+    pub fn candidates(first_line: &str) -> Vec<CsvFormat> {
+        CSV_FORMATS
+            .into_iter()
+            .filter(|format| format.matches(first_line))
+            .collect()
+    }
+}
+
+fn is_compact_date(s: &str) -> bool {
+    s.len() == 8 && NaiveDate::parse_from_str(s, "%Y%m%d").is_ok()
+}
+
+// YYYYMMDD HHMMSS
+fn is_compact_datetime(s: &str) -> bool {
+    match s.split_once(' ') {
+        Some((date, time)) => {
+            is_compact_date(date) && NaiveTime::parse_from_str(time, "%H%M%S").is_ok()
+        }
+        None => false,
+    }
+}
+
+const PREVIEW_ROWS: usize = 10;
+
+// Read only the head of the file, M1 exports can be hundreds of megabytes
+fn preview_csv(file_path: &Path, timeframe: &str, format: Option<CsvFormat>) -> FilePreview {
+    let mut preview = FilePreview {
+        timeframe: timeframe.to_string(),
+        file_path: file_path.to_string_lossy().to_string(),
+        format: None,
+        format_name: None,
+        candidates: Vec::new(),
+        rows: Vec::new(),
+        error: None,
+    };
+
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(e) => {
+            preview.error = Some(format!("Failed to read file: {}", e));
+            return preview;
+        }
+    };
+
+    let lines: Vec<String> = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .take(PREVIEW_ROWS + 1)
+        .collect();
+
+    let Some(first_line) = lines.first() else {
+        preview.error = Some("File is empty".to_string());
+        return preview;
+    };
+
+    let format = format.unwrap_or_else(|| CsvFormat::detect(first_line));
+    preview.format = Some(format);
+    preview.format_name = Some(format.display_name().to_string());
+    preview.candidates = CsvFormat::candidates(first_line);
+
+    let layout = match format.layout(first_line) {
+        Ok(layout) => layout,
+        Err(e) => {
+            preview.error = Some(e);
+            return preview;
+        }
+    };
+
+    let skip = if format.has_header() { 1 } else { 0 };
+    for (index, line) in lines.iter().enumerate().skip(skip).take(PREVIEW_ROWS) {
+        match layout.parse_line(line) {
+            Some(candle) => preview.rows.push(candle),
+            None => {
+                preview.error = Some(format!(
+                    "Row {}: could not parse '{}' as {}",
+                    index + 1,
+                    line,
+                    format.display_name()
+                ));
+                break;
+            }
+        }
+    }
+
+    preview
+}
+
+// Detect the layout of every CSV in a folder and parse its first rows, so the user can
+// check (or override) the format before anything is imported
+#[tauri::command]
+pub async fn preview_chart_folder(
+    folder_path: String,
+    format: Option<CsvFormat>,
+) -> Result<FolderPreview, String> {
+    let path = PathBuf::from(&folder_path);
+
+    if !path.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+
+    let symbol_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid folder name")?
+        .to_uppercase();
+
+    let mut files = Vec::new();
+
+    let entries = fs::read_dir(&path).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+        let file_path = entry.path();
+
+        if file_path.extension().and_then(|s| s.to_str()) == Some("csv") {
+            let timeframe = file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .ok_or("Invalid file name")?
+                .to_uppercase();
+
+            files.push(preview_csv(&file_path, &timeframe, format));
+        }
+    }
+
+    if files.is_empty() {
+        return Err("No CSV files found in the folder".to_string());
+    }
+
+    files.sort_by(|a, b| a.timeframe.cmp(&b.timeframe));
+
+    let failed = files.iter().filter(|f| f.error.is_some()).count();
+
+    Ok(FolderPreview {
+        success: failed == 0,
+        message: if failed == 0 {
+            format!("{} file(s) ready to import", files.len())
+        } else {
+            format!("{} of {} file(s) could not be parsed", failed, files.len())
+        },
+        symbol: symbol_name,
+        files,
+    })
+}
+
+// An explicit column mapping (or a saved profile) replaces format detection. The requested
+// timezone applies to registry formats and to mappings that don't set their own.
+fn resolve_csv_source(
+    app_handle: &AppHandle,
+    format: Option<CsvFormat>,
+    mapping: Option<ColumnMapping>,
+    profile: Option<String>,
+    timezone: Option<String>,
+) -> Result<CsvSource, String> {
+    let mapping = match (mapping, profile) {
+        (Some(mapping), _) => Some(mapping),
+        (None, Some(name)) if !name.trim().is_empty() => {
+            Some(load_import_profile(app_handle, name.trim())?)
+        }
+        _ => None,
+    };
+
+    Ok(match mapping {
+        Some(mut mapping) => {
+            if mapping
+                .timezone
+                .as_deref()
+                .is_none_or(|tz| tz.trim().is_empty())
+            {
+                mapping.timezone = timezone;
+            }
+            mapping.validate()?;
+            CsvSource::Mapping(mapping)
+        }
+        None => {
+            let timezone = match timezone {
+                Some(tz) if !tz.trim().is_empty() => Some(SourceTimezone::parse(&tz)?),
+                _ => None,
+            };
+            CsvSource::Format(format, timezone)
+        }
+    })
+}
+
+// Check every row of every CSV in the folder without importing anything, and report
+// what `policy` would skip or repair
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn validate_chart_folder(
+    app_handle: AppHandle,
+    folder_path: String,
+    format: Option<CsvFormat>,
+    mapping: Option<ColumnMapping>,
+    profile: Option<String>,
+    policy: Option<InvalidRowPolicy>,
+    trim_spikes: Option<bool>,
+    timezone: Option<String>,
+) -> Result<FolderValidation, String> {
+    let path = PathBuf::from(&folder_path);
+
+    if !path.is_dir() {
+        return Err("Folder does not exist".to_string());
+    }
+
+    let symbol_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Invalid folder name")?
+        .to_uppercase();
+
+    let source = resolve_csv_source(&app_handle, format, mapping, profile, timezone)?;
+    let policy = policy.unwrap_or_default();
+
+    let mut reports = Vec::new();
+
+    let entries = fs::read_dir(&path).map_err(|e| format!("Failed to read directory: {}", e))?;
+
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Failed to read entry: {}", e))?;
+        let file_path = entry.path();
+
+        if file_path.extension().and_then(|s| s.to_str()) == Some("csv") {
+            let timeframe = file_path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .ok_or("Invalid file name")?
+                .to_uppercase();
+
+            let (rows, _, _) = read_csv_rows(&file_path, &source)
+                .map_err(|e| format!("Error in {}.csv: {}", timeframe, e))?;
+            let (report, _) = validate_rows(&timeframe, rows, policy, trim_spikes.unwrap_or(false));
+
+            reports.push(report);
+        }
+    }
+
+    if reports.is_empty() {
+        return Err("No CSV files found in the folder".to_string());
+    }
+
+    reports.sort_by(|a, b| a.timeframe.cmp(&b.timeframe));
+
+    let errors: usize = reports.iter().map(|r| r.error_count).sum();
+    let warnings: usize = reports.iter().map(|r| r.warning_count).sum();
+
+    Ok(FolderValidation {
+        success: errors == 0,
+        message: format!(
+            "{} file(s) checked: {} error(s), {} warning(s)",
+            reports.len(),
+            errors,
+            warnings
+        ),
+        symbol: symbol_name,
+        reports,
+    })
+}
+
+// Import a symbol folder. Re-importing replaces the stored symbol unless `merge` asks for the
+// new candles to be spliced into it.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn process_chart_folder(
     app_handle: AppHandle,
     folder_path: String,
+    format: Option<CsvFormat>,
+    mapping: Option<ColumnMapping>,
+    profile: Option<String>,
+    policy: Option<InvalidRowPolicy>,
+    trim_spikes: Option<bool>,
+    timezone: Option<String>,
+    merge: Option<MergePolicy>,
 ) -> Result<ProcessResult, String> {
     let path = PathBuf::from(&folder_path);
 
@@ -66,8 +533,20 @@ pub async fn process_chart_folder(
         .ok_or("Invalid folder name")?
         .to_uppercase();
 
+    let source = resolve_csv_source(&app_handle, format, mapping, profile, timezone)?;
+    let policy = policy.unwrap_or_default();
+
+    let target = match merge {
+        Some(_) => import_target(&app_handle, &symbol_name, merge)?,
+        None => {
+            clear_provenance(&app_handle, &symbol_name)?;
+            symbol_name.clone()
+        }
+    };
+
     let mut timeframes: Vec<TimeframeInfo> = Vec::new();
     let mut total_candles = 0;
+    let mut reports = Vec::new();
 
     let entries = fs::read_dir(&path).map_err(|e| format!("Failed to read directory: {}", e))?;
 
@@ -83,23 +562,29 @@ pub async fn process_chart_folder(
                     .ok_or("Invalid file name")?
                     .to_uppercase();
 
-                match count_and_validate_csv(&file_path) {
-                    Ok(count) => {
-                        let display_name = convert_timeframe_to_display(&timeframe);
-
-                        timeframes.push(TimeframeInfo {
-                            name: timeframe.clone(),
-                            display_name,
-                            candle_count: count,
-                            file_path: file_path.to_string_lossy().to_string(),
-                        });
-
-                        total_candles += count;
-                    }
-                    Err(e) => {
-                        return Err(format!("Error in {}.csv: {}", timeframe, e));
-                    }
+                let (info, report) = import_timeframe(
+                    &app_handle,
+                    &target,
+                    &timeframe,
+                    &file_path,
+                    &source,
+                    policy,
+                    trim_spikes.unwrap_or(false),
+                )
+                .map_err(|e| format!("Error in {}.csv: {}", timeframe, e))?;
+
+                if report.error_count + report.warning_count > 0 {
+                    println!(
+                        "✗ {} - {}, {} warning(s)",
+                        timeframe,
+                        report.summary(),
+                        report.warning_count
+                    );
                 }
+
+                total_candles += info.candle_count;
+                timeframes.push(info);
+                reports.push(report);
             }
         }
     }
@@ -119,14 +604,18 @@ pub async fn process_chart_folder(
     });
 
     let symbol_data = SymbolData {
-        symbol: symbol_name.clone(),
+        symbol: target.clone(),
         timeframes: timeframes.clone(),
         total_candles,
         uploaded_at: chrono::Utc::now().to_rfc3339(),
+        metadata: None,
+        ticks: None,
     };
 
     save_symbol_data(&app_handle, &symbol_data)?;
 
+    let merge = finish_import(&app_handle, &symbol_name, &target, merge, "local")?;
+
     let timeframes_processed: Vec<String> = timeframes.iter().map(|tf| tf.name.clone()).collect();
 
     Ok(ProcessResult {
@@ -144,6 +633,8 @@ pub async fn process_chart_folder(
         symbol: Some(symbol_name),
         timeframes_processed,
         total_candles,
+        reports,
+        merge,
     })
 }
 
@@ -163,6 +654,9 @@ pub async fn delete_symbol(app_handle: AppHandle, symbol: String) -> Result<Dele
     // Delete the JSON file
     fs::remove_file(&json_path).map_err(|e| format!("Failed to delete symbol file: {}", e))?;
 
+    // Remove any candle files stored by the importers
+    let _ = fs::remove_dir_all(app_dir.join("data").join(&symbol));
+
     Ok(DeleteResult {
         success: true,
         message: format!("Symbol '{}' deleted successfully", symbol),
@@ -216,6 +710,25 @@ pub async fn rename_symbol(
     // Update the symbol name
     symbol_data.symbol = new_symbol_upper.clone();
 
+    // Move stored candles and events along with the symbol
+    let data_dir = app_dir.join("data");
+    let old_data_dir = data_dir.join(&old_symbol);
+    let new_data_dir = data_dir.join(&new_symbol_upper);
+
+    if old_data_dir.exists() {
+        fs::rename(&old_data_dir, &new_data_dir)
+            .map_err(|e| format!("Failed to move symbol data: {}", e))?;
+
+        let old_prefix = old_data_dir.to_string_lossy().to_string();
+        let new_prefix = new_data_dir.to_string_lossy().to_string();
+
+        for tf in &mut symbol_data.timeframes {
+            if let Some(rest) = tf.file_path.strip_prefix(&old_prefix) {
+                tf.file_path = format!("{}{}", new_prefix, rest);
+            }
+        }
+    }
+
     // Save with new name
     save_symbol_data(&app_handle, &symbol_data)?;
 
@@ -255,7 +768,7 @@ fn convert_timeframe_to_display(timeframe: &str) -> String {
     }
 }
 
-fn save_symbol_data(app_handle: &AppHandle, symbol_data: &SymbolData) -> Result<(), String> {
+pub fn save_symbol_data(app_handle: &AppHandle, symbol_data: &SymbolData) -> Result<(), String> {
     let app_dir = app_handle
         .path()
         .app_data_dir()
@@ -318,6 +831,10 @@ pub async fn get_available_symbols(app_handle: AppHandle) -> Result<Vec<SymbolDa
 
 #[tauri::command]
 pub async fn get_symbol_data(app_handle: AppHandle, symbol: String) -> Result<SymbolData, String> {
+    load_symbol_data(&app_handle, &symbol)
+}
+
+pub fn load_symbol_data(app_handle: &AppHandle, symbol: &str) -> Result<SymbolData, String> {
     let app_dir = app_handle
         .path()
         .app_data_dir()
@@ -338,12 +855,88 @@ pub async fn get_symbol_data(app_handle: AppHandle, symbol: String) -> Result<Sy
     Ok(symbol_data)
 }
 
-fn is_valid_date_format(s: &str) -> bool {
-    // Check if it matches YYYY.MM.DD or YYYY.MM.DD HH:MM:SS format
-    s.contains('.') && (s.len() >= 10)
+// Validate every row of a timeframe file and decide where its candles live. Clean UTC files in
+// a registry format are used in place; repaired, filtered, mapped or shifted files are
+// normalized into the app's own layout under data/<symbol>/ (UTC timestamps, '.' decimals).
+fn import_timeframe(
+    app_handle: &AppHandle,
+    symbol: &str,
+    timeframe: &str,
+    file_path: &Path,
+    source: &CsvSource,
+    policy: InvalidRowPolicy,
+    trim_spikes: bool,
+) -> Result<(TimeframeInfo, ValidationReport), String> {
+    let (rows, format, timezone) = read_csv_rows(file_path, source)?;
+    let (report, candles) = validate_rows(timeframe, rows, policy, trim_spikes);
+
+    if policy == InvalidRowPolicy::Reject && report.has_errors() {
+        return Err(report.summary());
+    }
+
+    if candles.is_empty() {
+        return Err("No valid data rows found in CSV".to_string());
+    }
+
+    let untouched = report.skipped_rows == 0 && report.repaired_rows == 0 && timezone.is_utc();
+
+    let (stored_path, format) = match format {
+        Some(format) if untouched => (file_path.to_path_buf(), Some(format)),
+        _ => {
+            let app_dir = app_handle
+                .path()
+                .app_data_dir()
+                .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+            let data_dir = app_dir.join("data").join(symbol);
+            fs::create_dir_all(&data_dir)
+                .map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+            let csv_path = data_dir.join(format!("{}.csv", timeframe));
+            write_candles(&csv_path, &candles)?;
+
+            (csv_path, None)
+        }
+    };
+
+    let info = TimeframeInfo {
+        name: timeframe.to_string(),
+        display_name: convert_timeframe_to_display(timeframe),
+        candle_count: candles.len(),
+        file_path: stored_path.to_string_lossy().to_string(),
+        source_sha: None,
+        format,
+        coverage: None,
+        source_timezone: Some(timezone.to_string()),
+    };
+
+    Ok((info, report))
+}
+
+// How the rows of a local CSV are read: through the format registry (with an optional source
+// timezone overriding the format's default) or an explicit mapping
+enum CsvSource {
+    Format(Option<CsvFormat>, Option<SourceTimezone>),
+    Mapping(ColumnMapping),
 }
 
-fn count_and_validate_csv(file_path: &PathBuf) -> Result<usize, String> {
+// Parse every data row with UTC timestamps, keeping the ones that fail so they can be reported
+// by line number. Also returns the registry format used, if any, and the source timezone.
+fn read_csv_rows(
+    file_path: &Path,
+    source: &CsvSource,
+) -> Result<(Vec<ParsedRow>, Option<CsvFormat>, SourceTimezone), String> {
+    let (format, timezone) = match source {
+        CsvSource::Mapping(mapping) => {
+            return Ok((
+                mapping.read_rows(file_path)?,
+                None,
+                mapping.source_timezone()?,
+            ))
+        }
+        CsvSource::Format(format, timezone) => (*format, *timezone),
+    };
+
     let content =
         fs::read_to_string(file_path).map_err(|e| format!("Failed to read file: {}", e))?;
 
@@ -351,114 +944,175 @@ fn count_and_validate_csv(file_path: &PathBuf) -> Result<usize, String> {
         return Err("File is empty".to_string());
     }
 
-    // Detect delimiter - check for tabs first (most common in your data), then comma, then semicolon
     let first_line = content.lines().next().unwrap_or("");
-    let delimiter = if first_line.matches('\t').count() >= 4 {
-        b'\t'
-    } else if first_line.matches(',').count() >= 4 {
-        b','
-    } else if first_line.matches(';').count() >= 4 {
-        b';'
-    } else if first_line.matches(' ').count() >= 7 {
-        // Multiple spaces might be used as delimiter
-        b' '
-    } else {
-        return Err(
-            "Could not detect CSV delimiter (expected tab, comma, or semicolon)".to_string(),
-        );
-    };
 
-    let mut rdr = ReaderBuilder::new()
-        .has_headers(true)
-        .delimiter(delimiter)
-        .flexible(true)
-        .trim(csv::Trim::All)
-        .from_path(file_path)
-        .map_err(|e| format!("Failed to read CSV: {}", e))?;
-
-    // FIX: Clone the headers to release the mutable borrow on 'rdr'
-    let headers = rdr
-        .headers()
-        .map_err(|e| format!("Failed to read headers: {}", e))?
-        .clone();
-
-    if headers.len() < 5 {
-        return Err(format!(
-            "Invalid CSV format: Expected at least 5 columns, found {} columns in header. Your columns: {}",
-            headers.len(),
-            headers.iter().collect::<Vec<_>>().join(", ")
-        ));
-    }
+    // Pick the parser from the registry unless the user chose one in the preview
+    let format = match format {
+        Some(format) => format,
+        None => CSV_FORMATS
+            .into_iter()
+            .find(|format| format.matches(first_line))
+            .ok_or(
+                "Could not detect the CSV format (expected MT4/MT5, HistData, MetaStock, NinjaTrader, TradingView or Yahoo layout)",
+            )?,
+    };
 
-    let mut count = 0;
+    let layout = format.layout(first_line)?;
+    let skip = if format.has_header() { 1 } else { 0 };
+    let timezone = timezone.unwrap_or_else(|| format.default_timezone());
+
+    let rows = content
+        .lines()
+        .enumerate()
+        .skip(skip)
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| ParsedRow {
+            line: index + 1,
+            candle: layout
+                .parse_line(line)
+                .map(|candle| Candle {
+                    // Epoch times are UTC already, only wall-clock dates are shifted
+                    timestamp: if layout.has_absolute_time(line) {
+                        candle.timestamp
+                    } else {
+                        timezone.wall_clock_to_utc(candle.timestamp)
+                    },
+                    ..candle
+                })
+                .ok_or(format!(
+                    "could not parse '{}' as {}",
+                    line.trim(),
+                    format.display_name()
+                )),
+        })
+        .collect();
+
+    Ok((rows, Some(format), timezone))
+}
 
-    // Now 'rdr' is free to be borrowed again here
-    for (index, result) in rdr.records().enumerate() {
-        let record = result.map_err(|e| format!("Row {}: {}", index + 2, e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Skip empty rows
-        if record.iter().all(|field| field.trim().is_empty()) {
-            continue;
-        }
+    fn read_timestamps(content: &str, timezone: &str) -> Vec<i64> {
+        let path = std::env::temp_dir().join(format!(
+            "read_csv_rows_{}_{}.csv",
+            std::process::id(),
+            timezone.replace(['/', ':', '+'], "_")
+        ));
+        fs::write(&path, content).unwrap();
 
-        if record.len() < 5 {
-            return Err(format!(
-                "Row {}: Expected at least 5 columns, found {}",
-                index + 2,
-                record.len()
-            ));
-        }
+        let source = CsvSource::Format(None, Some(SourceTimezone::parse(timezone).unwrap()));
+        let (rows, _, _) = read_csv_rows(&path, &source).unwrap();
+        fs::remove_file(&path).ok();
 
-        // Validate first data row
-        if count == 0 {
-            // Column 0: Check if it's a date string or timestamp
-            let first_col = record.get(0).ok_or("Missing first column")?;
-
-            // Accept either date format (YYYY.MM.DD) or Unix timestamp
-            if !is_valid_date_format(first_col) {
-                // Try parsing as number
-                first_col.parse::<i64>().map_err(|_| {
-                    format!(
-                        "Row {}: Invalid date/timestamp '{}'. Expected format: YYYY.MM.DD or Unix timestamp",
-                        index + 2,
-                        first_col
-                    )
-                })?;
-            }
-
-            // Columns 1-4 (or appropriate OHLC columns): Validate prices
-            let price_cols = if headers.len() >= 8 {
-                // Format: DATE, TIME, OPEN, HIGH, LOW, CLOSE, TICKVOL, VOL, SPREAD
-                // So OPEN is at index 2
-                2..=5
-            } else {
-                // Format: DATE, OPEN, HIGH, LOW, CLOSE
-                1..=4
-            };
+        rows.into_iter()
+            .map(|row| row.candle.unwrap().timestamp)
+            .collect()
+    }
 
-            for col in price_cols {
-                if col < record.len() {
-                    let value_str = record
-                        .get(col)
-                        .ok_or(format!("Missing column {}", col + 1))?;
-                    value_str.parse::<f64>().map_err(|_| {
-                        format!(
-                            "Row {}: Invalid price '{}' in column {}",
-                            index + 2,
-                            value_str,
-                            col + 1
-                        )
-                    })?;
-                }
-            }
+    // First line of a file in each layout, a data line and the candle it holds
+    const LAYOUT_SAMPLES: [(CsvFormat, &str, &str, i64); 7] = [
+        (
+            CsvFormat::Mt4,
+            "2023.01.02,17:00,1.0701,1.0710,1.0695,1.0705,120",
+            "2023.01.02,17:00,1.0701,1.0710,1.0695,1.0705,120",
+            1_672_678_800,
+        ),
+        (
+            CsvFormat::Mt5,
+            "<DATE>\t<TIME>\t<OPEN>\t<HIGH>\t<LOW>\t<CLOSE>\t<TICKVOL>\t<VOL>\t<SPREAD>",
+            "2023.01.02\t17:00:00\t1.0701\t1.0710\t1.0695\t1.0705\t120\t0\t5",
+            1_672_678_800,
+        ),
+        (
+            CsvFormat::MetaStock,
+            "<TICKER>,<DTYYYYMMDD>,<TIME>,<OPEN>,<HIGH>,<LOW>,<CLOSE>,<VOL>",
+            "EURUSD,20230102,170000,1.0701,1.0710,1.0695,1.0705,120",
+            1_672_678_800,
+        ),
+        (
+            CsvFormat::HistDataAscii,
+            "20230102 170000;1.0701;1.0710;1.0695;1.0705;120",
+            "20230102 170000;1.0701;1.0710;1.0695;1.0705;120",
+            1_672_678_800,
+        ),
+        (
+            CsvFormat::TradingView,
+            "time,open,high,low,close,Volume",
+            "1672678800,1.0701,1.0710,1.0695,1.0705,120",
+            1_672_678_800,
+        ),
+        (
+            CsvFormat::YahooCsv,
+            "Date,Open,High,Low,Close,Adj Close,Volume",
+            "2023-01-02,1.0701,1.0710,1.0695,1.0705,1.0600,120",
+            1_672_617_600,
+        ),
+        (
+            CsvFormat::Generic,
+            "datetime;open;high;low;close;volume",
+            "2023-01-02 17:00:00;1.0701;1.0710;1.0695;1.0705;120",
+            1_672_678_800,
+        ),
+    ];
+
+    #[test]
+    fn each_layout_is_detected_and_parsed() {
+        for (format, first_line, data_line, timestamp) in LAYOUT_SAMPLES {
+            assert_eq!(CsvFormat::detect(first_line), format, "{}", first_line);
+            assert_eq!(format.has_header(), first_line != data_line);
+
+            let candle = format
+                .layout(first_line)
+                .unwrap()
+                .parse_line(data_line)
+                .unwrap_or_else(|| panic!("{:?} did not parse {}", format, data_line));
+
+            assert_eq!(candle.timestamp, timestamp, "{:?}", format);
+            assert_eq!(
+                (
+                    candle.open,
+                    candle.high,
+                    candle.low,
+                    candle.close,
+                    candle.volume
+                ),
+                (1.0701, 1.071, 1.0695, 1.0705, 120.0),
+                "{:?}",
+                format
+            );
+            assert_eq!(
+                candle.adj_close,
+                (format == CsvFormat::YahooCsv).then_some(1.06)
+            );
         }
-
-        count += 1;
     }
 
-    if count == 0 {
-        return Err("No valid data rows found in CSV".to_string());
+    #[test]
+    fn histdata_wins_over_ninjatrader_for_intraday_files() {
+        let intraday = "20230102 170000;1.0701;1.0710;1.0695;1.0705;120";
+        assert_eq!(CsvFormat::detect(intraday), CsvFormat::HistDataAscii);
+        assert_eq!(
+            CsvFormat::candidates(intraday)[..2],
+            [CsvFormat::HistDataAscii, CsvFormat::NinjaTrader]
+        );
+
+        // Only NinjaTrader writes daily bars with a bare date
+        let daily = "20230102;1.0701;1.0710;1.0695;1.0705;120";
+        assert_eq!(CsvFormat::detect(daily), CsvFormat::NinjaTrader);
+        assert!(!CsvFormat::HistDataAscii.matches(daily));
     }
 
-    Ok(count)
+    #[test]
+    fn epoch_times_are_not_shifted_by_the_source_timezone() {
+        // TradingView export with unix seconds
+        let epoch = "time,open,high,low,close\n1704067200,1,2,0.5,1.5\n";
+        assert_eq!(read_timestamps(epoch, "+02:00"), vec![1_704_067_200]);
+
+        // Same bar as a wall-clock date two hours ahead of UTC
+        let wall_clock =
+            "Date,Open,High,Low,Close,Adj Close,Volume\n2024-01-01 02:00:00,1,2,0.5,1.5,1.5,10\n";
+        assert_eq!(read_timestamps(wall_clock, "+02:00"), vec![1_704_067_200]);
+    }
 }
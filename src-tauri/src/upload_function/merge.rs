@@ -0,0 +1,852 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use super::candles::{candle_row, candles_to_csv, read_candles, Candle, CSV_HEADER};
+use super::local_upload::{load_symbol_data, save_symbol_data, TimeframeInfo};
+
+// What to keep when both the stored symbol and the new source have a bar at the same time
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    PreferExisting,
+    PreferNew,
+    Average,
+}
+
+// A run of consecutive bars that came from one source
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SourceSpan {
+    pub source: String, // Importer name, or "a+b" for averaged bars
+    pub start: i64,
+    pub end: i64, // Open time of the last bar in the span
+    pub bars: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimeframeMerge {
+    pub timeframe: String,
+    pub existing_bars: usize,
+    pub new_bars: usize,
+    pub overlapping_bars: usize, // Resolved with the policy
+    pub added_bars: usize,
+    pub total_bars: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeReport {
+    pub policy: MergePolicy,
+    pub source: String,
+    pub timeframes: Vec<TimeframeMerge>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeResult {
+    pub success: bool,
+    pub message: String,
+    pub report: MergeReport,
+}
+
+// Timeframe name -> spans, oldest first
+pub type Provenance = HashMap<String, Vec<SourceSpan>>;
+
+fn provenance_path(app_dir: &Path, symbol: &str) -> PathBuf {
+    app_dir.join("data").join(symbol).join("provenance.json")
+}
+
+// Symbols that were never merged have no record
+pub fn load_provenance(app_dir: &Path, symbol: &str) -> Result<Provenance, String> {
+    let path = provenance_path(app_dir, symbol);
+
+    if !path.exists() {
+        return Ok(Provenance::new());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read provenance: {}", e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse provenance: {}", e))
+}
+
+fn save_provenance(app_dir: &Path, symbol: &str, provenance: &Provenance) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(provenance)
+        .map_err(|e| format!("Failed to serialize provenance: {}", e))?;
+
+    fs::write(provenance_path(app_dir, symbol), content)
+        .map_err(|e| format!("Failed to write provenance: {}", e))
+}
+
+// A symbol replaced by a fresh import starts without history
+pub fn clear_provenance(app_handle: &AppHandle, symbol: &str) -> Result<(), String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let _ = fs::remove_file(provenance_path(&app_dir, symbol));
+
+    Ok(())
+}
+
+// Source recorded for the bar at `timestamp`
+fn source_at(spans: &[SourceSpan], timestamp: i64) -> Option<&str> {
+    let idx = spans.partition_point(|span| span.start <= timestamp);
+
+    idx.checked_sub(1)
+        .map(|i| &spans[i])
+        .filter(|span| timestamp <= span.end)
+        .map(|span| span.source.as_str())
+}
+
+// Collapses per-bar sources into spans as the merged bars are produced
+#[derive(Default)]
+struct SpanBuilder {
+    spans: Vec<SourceSpan>,
+}
+
+impl SpanBuilder {
+    fn push(&mut self, timestamp: i64, source: &str) {
+        match self.spans.last_mut() {
+            Some(span) if span.source == source => {
+                span.end = timestamp;
+                span.bars += 1;
+            }
+            _ => self.spans.push(SourceSpan {
+                source: source.to_string(),
+                start: timestamp,
+                end: timestamp,
+                bars: 1,
+            }),
+        }
+    }
+}
+
+fn average(a: &Candle, b: &Candle) -> Candle {
+    let open = (a.open + b.open) / 2.0;
+    let close = (a.close + b.close) / 2.0;
+
+    Candle {
+        timestamp: a.timestamp,
+        open,
+        // Keep the averaged bar consistent with its own open and close
+        high: ((a.high + b.high) / 2.0).max(open).max(close),
+        low: ((a.low + b.low) / 2.0).min(open).min(close),
+        close,
+        volume: (a.volume + b.volume) / 2.0,
+        adj_close: None,
+    }
+}
+
+// Splice `new` into `existing` (both sorted by time) bar by bar, resolving bars present in
+// both with `policy`. Returns the merged candles, their provenance and the overlap count.
+fn merge_candles(
+    existing: &[Candle],
+    existing_spans: &[SourceSpan],
+    existing_source: &str,
+    new: &[Candle],
+    new_source: &str,
+    policy: MergePolicy,
+) -> (Vec<Candle>, Vec<SourceSpan>, usize) {
+    let mut merged = Vec::with_capacity(existing.len() + new.len());
+    let mut spans = SpanBuilder::default();
+    let mut overlapping = 0;
+
+    let (mut i, mut j) = (0, 0);
+
+    loop {
+        let take_existing = match (existing.get(i), new.get(j)) {
+            (None, None) => break,
+            (Some(a), Some(b)) if a.timestamp == b.timestamp => {
+                let old_source = source_at(existing_spans, a.timestamp).unwrap_or(existing_source);
+                overlapping += 1;
+
+                match policy {
+                    MergePolicy::PreferExisting => {
+                        spans.push(a.timestamp, old_source);
+                        merged.push(a.clone());
+                    }
+                    MergePolicy::PreferNew => {
+                        spans.push(b.timestamp, new_source);
+                        merged.push(b.clone());
+                    }
+                    MergePolicy::Average => {
+                        spans.push(a.timestamp, &format!("{}+{}", old_source, new_source));
+                        merged.push(average(a, b));
+                    }
+                }
+
+                i += 1;
+                j += 1;
+                continue;
+            }
+            (Some(a), Some(b)) => a.timestamp < b.timestamp,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+        };
+
+        if take_existing {
+            let a = &existing[i];
+            spans.push(
+                a.timestamp,
+                source_at(existing_spans, a.timestamp).unwrap_or(existing_source),
+            );
+            merged.push(a.clone());
+            i += 1;
+        } else {
+            spans.push(new[j].timestamp, new_source);
+            merged.push(new[j].clone());
+            j += 1;
+        }
+    }
+
+    (merged, spans.spans, overlapping)
+}
+
+// Columns after the app's own six (e.g. Binance's taker_buy_volume), with each bar's values. Only
+// files in the app's layout have them, upstream formats only ever yield the six.
+#[derive(Debug, Default)]
+struct ExtraColumns {
+    names: Vec<String>,
+    values: HashMap<i64, Vec<String>>,
+}
+
+fn read_extra_columns(path: &Path) -> Result<ExtraColumns, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut lines = content.lines();
+
+    let Some(extra_header) = lines
+        .next()
+        .and_then(|header| header.trim().strip_prefix(CSV_HEADER))
+        .and_then(|rest| rest.strip_prefix(','))
+    else {
+        return Ok(ExtraColumns::default());
+    };
+
+    let names: Vec<String> = extra_header
+        .split(',')
+        .map(|n| n.trim().to_string())
+        .collect();
+    let mut values = HashMap::new();
+
+    for line in lines {
+        let fields: Vec<&str> = line.trim().split(',').collect();
+        let Some(timestamp) = fields.first().and_then(|t| t.parse::<i64>().ok()) else {
+            continue;
+        };
+        let extra = (0..names.len())
+            .map(|i| fields.get(6 + i).unwrap_or(&"").to_string())
+            .collect();
+        values.insert(timestamp, extra);
+    }
+
+    Ok(ExtraColumns { names, values })
+}
+
+// Write merged bars in the app's layout, carrying over the extra columns of both files. A bar's
+// extra values come from the side its policy prefers, falling back to the other side. Averaged
+// bars get the mean of both sides' values instead, or nothing when one side lacks it, so a
+// column like adjclose never contradicts the averaged close.
+fn write_merged(
+    path: &Path,
+    candles: &[Candle],
+    existing: &ExtraColumns,
+    new: &ExtraColumns,
+    policy: MergePolicy,
+    averaged: &HashSet<i64>,
+) -> Result<(), String> {
+    let mut names = existing.names.clone();
+    for name in &new.names {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+
+    let csv = if names.is_empty() {
+        candles_to_csv(candles)
+    } else {
+        let (first, second) = match policy {
+            MergePolicy::PreferNew => (new, existing),
+            MergePolicy::PreferExisting | MergePolicy::Average => (existing, new),
+        };
+        let value = |side: &ExtraColumns, timestamp: i64, name: &str| {
+            let column = side.names.iter().position(|n| n == name)?;
+            side.values
+                .get(&timestamp)
+                .map(|row| row[column].clone())
+                .filter(|v| !v.is_empty())
+        };
+        let mean = |timestamp: i64, name: &str| {
+            let a: f64 = value(existing, timestamp, name)?.parse().ok()?;
+            let b: f64 = value(new, timestamp, name)?.parse().ok()?;
+            Some(((a + b) / 2.0).to_string())
+        };
+
+        let mut csv = format!("{},{}\n", CSV_HEADER, names.join(","));
+        for c in candles {
+            csv.push_str(&candle_row(c));
+            for name in &names {
+                csv.push(',');
+                let v = if averaged.contains(&c.timestamp) {
+                    mean(c.timestamp, name)
+                } else {
+                    value(first, c.timestamp, name).or_else(|| value(second, c.timestamp, name))
+                };
+                if let Some(v) = v {
+                    csv.push_str(&v);
+                }
+            }
+            csv.push('\n');
+        }
+        csv
+    };
+
+    fs::write(path, csv).map_err(|e| format!("Failed to write file: {}", e))
+}
+
+fn symbol_json_path(app_handle: &AppHandle, symbol: &str) -> Result<PathBuf, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    Ok(app_dir.join("symbols").join(format!("{}.json", symbol)))
+}
+
+fn remove_symbol_files(app_handle: &AppHandle, symbol: &str) -> Result<(), String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let _ = fs::remove_file(app_dir.join("symbols").join(format!("{}.json", symbol)));
+    let _ = fs::remove_dir_all(app_dir.join("data").join(symbol));
+
+    Ok(())
+}
+
+// Symbol an import should write to. New symbols are written directly; merging into an existing
+// symbol goes through a staging symbol that `finish_import` folds in afterwards.
+pub fn import_target(
+    app_handle: &AppHandle,
+    symbol: &str,
+    merge: Option<MergePolicy>,
+) -> Result<String, String> {
+    if !symbol_json_path(app_handle, symbol)?.exists() {
+        return Ok(symbol.to_string());
+    }
+
+    if merge.is_none() {
+        return Err(format!(
+            "Symbol '{}' already exists. Please delete it first, choose a different name or merge into it.",
+            symbol
+        ));
+    }
+
+    let staging = format!("{}__MERGE", symbol);
+
+    // Left behind by an import that failed halfway
+    remove_symbol_files(app_handle, &staging)?;
+
+    Ok(staging)
+}
+
+// Fold a staged import into its symbol. Does nothing for imports that wrote the symbol directly.
+pub fn finish_import(
+    app_handle: &AppHandle,
+    symbol: &str,
+    target: &str,
+    merge: Option<MergePolicy>,
+    source: &str,
+) -> Result<Option<MergeReport>, String> {
+    let Some(policy) = merge.filter(|_| target != symbol) else {
+        return Ok(None);
+    };
+
+    let result = merge_symbol_into(app_handle, symbol, target, source, policy);
+
+    remove_symbol_files(app_handle, target)?;
+
+    result.map(Some)
+}
+
+// Splice every timeframe of `source_symbol` into `symbol`. Timeframes only the source has are
+// copied over. Merged timeframes are rewritten under data/<symbol>/ with their provenance.
+pub fn merge_symbol_into(
+    app_handle: &AppHandle,
+    symbol: &str,
+    source_symbol: &str,
+    source: &str,
+    policy: MergePolicy,
+) -> Result<MergeReport, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let mut symbol_data = load_symbol_data(app_handle, symbol)?;
+    let source_data = load_symbol_data(app_handle, source_symbol)?;
+
+    let data_dir = app_dir.join("data").join(symbol);
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    let mut provenance = load_provenance(&app_dir, symbol)?;
+
+    // Bars stored before the first merge are credited to the symbol's own importer
+    let existing_source = symbol_data
+        .metadata
+        .as_ref()
+        .map(|m| m.source.clone())
+        .unwrap_or_else(|| "local".to_string());
+
+    let mut timeframes = Vec::new();
+
+    for new_info in &source_data.timeframes {
+        let new_candles = read_candles(Path::new(&new_info.file_path), new_info.format)?;
+        let new_extra = read_extra_columns(Path::new(&new_info.file_path))?;
+        let csv_path = data_dir.join(format!("{}.csv", new_info.name));
+
+        let Some(info) = symbol_data
+            .timeframes
+            .iter_mut()
+            .find(|tf| tf.name == new_info.name)
+        else {
+            write_merged(
+                &csv_path,
+                &new_candles,
+                &ExtraColumns::default(),
+                &new_extra,
+                policy,
+                &HashSet::new(),
+            )?;
+
+            let mut spans = SpanBuilder::default();
+            for candle in &new_candles {
+                spans.push(candle.timestamp, source);
+            }
+            provenance.insert(new_info.name.clone(), spans.spans);
+
+            symbol_data.timeframes.push(TimeframeInfo {
+                name: new_info.name.clone(),
+                display_name: new_info.display_name.clone(),
+                candle_count: new_candles.len(),
+                file_path: csv_path.to_string_lossy().to_string(),
+                source_sha: None,
+                format: None,
+                coverage: None,
+                source_timezone: new_info.source_timezone.clone(),
+            });
+
+            println!("✓ {} - {} candles added", new_info.name, new_candles.len());
+
+            timeframes.push(TimeframeMerge {
+                timeframe: new_info.name.clone(),
+                existing_bars: 0,
+                new_bars: new_candles.len(),
+                overlapping_bars: 0,
+                added_bars: new_candles.len(),
+                total_bars: new_candles.len(),
+            });
+            continue;
+        };
+
+        let existing = read_candles(Path::new(&info.file_path), info.format)?;
+        let existing_extra = read_extra_columns(Path::new(&info.file_path))?;
+        let existing_spans = provenance.remove(&info.name).unwrap_or_default();
+
+        let (merged, spans, overlapping) = merge_candles(
+            &existing,
+            &existing_spans,
+            &existing_source,
+            &new_candles,
+            source,
+            policy,
+        );
+
+        // Bars present on both sides, which the average policy combined
+        let averaged: HashSet<i64> = if policy == MergePolicy::Average {
+            let existing_times: HashSet<i64> = existing.iter().map(|c| c.timestamp).collect();
+            new_candles
+                .iter()
+                .map(|c| c.timestamp)
+                .filter(|t| existing_times.contains(t))
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        write_merged(
+            &csv_path,
+            &merged,
+            &existing_extra,
+            &new_extra,
+            policy,
+            &averaged,
+        )?;
+        provenance.insert(info.name.clone(), spans);
+
+        println!(
+            "✓ {} - {} candles merged ({} overlapping)",
+            info.name,
+            merged.len(),
+            overlapping
+        );
+
+        timeframes.push(TimeframeMerge {
+            timeframe: info.name.clone(),
+            existing_bars: existing.len(),
+            new_bars: new_candles.len(),
+            overlapping_bars: overlapping,
+            added_bars: merged.len() - existing.len(),
+            total_bars: merged.len(),
+        });
+
+        // The stored file is now the app's own layout and no longer matches any upstream blob
+        info.candle_count = merged.len();
+        info.file_path = csv_path.to_string_lossy().to_string();
+        info.format = None;
+        info.source_sha = None;
+        info.coverage = None;
+        if info.source_timezone != new_info.source_timezone {
+            info.source_timezone = None;
+        }
+    }
+
+    // Sort timeframes
+    symbol_data.timeframes.sort_by(|a, b| {
+        let order = [
+            "M1", "M2", "M3", "M4", "M5", "M10", "M15", "M30", "H1", "H2", "H3", "H4", "D1", "W1",
+            "MN1",
+        ];
+        let a_idx = order.iter().position(|&x| x == a.name).unwrap_or(999);
+        let b_idx = order.iter().position(|&x| x == b.name).unwrap_or(999);
+        a_idx.cmp(&b_idx)
+    });
+
+    symbol_data.total_candles = symbol_data
+        .timeframes
+        .iter()
+        .map(|tf| tf.candle_count)
+        .sum();
+    symbol_data.uploaded_at = chrono::Utc::now().to_rfc3339();
+
+    if symbol_data.metadata.is_none() {
+        symbol_data.metadata = source_data.metadata;
+    }
+
+    save_symbol_data(app_handle, &symbol_data)?;
+    save_provenance(&app_dir, symbol, &provenance)?;
+
+    Ok(MergeReport {
+        policy,
+        source: source.to_string(),
+        timeframes,
+    })
+}
+
+// Merge one imported symbol into another, e.g. older history from a GitHub repo into a symbol
+// kept up to date from Yahoo
+#[tauri::command]
+pub async fn merge_symbols(
+    app_handle: AppHandle,
+    symbol: String,
+    source_symbol: String,
+    policy: MergePolicy,
+    keep_source: Option<bool>,
+) -> Result<MergeResult, String> {
+    if symbol == source_symbol {
+        return Err("Cannot merge a symbol into itself".to_string());
+    }
+
+    let source_data = load_symbol_data(&app_handle, &source_symbol)?;
+    let source = source_data
+        .metadata
+        .as_ref()
+        .map(|m| m.source.clone())
+        .unwrap_or_else(|| source_symbol.to_lowercase());
+
+    let report = merge_symbol_into(&app_handle, &symbol, &source_symbol, &source, policy)?;
+
+    if !keep_source.unwrap_or(false) {
+        remove_symbol_files(&app_handle, &source_symbol)?;
+    }
+
+    let added: usize = report.timeframes.iter().map(|tf| tf.added_bars).sum();
+    let overlapping: usize = report.timeframes.iter().map(|tf| tf.overlapping_bars).sum();
+
+    Ok(MergeResult {
+        success: true,
+        message: format!(
+            "Merged {} into {}: {} candles added, {} overlapping",
+            source_symbol, symbol, added, overlapping
+        ),
+        report,
+    })
+}
+
+// Which source each span of every timeframe came from
+#[tauri::command]
+pub async fn get_symbol_provenance(
+    app_handle: AppHandle,
+    symbol: String,
+) -> Result<Provenance, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    load_provenance(&app_dir, &symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(timestamp: i64, close: f64) -> Candle {
+        Candle {
+            timestamp,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1.0,
+            adj_close: None,
+        }
+    }
+
+    fn bar(timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> Candle {
+        Candle {
+            timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume: 1.0,
+            adj_close: None,
+        }
+    }
+
+    fn span(source: &str, start: i64, end: i64, bars: usize) -> SourceSpan {
+        SourceSpan {
+            source: source.to_string(),
+            start,
+            end,
+            bars,
+        }
+    }
+
+    fn summary(spans: &[SourceSpan]) -> Vec<(&str, i64, i64, usize)> {
+        spans
+            .iter()
+            .map(|s| (s.source.as_str(), s.start, s.end, s.bars))
+            .collect()
+    }
+
+    #[test]
+    fn overlapping_bars_follow_the_policy() {
+        let existing = [candle(60, 1.0), candle(120, 2.0)];
+        let new = [candle(120, 4.0), candle(180, 5.0)];
+
+        let closes = |policy| {
+            let (merged, spans, overlapping) =
+                merge_candles(&existing, &[], "github", &new, "yahoo", policy);
+            assert_eq!(overlapping, 1);
+            let closes: Vec<(i64, f64)> = merged.iter().map(|c| (c.timestamp, c.close)).collect();
+            (closes, spans)
+        };
+
+        let (merged, spans) = closes(MergePolicy::PreferExisting);
+        assert_eq!(merged, vec![(60, 1.0), (120, 2.0), (180, 5.0)]);
+        assert_eq!(
+            summary(&spans),
+            vec![("github", 60, 120, 2), ("yahoo", 180, 180, 1)]
+        );
+
+        let (merged, spans) = closes(MergePolicy::PreferNew);
+        assert_eq!(merged, vec![(60, 1.0), (120, 4.0), (180, 5.0)]);
+        assert_eq!(
+            summary(&spans),
+            vec![("github", 60, 60, 1), ("yahoo", 120, 180, 2)]
+        );
+
+        let (merged, spans) = closes(MergePolicy::Average);
+        assert_eq!(merged, vec![(60, 1.0), (120, 3.0), (180, 5.0)]);
+        assert_eq!(
+            summary(&spans),
+            vec![
+                ("github", 60, 60, 1),
+                ("github+yahoo", 120, 120, 1),
+                ("yahoo", 180, 180, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn averaged_bars_contain_their_open_and_close() {
+        // The averaged high (10.5) sits below the averaged close and the low above the open
+        let existing = [bar(60, 8.0, 9.0, 7.0, 9.0)];
+        let new = [bar(60, 10.0, 12.0, 9.5, 14.0)];
+
+        let (merged, _, _) = merge_candles(&existing, &[], "a", &new, "b", MergePolicy::Average);
+        let c = &merged[0];
+
+        assert_eq!((c.open, c.close), (9.0, 11.5));
+        assert!(c.high >= c.open.max(c.close));
+        assert!(c.low <= c.open.min(c.close));
+        assert_eq!((c.high, c.low), (11.5, 8.25));
+    }
+
+    #[test]
+    fn spans_split_and_rejoin_around_recorded_provenance() {
+        let existing: Vec<Candle> = (1..=5).map(|i| candle(i * 60, 1.0)).collect();
+        let recorded = [span("github", 60, 120, 2), span("yahoo", 180, 300, 3)];
+
+        // A new bar inside the yahoo span splits it in two
+        let new = [candle(240, 2.0)];
+        let (_, spans, _) = merge_candles(
+            &existing,
+            &recorded,
+            "local",
+            &new,
+            "binance",
+            MergePolicy::PreferNew,
+        );
+        assert_eq!(
+            summary(&spans),
+            vec![
+                ("github", 60, 120, 2),
+                ("yahoo", 180, 180, 1),
+                ("binance", 240, 240, 1),
+                ("yahoo", 300, 300, 1)
+            ]
+        );
+
+        // Kept bars rejoin the span they came from, so the record comes back unchanged
+        let (_, spans, _) = merge_candles(
+            &existing,
+            &recorded,
+            "local",
+            &new,
+            "binance",
+            MergePolicy::PreferExisting,
+        );
+        assert_eq!(
+            summary(&spans),
+            vec![("github", 60, 120, 2), ("yahoo", 180, 300, 3)]
+        );
+
+        // Bars outside every recorded span are credited to the symbol's importer
+        assert_eq!(source_at(&recorded, 150), None);
+        assert_eq!(source_at(&recorded, 300), Some("yahoo"));
+    }
+
+    #[test]
+    fn averaged_bars_average_extra_columns() {
+        let dir = std::env::temp_dir().join(format!("merge_average_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let existing_path = dir.join("existing.csv");
+        let new_path = dir.join("new.csv");
+        let merged_path = dir.join("merged.csv");
+
+        fs::write(
+            &existing_path,
+            "timestamp,open,high,low,close,volume,adjclose,note\n60,2,2,2,2,1,1.8,x\n120,2,2,2,2,1,1.9,y\n",
+        )
+        .unwrap();
+        fs::write(
+            &new_path,
+            "timestamp,open,high,low,close,volume,adjclose\n120,4,4,4,4,1,3.7\n",
+        )
+        .unwrap();
+
+        let existing = read_extra_columns(&existing_path).unwrap();
+        let new = read_extra_columns(&new_path).unwrap();
+        let candles = [candle(60, 2.0), candle(120, 3.0)];
+        write_merged(
+            &merged_path,
+            &candles,
+            &existing,
+            &new,
+            MergePolicy::Average,
+            &HashSet::from([120]),
+        )
+        .unwrap();
+
+        // The averaged bar averages adjclose and drops the column the new side lacks
+        assert_eq!(
+            fs::read_to_string(&merged_path).unwrap(),
+            "timestamp,open,high,low,close,volume,adjclose,note\n\
+             60,2,2,2,2,1,1.8,x\n\
+             120,3,3,3,3,1,2.8,\n"
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn merged_files_keep_extra_columns() {
+        let dir = std::env::temp_dir().join(format!("merge_extra_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let existing_path = dir.join("existing.csv");
+        let new_path = dir.join("new.csv");
+        let merged_path = dir.join("merged.csv");
+
+        fs::write(
+            &existing_path,
+            "timestamp,open,high,low,close,volume,taker_buy_volume\n60,1,1,1,1,1,0.5\n120,2,2,2,2,1,0.7\n",
+        )
+        .unwrap();
+        fs::write(
+            &new_path,
+            "timestamp,open,high,low,close,volume,taker_buy_volume\n120,3,3,3,3,1,0.9\n180,4,4,4,4,1,0.2\n",
+        )
+        .unwrap();
+
+        let existing = read_extra_columns(&existing_path).unwrap();
+        let new = read_extra_columns(&new_path).unwrap();
+        assert_eq!(existing.names, vec!["taker_buy_volume"]);
+
+        let candles = [candle(60, 1.0), candle(120, 3.0), candle(180, 4.0)];
+        write_merged(
+            &merged_path,
+            &candles,
+            &existing,
+            &new,
+            MergePolicy::PreferNew,
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&merged_path).unwrap(),
+            "timestamp,open,high,low,close,volume,taker_buy_volume\n\
+             60,1,1,1,1,1,0.5\n\
+             120,3,3,3,3,1,0.9\n\
+             180,4,4,4,4,1,0.2\n"
+        );
+
+        // Files without extra columns are written as before
+        fs::write(
+            &existing_path,
+            "timestamp,open,high,low,close,volume\n60,1,1,1,1,1\n",
+        )
+        .unwrap();
+        let plain = read_extra_columns(&existing_path).unwrap();
+        write_merged(
+            &merged_path,
+            &candles[..1],
+            &plain,
+            &ExtraColumns::default(),
+            MergePolicy::PreferExisting,
+            &HashSet::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&merged_path).unwrap(),
+            "timestamp,open,high,low,close,volume\n60,1,1,1,1,1\n"
+        );
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,16 +1,64 @@
 // Module declarations
+pub mod archive;
+pub mod bars;
+pub mod binance;
+pub mod blind;
+pub mod candles;
+pub mod column_mapping;
+pub mod corporate_actions;
+pub mod dukascopy;
+pub mod gaps;
 pub mod github;
 pub mod local_upload;
+pub mod merge;
+pub mod profile;
+pub mod sessions;
+#[cfg(test)]
+mod test_server;
+pub mod ticks;
+pub mod timezone;
+pub mod validation;
 pub mod yfinance;
 
 // Re-export command functions for lib.rs
+pub use candles::get_candles;
+
+pub use binance::fetch_binance_data_command;
+
+pub use blind::{
+    end_blind_replay, record_blind_trade, start_blind_replay, step_blind_replay, BlindReplays,
+};
+
+pub use column_mapping::{
+    delete_import_profile, list_import_profiles, preview_column_mapping, sample_csv_file,
+    save_import_profile,
+};
+
+pub use dukascopy::fetch_dukascopy_data_command;
+
+pub use gaps::analyze_gaps;
+
+pub use merge::{get_symbol_provenance, merge_symbols};
+
+pub use profile::compute_volume_profile;
+
+pub use sessions::{get_default_sessions, get_sessions};
+
+pub use ticks::{
+    get_tick_summary, import_tick_csv, start_tick_replay, step_tick_replay, stop_tick_replay,
+    TickReplays,
+};
+
 pub use local_upload::{
-    delete_symbol, get_available_symbols, get_symbol_data, process_chart_folder, rename_symbol,
+    delete_symbol, get_available_symbols, get_symbol_data, preview_chart_folder,
+    process_chart_folder, rename_symbol, validate_chart_folder,
 };
 
-pub use github::fetch_github_data_command;
+pub use github::{
+    fetch_github_data_command, fetch_github_release_asset_command, list_github_releases,
+};
 
-pub use yfinance::fetch_yfinance_data_command;
+pub use yfinance::{check_yfinance_symbol, fetch_yfinance_data_command, search_yfinance_symbols};
 
 // If you need to use these types elsewhere, uncomment them:
 // pub use local_upload::{DeleteResult, ProcessResult, RenameResult, SymbolData, TimeframeInfo};
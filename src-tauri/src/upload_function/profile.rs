@@ -0,0 +1,571 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use tauri::{AppHandle, Manager};
+
+use super::candles::{load_timeframe_candles, timeframe_seconds};
+use super::gaps::TradingCalendar;
+use super::local_upload::load_symbol_data;
+use super::ticks::for_each_tick;
+
+#[derive(Debug, Deserialize)]
+pub struct ProfileRequest {
+    pub symbol: String,
+    #[serde(default)]
+    pub timeframe: Option<String>, // Candles to use instead of ticks, the lowest stored by default
+    #[serde(default)]
+    pub start: Option<i64>, // Unix seconds, inclusive. A visible chart range passes its edges.
+    #[serde(default)]
+    pub end: Option<i64>, // Unix seconds, exclusive
+    #[serde(default)]
+    pub per_session: bool, // One profile per trading day instead of one for the whole range
+    #[serde(default)]
+    pub row_size: Option<f64>, // Price step of the histogram rows
+    #[serde(default)]
+    pub rows: Option<usize>, // Number of rows when no row size is given
+    #[serde(default)]
+    pub value_area_percent: Option<f64>,
+    #[serde(default)]
+    pub tpo: bool, // Also build market profile letters
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileRow {
+    pub price: f64, // Bottom of the row
+    pub volume: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tpo: Option<String>, // Letters of the 30 minute periods that traded in the row
+}
+
+#[derive(Debug, Serialize)]
+pub struct TpoSummary {
+    pub periods: usize,
+    pub poc: f64,
+    pub value_area_high: f64,
+    pub value_area_low: f64,
+    pub initial_balance_high: Option<f64>, // Range of the first two periods (A and B)
+    pub initial_balance_low: Option<f64>,
+    pub single_prints: usize, // Rows touched by one period only
+}
+
+#[derive(Debug, Serialize)]
+pub struct VolumeProfile {
+    pub start: i64,
+    pub end: i64, // Time of the last bar or tick
+    pub source: String,
+    pub row_size: f64,
+    pub total_volume: f64,
+    pub poc: f64, // Middle of the row with the most volume
+    pub value_area_high: f64,
+    pub value_area_low: f64,
+    pub high_volume_nodes: Vec<f64>,
+    pub low_volume_nodes: Vec<f64>,
+    pub rows: Vec<ProfileRow>, // Bottom to top
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tpo: Option<TpoSummary>,
+}
+
+const DEFAULT_ROWS: usize = 100;
+const MAX_ROWS: usize = 10_000;
+const DEFAULT_VALUE_AREA: f64 = 70.0;
+const TPO_PERIOD_SECONDS: i64 = 1800;
+const TPO_LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// A tick is a bar with no range
+#[derive(Debug, Clone, Copy)]
+struct PriceBar {
+    timestamp: i64,
+    low: f64,
+    high: f64,
+    close: f64,
+    volume: f64,
+}
+
+// Rows on a grid anchored at price 0, so profiles of different sessions line up
+struct Histogram {
+    row_size: f64,
+    first_row: i64,
+    volume: Vec<f64>,
+    letters: Vec<String>,
+}
+
+impl Histogram {
+    fn new(low: f64, high: f64, row_size: f64) -> Result<Self, String> {
+        let first_row = (low / row_size).floor() as i64;
+        let last_row = (high / row_size).floor() as i64;
+        let rows = (last_row - first_row + 1) as usize;
+
+        if rows > MAX_ROWS {
+            return Err(format!(
+                "Row size {} gives {} rows, the limit is {}",
+                row_size, rows, MAX_ROWS
+            ));
+        }
+
+        Ok(Histogram {
+            row_size,
+            first_row,
+            volume: vec![0.0; rows],
+            letters: vec![String::new(); rows],
+        })
+    }
+
+    fn row_of(&self, price: f64) -> usize {
+        let row = (price / self.row_size).floor() as i64 - self.first_row;
+        row.clamp(0, self.volume.len() as i64 - 1) as usize
+    }
+
+    fn row_low(&self, row: usize) -> f64 {
+        (self.first_row + row as i64) as f64 * self.row_size
+    }
+
+    // Volume of a bar is spread uniformly over its high-low range: every row gets the share
+    // of the range it overlaps. This assumes no knowledge of where inside the bar trading
+    // happened, which is all OHLC data tells. Bars without range (ticks) go to their row.
+    fn add_volume(&mut self, bar: &PriceBar) {
+        let range = bar.high - bar.low;
+
+        if range <= 0.0 {
+            let row = self.row_of(bar.close);
+            self.volume[row] += bar.volume;
+            return;
+        }
+
+        for row in self.rows_of(bar) {
+            let row_low = self.row_low(row);
+            let overlap = bar.high.min(row_low + self.row_size) - bar.low.max(row_low);
+            if overlap > 0.0 {
+                self.volume[row] += bar.volume * overlap / range;
+            }
+        }
+    }
+
+    // Rows a bar trades in, a high sitting exactly on a row's bottom doesn't reach into it
+    fn rows_of(&self, bar: &PriceBar) -> std::ops::RangeInclusive<usize> {
+        let (low, mut high) = (self.row_of(bar.low), self.row_of(bar.high));
+        if high > low && self.row_low(high) >= bar.high {
+            high -= 1;
+        }
+        low..=high
+    }
+
+    fn add_letter(&mut self, bar: &PriceBar, letter: char) {
+        for row in self.rows_of(bar) {
+            if !self.letters[row].ends_with(letter) {
+                self.letters[row].push(letter);
+            }
+        }
+    }
+}
+
+// Row with the highest value, ties go to the one nearest the middle of the profile
+fn point_of_control(values: &[f64]) -> usize {
+    let middle = values.len() as f64 / 2.0;
+
+    (0..values.len())
+        .max_by(|&a, &b| {
+            values[a].total_cmp(&values[b]).then(
+                (b as f64 - middle)
+                    .abs()
+                    .total_cmp(&(a as f64 - middle).abs()),
+            )
+        })
+        .unwrap_or(0)
+}
+
+// Grow from the POC one row at a time towards the side with more volume until `percent`
+// of the total is covered. Returns the lowest and highest row inside the value area.
+fn value_area(values: &[f64], poc: usize, percent: f64) -> (usize, usize) {
+    let target = values.iter().sum::<f64>() * percent / 100.0;
+    let (mut low, mut high) = (poc, poc);
+    let mut covered = values.get(poc).copied().unwrap_or(0.0);
+
+    while covered < target && (low > 0 || high + 1 < values.len()) {
+        let below = if low > 0 { values[low - 1] } else { -1.0 };
+        let above = values.get(high + 1).copied().unwrap_or(-1.0);
+
+        if above >= below {
+            high += 1;
+            covered += above;
+        } else {
+            low -= 1;
+            covered += below;
+        }
+    }
+
+    (low, high)
+}
+
+// Peaks and troughs of the volume smoothed over three rows. High-volume nodes are peaks above
+// the average row, low-volume nodes are troughs under half of it.
+fn volume_nodes(values: &[f64]) -> (Vec<usize>, Vec<usize>) {
+    if values.len() < 3 {
+        return (Vec::new(), Vec::new());
+    }
+
+    let smoothed: Vec<f64> = (0..values.len())
+        .map(|i| {
+            let window = &values[i.saturating_sub(1)..(i + 2).min(values.len())];
+            window.iter().sum::<f64>() / window.len() as f64
+        })
+        .collect();
+
+    let average = values.iter().sum::<f64>() / values.len() as f64;
+    let (mut high, mut low) = (Vec::new(), Vec::new());
+
+    for i in 1..smoothed.len() - 1 {
+        let (previous, value, next) = (smoothed[i - 1], smoothed[i], smoothed[i + 1]);
+
+        if value > previous && value >= next && value > average {
+            high.push(i);
+        } else if value < previous && value <= next && value < average / 2.0 {
+            low.push(i);
+        }
+    }
+
+    (high, low)
+}
+
+fn build_profile(
+    bars: &[PriceBar],
+    session_start: i64,
+    source: &str,
+    request: &ProfileRequest,
+) -> Result<VolumeProfile, String> {
+    let low = bars.iter().map(|b| b.low).fold(f64::INFINITY, f64::min);
+    let high = bars
+        .iter()
+        .map(|b| b.high)
+        .fold(f64::NEG_INFINITY, f64::max);
+
+    let row_size = match (request.row_size, request.rows) {
+        (Some(size), _) if size > 0.0 => size,
+        (Some(_), _) => return Err("Row size must be positive".to_string()),
+        (None, rows) => {
+            let rows = rows.unwrap_or(DEFAULT_ROWS).max(1);
+            // A flat range still needs a non-zero row
+            ((high - low) / rows as f64)
+                .max(high.abs() * 1e-6)
+                .max(f64::EPSILON)
+        }
+    };
+
+    let mut histogram = Histogram::new(low, high, row_size)?;
+
+    // Without any reported volume every bar counts once, giving time at price
+    let has_volume = bars.iter().any(|b| b.volume > 0.0);
+
+    for bar in bars {
+        let volume = if has_volume { bar.volume } else { 1.0 };
+        histogram.add_volume(&PriceBar { volume, ..*bar });
+
+        if request.tpo {
+            let period = (bar.timestamp - session_start).div_euclid(TPO_PERIOD_SECONDS);
+            // Pre-market bars of exchange sessions have no letter
+            if period >= 0 {
+                let letter = TPO_LETTERS[period as usize % TPO_LETTERS.len()] as char;
+                histogram.add_letter(bar, letter);
+            }
+        }
+    }
+
+    let percent = request.value_area_percent.unwrap_or(DEFAULT_VALUE_AREA);
+    if !(0.0..=100.0).contains(&percent) {
+        return Err("Value area must be between 0 and 100 percent".to_string());
+    }
+
+    let mid = |row: usize| histogram.row_low(row) + row_size / 2.0;
+
+    let poc = point_of_control(&histogram.volume);
+    let (va_low, va_high) = value_area(&histogram.volume, poc, percent);
+    let (hvn, lvn) = volume_nodes(&histogram.volume);
+
+    let tpo = request.tpo.then(|| {
+        let counts: Vec<f64> = histogram
+            .letters
+            .iter()
+            .map(|letters| letters.len() as f64)
+            .collect();
+        let poc = point_of_control(&counts);
+        let (low, high) = value_area(&counts, poc, percent);
+
+        let first_hour: Vec<&PriceBar> = bars
+            .iter()
+            .filter(|b| (0..2 * TPO_PERIOD_SECONDS).contains(&(b.timestamp - session_start)))
+            .collect();
+
+        TpoSummary {
+            periods: histogram
+                .letters
+                .iter()
+                .flat_map(|letters| letters.chars())
+                .collect::<HashSet<_>>()
+                .len(),
+            poc: mid(poc),
+            value_area_high: histogram.row_low(high) + row_size,
+            value_area_low: histogram.row_low(low),
+            initial_balance_high: first_hour.iter().map(|b| b.high).reduce(f64::max),
+            initial_balance_low: first_hour.iter().map(|b| b.low).reduce(f64::min),
+            single_prints: counts.iter().filter(|&&count| count == 1.0).count(),
+        }
+    });
+
+    Ok(VolumeProfile {
+        start: bars.first().map(|b| b.timestamp).unwrap_or(session_start),
+        end: bars.last().map(|b| b.timestamp).unwrap_or(session_start),
+        source: source.to_string(),
+        row_size,
+        total_volume: histogram.volume.iter().sum(),
+        poc: mid(poc),
+        value_area_high: histogram.row_low(va_high) + row_size,
+        value_area_low: histogram.row_low(va_low),
+        high_volume_nodes: hvn.into_iter().map(mid).collect(),
+        low_volume_nodes: lvn.into_iter().map(mid).collect(),
+        rows: histogram
+            .volume
+            .iter()
+            .enumerate()
+            .map(|(row, &volume)| ProfileRow {
+                price: histogram.row_low(row),
+                volume,
+                tpo: request.tpo.then(|| histogram.letters[row].clone()),
+            })
+            .collect(),
+        tpo,
+    })
+}
+
+// Volume-at-price for a time range, or for each trading session in it. Uses stored ticks
+// when the symbol has them and no timeframe is asked for, candles otherwise.
+#[tauri::command]
+pub async fn compute_volume_profile(
+    app_handle: AppHandle,
+    request: ProfileRequest,
+) -> Result<Vec<VolumeProfile>, String> {
+    let symbol_data = load_symbol_data(&app_handle, &request.symbol)?;
+
+    let start = request.start.unwrap_or(i64::MIN / 1000);
+    let end = request.end.unwrap_or(i64::MAX / 1000);
+
+    if start >= end {
+        return Err("Profile start must be before its end".to_string());
+    }
+
+    let (bars, source) = if symbol_data.ticks.is_some() && request.timeframe.is_none() {
+        let app_dir = app_handle
+            .path()
+            .app_data_dir()
+            .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+        let mut bars = Vec::new();
+        for_each_tick(
+            &app_dir,
+            &symbol_data.symbol,
+            Some((start * 1000, end * 1000)),
+            |tick| {
+                let price = tick.price();
+                bars.push(PriceBar {
+                    timestamp: tick.timestamp_ms.div_euclid(1000),
+                    low: price,
+                    high: price,
+                    close: price,
+                    volume: tick.volume,
+                });
+            },
+        )?;
+
+        (bars, "ticks".to_string())
+    } else {
+        let timeframe = match &request.timeframe {
+            Some(timeframe) => timeframe.clone(),
+            None => symbol_data
+                .timeframes
+                .iter()
+                .filter_map(|tf| Some((timeframe_seconds(&tf.name)?, &tf.name)))
+                .min()
+                .map(|(_, name)| name.clone())
+                .ok_or(format!("{} has no candles", symbol_data.symbol))?,
+        };
+
+        if request.tpo && timeframe_seconds(&timeframe).is_none_or(|s| s > TPO_PERIOD_SECONDS) {
+            return Err(format!("TPO needs M30 or lower data, got {}", timeframe));
+        }
+
+        let bars = load_timeframe_candles(&symbol_data, &timeframe)?
+            .into_iter()
+            .filter(|c| c.timestamp >= start && c.timestamp < end)
+            .map(|c| PriceBar {
+                timestamp: c.timestamp,
+                low: c.low,
+                high: c.high,
+                close: c.close,
+                volume: c.volume,
+            })
+            .collect();
+
+        (bars, format!("{} candles", timeframe))
+    };
+
+    if bars.is_empty() {
+        return Err("No data in the selected range".to_string());
+    }
+
+    let calendar = TradingCalendar::from_metadata(symbol_data.metadata.as_ref(), &[]);
+
+    let profiles = if request.per_session {
+        let mut sessions: BTreeMap<i64, Vec<PriceBar>> = BTreeMap::new();
+        for bar in bars {
+            sessions
+                .entry(calendar.session_start(bar.timestamp))
+                .or_default()
+                .push(bar);
+        }
+
+        sessions
+            .iter()
+            .map(|(session_start, bars)| build_profile(bars, *session_start, &source, &request))
+            .collect::<Result<Vec<_>, _>>()?
+    } else {
+        let session_start = calendar.session_start(bars[0].timestamp);
+
+        // Letters restart every session, so one profile over several would reuse them
+        let last_session = bars.last().map(|bar| calendar.session_start(bar.timestamp));
+        if request.tpo && last_session != Some(session_start) {
+            return Err(
+                "TPO letters need a profile per session when the range spans several sessions"
+                    .to_string(),
+            );
+        }
+
+        vec![build_profile(&bars, session_start, &source, &request)?]
+    };
+
+    println!(
+        "✓ {} profile(s) for {} from {}",
+        profiles.len(),
+        symbol_data.symbol,
+        source
+    );
+
+    Ok(profiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(low: f64, high: f64, volume: f64) -> PriceBar {
+        PriceBar {
+            timestamp: 0,
+            low,
+            high,
+            close: high,
+            volume,
+        }
+    }
+
+    #[test]
+    fn bar_volume_is_spread_over_the_rows_it_overlaps() {
+        // Rows 10, 11 and 12
+        let mut histogram = Histogram::new(10.0, 12.0, 1.0).unwrap();
+        assert_eq!(histogram.volume.len(), 3);
+
+        histogram.add_volume(&bar(10.5, 11.5, 3.0));
+        assert_eq!(histogram.volume, vec![1.5, 1.5, 0.0]);
+
+        // A quarter of the range in row 10, the rest in row 11
+        histogram.add_volume(&bar(10.75, 11.75, 4.0));
+        assert_eq!(histogram.volume, vec![2.5, 4.5, 0.0]);
+
+        // A tick goes whole to its row
+        histogram.add_volume(&bar(12.0, 12.0, 5.0));
+        assert_eq!(histogram.volume, vec![2.5, 4.5, 5.0]);
+    }
+
+    #[test]
+    fn a_high_on_a_row_boundary_stays_out_of_that_row() {
+        let mut histogram = Histogram::new(10.0, 12.0, 1.0).unwrap();
+        let on_boundary = bar(10.0, 12.0, 4.0);
+
+        assert_eq!(histogram.rows_of(&on_boundary), 0..=1);
+
+        histogram.add_volume(&on_boundary);
+        histogram.add_letter(&on_boundary, 'A');
+        assert_eq!(histogram.volume, vec![2.0, 2.0, 0.0]);
+        assert_eq!(histogram.letters, vec!["A", "A", ""]);
+
+        // A tick on the boundary belongs to the row above
+        assert_eq!(histogram.rows_of(&bar(12.0, 12.0, 1.0)), 2..=2);
+    }
+
+    #[test]
+    fn point_of_control_ties_go_to_the_middle() {
+        assert_eq!(point_of_control(&[1.0, 3.0, 2.0]), 1);
+        assert_eq!(point_of_control(&[5.0, 1.0, 1.0, 1.0, 5.0, 1.0]), 4);
+        assert_eq!(point_of_control(&[1.0, 5.0, 5.0, 1.0]), 2);
+        assert_eq!(point_of_control(&[]), 0);
+    }
+
+    #[test]
+    fn value_area_grows_towards_the_heavier_side() {
+        // 70% of 18 is 12.6: the POC's 10 plus the 3 above it
+        let values = [1.0, 2.0, 10.0, 3.0, 1.0, 1.0];
+        assert_eq!(value_area(&values, 2, 70.0), (2, 3));
+        assert_eq!(value_area(&values, 2, 80.0), (1, 3));
+        assert_eq!(value_area(&values, 2, 100.0), (0, 5));
+
+        // Even sides go up first
+        assert_eq!(value_area(&[2.0, 5.0, 2.0], 1, 60.0), (1, 2));
+    }
+
+    #[test]
+    fn volume_nodes_are_smoothed_peaks_and_troughs() {
+        // Smoothed: 1.5, 3, 3.33, 3, 1, 0.33, 0, 0.33, 0.67, 1 against an average of 1.4
+        let values = [1.0, 2.0, 6.0, 2.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0];
+        assert_eq!(volume_nodes(&values), (vec![2], vec![6]));
+
+        assert_eq!(volume_nodes(&[1.0, 5.0]), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn profile_prices_come_from_the_rows() {
+        let request = ProfileRequest {
+            symbol: "TEST".to_string(),
+            timeframe: None,
+            start: None,
+            end: None,
+            per_session: false,
+            row_size: Some(1.0),
+            rows: None,
+            value_area_percent: None,
+            tpo: false,
+        };
+
+        // Volume per row: 2, 4, 0
+        let profile = build_profile(
+            &[bar(10.0, 12.0, 4.0), bar(11.0, 12.0, 2.0)],
+            0,
+            "M1 candles",
+            &request,
+        )
+        .unwrap();
+
+        assert_eq!(profile.total_volume, 6.0);
+        assert_eq!(profile.poc, 11.5);
+        assert_eq!(
+            (profile.value_area_low, profile.value_area_high),
+            (10.0, 12.0)
+        );
+        let rows: Vec<(f64, f64)> = profile.rows.iter().map(|r| (r.price, r.volume)).collect();
+        assert_eq!(rows, vec![(10.0, 2.0), (11.0, 4.0), (12.0, 0.0)]);
+
+        // Without volume every bar counts once
+        let profile = build_profile(
+            &[bar(10.0, 11.0, 0.0), bar(10.0, 11.0, 0.0)],
+            0,
+            "M1 candles",
+            &request,
+        )
+        .unwrap();
+        assert_eq!(profile.total_volume, 2.0);
+    }
+}
@@ -0,0 +1,458 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use super::candles::{load_timeframe_candles, timeframe_seconds, Candle};
+use super::local_upload::load_symbol_data;
+use super::timezone::SourceTimezone;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionKind {
+    Session,
+    Killzone,
+}
+
+// A recurring window in local time. The timezone takes anything SourceTimezone parses, so
+// IANA zones and fixed offsets with a US/EU DST rule both work.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionDefinition {
+    pub name: String,
+    pub kind: SessionKind,
+    pub timezone: String,
+    pub start: String, // "HH:MM" local
+    pub end: String,   // "HH:MM" local, at or before start means the next day
+    #[serde(default)]
+    pub days: Option<Vec<String>>, // Local weekdays it opens on ("Mon".."Sun"), Mon-Fri by default
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionStats {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub range: f64,
+    pub high_time: i64,
+    pub low_time: i64,
+    pub bars: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionWindow {
+    pub name: String,
+    pub kind: SessionKind,
+    pub open: i64,  // UTC unix seconds
+    pub close: i64, // UTC unix seconds, exclusive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SessionStats>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionRequest {
+    pub start: i64, // Unix seconds
+    pub end: i64,
+    #[serde(default)]
+    pub sessions: Option<Vec<SessionDefinition>>, // The built-in sessions and killzones when missing
+    #[serde(default)]
+    pub symbol: Option<String>, // Adds high/low/range from this symbol's candles
+    #[serde(default)]
+    pub timeframe: Option<String>, // Candles for the stats, the lowest stored by default
+}
+
+// Enough for years of daily sessions and killzones without flooding the frontend
+const MAX_WINDOWS: usize = 50_000;
+
+fn definition(
+    name: &str,
+    kind: SessionKind,
+    timezone: &str,
+    start: &str,
+    end: &str,
+) -> SessionDefinition {
+    SessionDefinition {
+        name: name.to_string(),
+        kind,
+        timezone: timezone.to_string(),
+        start: start.to_string(),
+        end: end.to_string(),
+        days: None,
+    }
+}
+
+// The four FX sessions in their exchanges' local time, and the ICT killzones in New York time
+pub fn default_sessions() -> Vec<SessionDefinition> {
+    let mut asian_killzone = definition(
+        "Asian Killzone",
+        SessionKind::Killzone,
+        "America/New_York",
+        "20:00",
+        "00:00",
+    );
+    asian_killzone.days = Some(
+        ["Sun", "Mon", "Tue", "Wed", "Thu"]
+            .map(String::from)
+            .to_vec(),
+    );
+
+    vec![
+        definition(
+            "Sydney",
+            SessionKind::Session,
+            "Australia/Sydney",
+            "07:00",
+            "16:00",
+        ),
+        definition(
+            "Tokyo",
+            SessionKind::Session,
+            "Asia/Tokyo",
+            "09:00",
+            "18:00",
+        ),
+        definition(
+            "London",
+            SessionKind::Session,
+            "Europe/London",
+            "08:00",
+            "17:00",
+        ),
+        definition(
+            "New York",
+            SessionKind::Session,
+            "America/New_York",
+            "08:00",
+            "17:00",
+        ),
+        asian_killzone,
+        definition(
+            "London Open Killzone",
+            SessionKind::Killzone,
+            "America/New_York",
+            "02:00",
+            "05:00",
+        ),
+        definition(
+            "New York Open Killzone",
+            SessionKind::Killzone,
+            "America/New_York",
+            "07:00",
+            "10:00",
+        ),
+        definition(
+            "London Close Killzone",
+            SessionKind::Killzone,
+            "America/New_York",
+            "10:00",
+            "12:00",
+        ),
+    ]
+}
+
+// A definition checked and parsed once, before walking the dates
+struct ParsedSession<'a> {
+    definition: &'a SessionDefinition,
+    timezone: SourceTimezone,
+    start: NaiveTime,
+    end: NaiveTime,
+    days: Vec<Weekday>,
+}
+
+fn parse_session(definition: &SessionDefinition) -> Result<ParsedSession<'_>, String> {
+    let time = |value: &str| {
+        NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| {
+            format!(
+                "{}: invalid time '{}', expected HH:MM",
+                definition.name, value
+            )
+        })
+    };
+
+    let days = match &definition.days {
+        Some(days) => days
+            .iter()
+            .map(|day| {
+                day.trim()
+                    .parse::<Weekday>()
+                    .map_err(|_| format!("{}: invalid weekday '{}'", definition.name, day))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => vec![
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ],
+    };
+
+    Ok(ParsedSession {
+        definition,
+        timezone: SourceTimezone::parse(&definition.timezone)
+            .map_err(|e| format!("{}: {}", definition.name, e))?,
+        start: time(&definition.start)?,
+        end: time(&definition.end)?,
+        days,
+    })
+}
+
+// UTC open and close of every window overlapping [start, end). Each local date is converted
+// on its own, so windows follow the zone's DST changes.
+pub fn session_windows(
+    definitions: &[SessionDefinition],
+    start: i64,
+    end: i64,
+) -> Result<Vec<SessionWindow>, String> {
+    let sessions = definitions
+        .iter()
+        .map(parse_session)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (Some(first), Some(last)) = (
+        DateTime::from_timestamp(start, 0),
+        DateTime::from_timestamp(end, 0),
+    ) else {
+        return Err("Invalid session range".to_string());
+    };
+
+    let mut windows = Vec::new();
+
+    for session in &sessions {
+        // A day of slack on both sides covers every UTC offset
+        let mut date = first.date_naive() - Duration::days(1);
+
+        while date <= last.date_naive() + Duration::days(1) {
+            if session.days.contains(&date.weekday()) {
+                let close_date = if session.end <= session.start {
+                    date + Duration::days(1)
+                } else {
+                    date
+                };
+
+                let open = session.timezone.local_to_utc(date.and_time(session.start));
+                let close = session
+                    .timezone
+                    .local_to_utc(close_date.and_time(session.end));
+
+                if open < end && close > start {
+                    windows.push(SessionWindow {
+                        name: session.definition.name.clone(),
+                        kind: session.definition.kind,
+                        open,
+                        close,
+                        stats: None,
+                    });
+                }
+            }
+
+            date += Duration::days(1);
+        }
+
+        if windows.len() > MAX_WINDOWS {
+            return Err(format!(
+                "More than {} session windows, choose a shorter range",
+                MAX_WINDOWS
+            ));
+        }
+    }
+
+    windows.sort_by_key(|window| (window.open, window.close));
+
+    Ok(windows)
+}
+
+// Open, high, low and close of the candles opening inside the window
+fn session_stats(candles: &[Candle], open: i64, close: i64) -> Option<SessionStats> {
+    let first = candles.partition_point(|c| c.timestamp < open);
+    let last = candles.partition_point(|c| c.timestamp < close);
+    let bars = candles.get(first..last).filter(|bars| !bars.is_empty())?;
+
+    let high = bars.iter().max_by(|a, b| a.high.total_cmp(&b.high))?;
+    let low = bars.iter().min_by(|a, b| a.low.total_cmp(&b.low))?;
+
+    Some(SessionStats {
+        open: bars[0].open,
+        high: high.high,
+        low: low.low,
+        close: bars[bars.len() - 1].close,
+        range: high.high - low.low,
+        high_time: high.timestamp,
+        low_time: low.timestamp,
+        bars: bars.len(),
+    })
+}
+
+// Session and killzone windows for a date range, with each window's high, low and range
+// when a symbol is given
+#[tauri::command]
+pub async fn get_sessions(
+    app_handle: AppHandle,
+    request: SessionRequest,
+) -> Result<Vec<SessionWindow>, String> {
+    if request.start >= request.end {
+        return Err("Session range start must be before its end".to_string());
+    }
+
+    let definitions = request.sessions.unwrap_or_else(default_sessions);
+    let mut windows = session_windows(&definitions, request.start, request.end)?;
+
+    if let Some(symbol) = &request.symbol {
+        let symbol_data = load_symbol_data(&app_handle, symbol)?;
+
+        let timeframe = match &request.timeframe {
+            Some(timeframe) => timeframe.clone(),
+            None => symbol_data
+                .timeframes
+                .iter()
+                .filter_map(|tf| Some((timeframe_seconds(&tf.name)?, &tf.name)))
+                .min()
+                .map(|(_, name)| name.clone())
+                .ok_or(format!("{} has no candles", symbol))?,
+        };
+
+        // Anything coarser than an hour can't place a session's high and low
+        if timeframe_seconds(&timeframe).is_none_or(|seconds| seconds > 3600) {
+            return Err(format!(
+                "Session stats need H1 or lower data, got {}",
+                timeframe
+            ));
+        }
+
+        let candles = load_timeframe_candles(&symbol_data, &timeframe)?;
+
+        for window in &mut windows {
+            window.stats = session_stats(&candles, window.open, window.close);
+        }
+    }
+
+    Ok(windows)
+}
+
+// Built-in definitions, for the frontend to show and edit
+#[tauri::command]
+pub async fn get_default_sessions() -> Result<Vec<SessionDefinition>, String> {
+    Ok(default_sessions())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ts(text: &str) -> i64 {
+        DateTime::parse_from_rfc3339(text).unwrap().timestamp()
+    }
+
+    fn builtin(name: &str) -> SessionDefinition {
+        default_sessions()
+            .into_iter()
+            .find(|session| session.name == name)
+            .unwrap()
+    }
+
+    // UTC open and close of the window that opens on `day`
+    fn opening_on(session: &SessionDefinition, day: &str) -> (i64, i64) {
+        let start = ts(&format!("{}T00:00:00Z", day));
+        let windows =
+            session_windows(std::slice::from_ref(session), start, start + 86_400).unwrap();
+
+        windows
+            .iter()
+            .find(|window| window.open >= start)
+            .map(|window| (window.open, window.close))
+            .unwrap()
+    }
+
+    #[test]
+    fn london_and_new_york_follow_their_own_dst() {
+        let (london, new_york) = (builtin("London"), builtin("New York"));
+
+        // (day, London open and close, New York open and close), all in UTC. The US moved its
+        // clocks on 10 March and 3 November 2024, Europe on 31 March and 27 October.
+        let cases = [
+            ("2024-03-06", "08:00", "17:00", "13:00", "22:00"),
+            ("2024-03-13", "08:00", "17:00", "12:00", "21:00"),
+            ("2024-04-03", "07:00", "16:00", "12:00", "21:00"),
+            ("2024-10-23", "07:00", "16:00", "12:00", "21:00"),
+            ("2024-10-30", "08:00", "17:00", "12:00", "21:00"),
+            ("2024-11-06", "08:00", "17:00", "13:00", "22:00"),
+        ];
+
+        for (day, london_open, london_close, new_york_open, new_york_close) in cases {
+            let utc = |time: &str| ts(&format!("{}T{}:00Z", day, time));
+
+            assert_eq!(
+                opening_on(&london, day),
+                (utc(london_open), utc(london_close)),
+                "London on {}",
+                day
+            );
+            assert_eq!(
+                opening_on(&new_york, day),
+                (utc(new_york_open), utc(new_york_close)),
+                "New York on {}",
+                day
+            );
+        }
+    }
+
+    #[test]
+    fn overnight_killzones_close_the_next_day() {
+        let asian = builtin("Asian Killzone");
+
+        // Monday 4 March to Tuesday 12 March 2024, across the US change on Sunday 10 March
+        let windows = session_windows(
+            &[asian],
+            ts("2024-03-04T00:00:00Z"),
+            ts("2024-03-12T00:00:00Z"),
+        )
+        .unwrap();
+
+        let found: Vec<(i64, i64)> = windows.iter().map(|w| (w.open, w.close)).collect();
+        assert_eq!(
+            found,
+            vec![
+                // Sunday to Thursday evenings in New York, nothing on Friday or Saturday
+                (ts("2024-03-04T01:00:00Z"), ts("2024-03-04T05:00:00Z")),
+                (ts("2024-03-05T01:00:00Z"), ts("2024-03-05T05:00:00Z")),
+                (ts("2024-03-06T01:00:00Z"), ts("2024-03-06T05:00:00Z")),
+                (ts("2024-03-07T01:00:00Z"), ts("2024-03-07T05:00:00Z")),
+                (ts("2024-03-08T01:00:00Z"), ts("2024-03-08T05:00:00Z")),
+                (ts("2024-03-11T00:00:00Z"), ts("2024-03-11T04:00:00Z")),
+            ]
+        );
+        assert!(windows.iter().all(|w| w.kind == SessionKind::Killzone));
+    }
+
+    #[test]
+    fn windows_overlapping_the_range_edges_are_kept() {
+        // London on Wednesday 6 March 2024 runs 08:00-17:00 UTC
+        let london = builtin("London");
+
+        let windows = session_windows(
+            std::slice::from_ref(&london),
+            ts("2024-03-06T16:00:00Z"),
+            ts("2024-03-06T18:00:00Z"),
+        )
+        .unwrap();
+        assert_eq!(windows.len(), 1);
+
+        let windows = session_windows(
+            &[london],
+            ts("2024-03-06T17:00:00Z"),
+            ts("2024-03-07T08:00:00Z"),
+        )
+        .unwrap();
+        assert!(windows.is_empty());
+    }
+
+    #[test]
+    fn invalid_definitions_are_rejected() {
+        let mut session = builtin("London");
+        session.start = "8am".to_string();
+        assert!(session_windows(&[session], 0, 86_400).is_err());
+
+        let mut session = builtin("London");
+        session.days = Some(vec!["Funday".to_string()]);
+        assert!(session_windows(&[session], 0, 86_400).is_err());
+    }
+}
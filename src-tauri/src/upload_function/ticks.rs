@@ -0,0 +1,904 @@
+use chrono::{DateTime, NaiveDateTime, Timelike};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+use crate::analysis::patterns::{PatternConfig, PatternHit, PatternScanner};
+use crate::analysis::structure::{StructureConfig, StructureEvent, StructureTracker};
+
+use super::bars::{candle_path, BarBuilder, BarType, DEFAULT_ATR_PERIOD};
+use super::candles::{bar_start, load_timeframe_candles, write_candles, Candle};
+use super::local_upload::{load_symbol_data, save_symbol_data, SymbolData, TimeframeInfo};
+use super::timezone::{resolve_timezone, SourceTimezone};
+
+// Tick flags, same bits as MT5's MqlTick.flags
+pub const TICK_FLAG_BID: u32 = 2;
+pub const TICK_FLAG_ASK: u32 = 4;
+pub const TICK_FLAG_LAST: u32 = 8;
+pub const TICK_FLAG_VOLUME: u32 = 16;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct TickRecord {
+    pub timestamp_ms: i64, // UTC
+    pub bid: f64,
+    pub ask: f64,
+    pub last: f64, // 0 for quote-only feeds (FX, Dukascopy)
+    pub volume: f64,
+    pub flags: u32,
+}
+
+impl TickRecord {
+    // Price candles are built from: bid like MT4/MT5 charts, last for exchange trades without quotes
+    pub fn price(&self) -> f64 {
+        if self.bid > 0.0 {
+            self.bid
+        } else {
+            self.last
+        }
+    }
+
+    // Tick volume when the feed has no traded volume
+    pub fn volume_or_count(&self) -> f64 {
+        if self.volume > 0.0 {
+            self.volume
+        } else {
+            1.0
+        }
+    }
+}
+
+// What a symbol has in its tick store
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TickSummary {
+    pub count: usize,
+    pub first_ms: i64,
+    pub last_ms: i64,
+    pub days: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct DaySummary {
+    count: usize,
+    first_ms: i64,
+    last_ms: i64,
+}
+
+// One gzip file of fixed-size little-endian records per UTC day, plus an index so summaries
+// don't need to decompress anything:
+//   data/<SYM>/ticks/index.json
+//   data/<SYM>/ticks/2024-01-02.ticks.gz
+type TickIndex = BTreeMap<String, DaySummary>;
+
+const TICK_MAGIC: &[u8; 4] = b"TCK1";
+const RECORD_SIZE: usize = 44; // i64 time, 4 x f64 prices/volume, u32 flags
+
+fn ticks_dir(app_dir: &Path, symbol: &str) -> PathBuf {
+    app_dir.join("data").join(symbol).join("ticks")
+}
+
+fn day_path(dir: &Path, day: &str) -> PathBuf {
+    dir.join(format!("{}.ticks.gz", day))
+}
+
+fn day_key(timestamp_ms: i64) -> String {
+    DateTime::from_timestamp_millis(timestamp_ms)
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn load_index(dir: &Path) -> Result<TickIndex, String> {
+    let path = dir.join("index.json");
+
+    if !path.exists() {
+        return Ok(TickIndex::new());
+    }
+
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read tick index: {}", e))?;
+
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse tick index: {}", e))
+}
+
+fn save_index(dir: &Path, index: &TickIndex) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(index)
+        .map_err(|e| format!("Failed to serialize tick index: {}", e))?;
+
+    fs::write(dir.join("index.json"), content)
+        .map_err(|e| format!("Failed to write tick index: {}", e))
+}
+
+fn summarize(index: &TickIndex) -> Option<TickSummary> {
+    Some(TickSummary {
+        count: index.values().map(|day| day.count).sum(),
+        first_ms: index.values().next()?.first_ms,
+        last_ms: index.values().next_back()?.last_ms,
+        days: index.len(),
+    })
+}
+
+fn write_day(path: &Path, ticks: &[TickRecord]) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Failed to create tick file: {}", e))?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+
+    let mut write = |bytes: &[u8]| {
+        encoder
+            .write_all(bytes)
+            .map_err(|e| format!("Failed to write tick file: {}", e))
+    };
+
+    write(TICK_MAGIC)?;
+
+    for tick in ticks {
+        write(&tick.timestamp_ms.to_le_bytes())?;
+        write(&tick.bid.to_le_bytes())?;
+        write(&tick.ask.to_le_bytes())?;
+        write(&tick.last.to_le_bytes())?;
+        write(&tick.volume.to_le_bytes())?;
+        write(&tick.flags.to_le_bytes())?;
+    }
+
+    encoder
+        .finish()
+        .and_then(|mut writer| writer.flush())
+        .map_err(|e| format!("Failed to write tick file: {}", e))
+}
+
+fn read_day(path: &Path) -> Result<Vec<TickRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open tick file: {}", e))?;
+
+    let mut bytes = Vec::new();
+    GzDecoder::new(BufReader::new(file))
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to decompress tick file: {}", e))?;
+
+    let records = bytes
+        .strip_prefix(TICK_MAGIC)
+        .ok_or(format!("{} is not a tick file", path.display()))?;
+
+    let f64_at =
+        |r: &[u8], at: usize| f64::from_le_bytes(r[at..at + 8].try_into().unwrap_or([0; 8]));
+
+    Ok(records
+        .chunks_exact(RECORD_SIZE)
+        .map(|r| TickRecord {
+            timestamp_ms: i64::from_le_bytes(r[0..8].try_into().unwrap_or([0; 8])),
+            bid: f64_at(r, 8),
+            ask: f64_at(r, 16),
+            last: f64_at(r, 24),
+            volume: f64_at(r, 32),
+            flags: u32::from_le_bytes(r[40..44].try_into().unwrap_or([0; 4])),
+        })
+        .collect())
+}
+
+// Visit the stored ticks of a symbol in time order, optionally only those in
+// [start_ms, end_ms), one day in memory at a time
+pub fn for_each_tick(
+    app_dir: &Path,
+    symbol: &str,
+    range: Option<(i64, i64)>,
+    mut visit: impl FnMut(&TickRecord),
+) -> Result<(), String> {
+    let dir = ticks_dir(app_dir, symbol);
+    let (start_ms, end_ms) = range.unwrap_or((i64::MIN, i64::MAX));
+
+    for (day, summary) in load_index(&dir)? {
+        if summary.last_ms < start_ms || summary.first_ms >= end_ms {
+            continue;
+        }
+
+        read_day(&day_path(&dir, &day))?
+            .iter()
+            .filter(|tick| tick.timestamp_ms >= start_ms && tick.timestamp_ms < end_ms)
+            .for_each(&mut visit);
+    }
+
+    Ok(())
+}
+
+// Write ticks (any order) into the symbol's tick store. Each day is merged with what is
+// already stored, the imported ticks replacing stored ones at the same millisecond, so a
+// partial day keeps the rest of that day and re-importing a range never duplicates ticks.
+pub fn store_ticks(
+    app_dir: &Path,
+    symbol: &str,
+    ticks: &mut [TickRecord],
+) -> Result<Option<TickSummary>, String> {
+    let dir = ticks_dir(app_dir, symbol);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create tick directory: {}", e))?;
+
+    ticks.sort_by_key(|t| t.timestamp_ms);
+
+    let mut index = load_index(&dir)?;
+
+    for day_ticks in ticks.chunk_by(|a, b| day_key(a.timestamp_ms) == day_key(b.timestamp_ms)) {
+        let Some(first) = day_ticks.first() else {
+            continue;
+        };
+
+        let day = day_key(first.timestamp_ms);
+        let path = day_path(&dir, &day);
+
+        let merged = if path.exists() {
+            let imported: HashSet<i64> = day_ticks.iter().map(|t| t.timestamp_ms).collect();
+            let mut merged: Vec<TickRecord> = read_day(&path)?
+                .into_iter()
+                .filter(|t| !imported.contains(&t.timestamp_ms))
+                .collect();
+            merged.extend_from_slice(day_ticks);
+            merged.sort_by_key(|t| t.timestamp_ms);
+            merged
+        } else {
+            day_ticks.to_vec()
+        };
+
+        let (Some(first), Some(last)) = (merged.first(), merged.last()) else {
+            continue;
+        };
+        let summary = DaySummary {
+            count: merged.len(),
+            first_ms: first.timestamp_ms,
+            last_ms: last.timestamp_ms,
+        };
+
+        write_day(&path, &merged)?;
+        index.insert(day, summary);
+    }
+
+    save_index(&dir, &index)?;
+
+    Ok(summarize(&index))
+}
+
+// Builds candles of one timeframe as ticks arrive
+pub struct CandleBuilder {
+    timeframe: String,
+    current: Option<Candle>,
+}
+
+impl CandleBuilder {
+    pub fn new(timeframe: &str) -> Result<Self, String> {
+        bar_start(0, timeframe).ok_or(format!("Unsupported timeframe: {}", timeframe))?;
+
+        Ok(CandleBuilder {
+            timeframe: timeframe.to_string(),
+            current: None,
+        })
+    }
+
+    // Returns the finished candle when the tick opens a new bar
+    pub fn push(&mut self, tick: &TickRecord) -> Option<Candle> {
+        let price = tick.price();
+        let volume = tick.volume_or_count();
+        let start = bar_start(tick.timestamp_ms.div_euclid(1000), &self.timeframe)?;
+
+        match &mut self.current {
+            Some(candle) if candle.timestamp == start => {
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.volume += volume;
+                None
+            }
+            _ => self.current.replace(Candle {
+                timestamp: start,
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume,
+                adj_close: None,
+            }),
+        }
+    }
+
+    pub fn current(&self) -> Option<&Candle> {
+        self.current.as_ref()
+    }
+
+    pub fn finish(self) -> Option<Candle> {
+        self.current
+    }
+}
+
+pub fn build_candles(ticks: &[TickRecord], timeframe: &str) -> Result<Vec<Candle>, String> {
+    let mut builder = CandleBuilder::new(timeframe)?;
+    let mut candles: Vec<Candle> = ticks.iter().filter_map(|t| builder.push(t)).collect();
+    candles.extend(builder.finish());
+    Ok(candles)
+}
+
+// MT5 "Export ticks" layout, tab separated with a header:
+// <DATE> <TIME> <BID> <ASK> <LAST> <VOLUME> <FLAGS>
+// 2024.01.02 00:00:00.123 1.10412 1.10418 ... Fields that didn't change are left empty.
+fn read_mt5_ticks(path: &Path, timezone: &SourceTimezone) -> Result<Vec<TickRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header = lines
+        .next()
+        .ok_or("File is empty")?
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let delimiter = if header.contains('\t') { '\t' } else { ',' };
+    let columns: Vec<String> = header
+        .split(delimiter)
+        .map(|c| c.trim().trim_matches(['<', '>']).to_lowercase())
+        .collect();
+
+    let col = |name: &str| columns.iter().position(|c| c == name);
+    let (Some(date_col), Some(time_col)) = (col("date"), col("time")) else {
+        return Err("Not an MT5 tick export (expected <DATE> and <TIME> columns)".to_string());
+    };
+    let (bid_col, ask_col, last_col, volume_col, flags_col) = (
+        col("bid"),
+        col("ask"),
+        col("last"),
+        col("volume"),
+        col("flags"),
+    );
+
+    if bid_col.is_none() && last_col.is_none() {
+        return Err("Not an MT5 tick export (expected <BID> or <LAST> columns)".to_string());
+    }
+
+    let mut ticks = Vec::new();
+    let mut previous = TickRecord {
+        timestamp_ms: 0,
+        bid: 0.0,
+        ask: 0.0,
+        last: 0.0,
+        volume: 0.0,
+        flags: 0,
+    };
+
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("Failed to read file: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(delimiter).map(str::trim).collect();
+        let field = |col: Option<usize>| col.and_then(|c| fields.get(c)).copied().unwrap_or("");
+        // Empty means unchanged since the previous tick
+        let number = |col: Option<usize>, previous: f64| field(col).parse().unwrap_or(previous);
+
+        let stamp = format!("{} {}", field(Some(date_col)), field(Some(time_col)));
+        let local = NaiveDateTime::parse_from_str(&stamp, "%Y.%m.%d %H:%M:%S%.f")
+            .map_err(|_| format!("Line {}: invalid date/time '{}'", index + 2, stamp))?;
+
+        let seconds = timezone.local_to_utc(local.with_nanosecond(0).unwrap_or(local));
+        let millis = (local.nanosecond() / 1_000_000) as i64;
+
+        let tick = TickRecord {
+            timestamp_ms: seconds * 1000 + millis,
+            bid: number(bid_col, previous.bid),
+            ask: number(ask_col, previous.ask),
+            last: number(last_col, previous.last),
+            volume: field(volume_col).parse().unwrap_or(0.0),
+            flags: field(flags_col).parse().unwrap_or_else(|_| {
+                let mut flags = 0;
+                if !field(bid_col).is_empty() {
+                    flags |= TICK_FLAG_BID;
+                }
+                if !field(ask_col).is_empty() {
+                    flags |= TICK_FLAG_ASK;
+                }
+                if !field(last_col).is_empty() {
+                    flags |= TICK_FLAG_LAST;
+                }
+                if !field(volume_col).is_empty() {
+                    flags |= TICK_FLAG_VOLUME;
+                }
+                flags
+            }),
+        };
+
+        previous = tick;
+
+        // Ticks before the first full quote can't be priced
+        if tick.price() > 0.0 {
+            ticks.push(tick);
+        }
+    }
+
+    Ok(ticks)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TickImportRequest {
+    pub file_path: String,
+    pub save_as: String,
+    #[serde(default)]
+    pub timezone: Option<String>, // Broker server time of the export, UTC when missing
+    #[serde(default)]
+    pub timeframes: Vec<String>, // Candle timeframes to build from the ticks
+}
+
+#[derive(Debug, Serialize)]
+pub struct TickImportResult {
+    pub success: bool,
+    pub message: String,
+    pub symbol: String,
+    pub total_ticks: usize,
+    pub timeframes_built: Vec<String>,
+    pub ticks: Option<TickSummary>, // Whole tick store after the import
+}
+
+// Import an MT5 tick export into a new or existing symbol. Candles are only built for
+// timeframes the symbol doesn't have yet; use a merge import to extend stored ones.
+#[tauri::command]
+pub async fn import_tick_csv(
+    app_handle: AppHandle,
+    request: TickImportRequest,
+) -> Result<TickImportResult, String> {
+    let save_as = request.save_as.trim().to_uppercase();
+
+    if save_as.is_empty() {
+        return Err("Symbol name cannot be empty".to_string());
+    }
+
+    if save_as.contains(|c: char| !c.is_alphanumeric() && c != '_') {
+        return Err("Symbol name can only contain letters, numbers, and underscores".to_string());
+    }
+
+    let timezone = resolve_timezone(request.timezone.as_deref(), SourceTimezone::Utc)?;
+    let mut ticks = read_mt5_ticks(Path::new(&request.file_path), &timezone)?;
+
+    if ticks.is_empty() {
+        return Err("No ticks found in the file".to_string());
+    }
+
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let summary = store_ticks(&app_dir, &save_as, &mut ticks)?;
+    println!("✓ {} ticks stored", ticks.len());
+
+    let mut symbol_data = load_symbol_data(&app_handle, &save_as).unwrap_or(SymbolData {
+        symbol: save_as.clone(),
+        timeframes: Vec::new(),
+        total_candles: 0,
+        uploaded_at: chrono::Utc::now().to_rfc3339(),
+        metadata: None,
+        ticks: None,
+    });
+
+    let data_dir = app_dir.join("data").join(&save_as);
+    let mut timeframes_built = Vec::new();
+
+    for tf in &request.timeframes {
+        if symbol_data.timeframes.iter().any(|info| &info.name == tf) {
+            println!("= {} - already stored, skipped", tf);
+            continue;
+        }
+
+        let candles = build_candles(&ticks, tf)?;
+        let csv_path = data_dir.join(format!("{}.csv", tf));
+        write_candles(&csv_path, &candles)?;
+
+        symbol_data.timeframes.push(TimeframeInfo {
+            name: tf.clone(),
+            display_name: tf.clone(),
+            candle_count: candles.len(),
+            file_path: csv_path.to_string_lossy().to_string(),
+            source_sha: None,
+            format: None,
+            coverage: None,
+            source_timezone: Some(timezone.to_string()),
+        });
+
+        println!("✓ {} - {} candles built", tf, candles.len());
+        timeframes_built.push(tf.clone());
+    }
+
+    symbol_data.total_candles = symbol_data
+        .timeframes
+        .iter()
+        .map(|tf| tf.candle_count)
+        .sum();
+    symbol_data.ticks = summary.clone();
+
+    save_symbol_data(&app_handle, &symbol_data)?;
+
+    Ok(TickImportResult {
+        success: true,
+        message: format!(
+            "Imported {} ticks{}",
+            ticks.len(),
+            if timeframes_built.is_empty() {
+                String::new()
+            } else {
+                format!(" and built {}", timeframes_built.join(", "))
+            }
+        ),
+        symbol: save_as,
+        total_ticks: ticks.len(),
+        timeframes_built,
+        ticks: summary,
+    })
+}
+
+// Replay sessions, one per chart being replayed
+#[derive(Default)]
+pub struct TickReplays {
+    sessions: Mutex<(u32, HashMap<u32, TickReplay>)>, // (next id, sessions)
+}
+
+// Where a replay reads its ticks from, one day (or day of M1 candles) at a time
+enum ReplaySource {
+    Days {
+        dir: PathBuf,
+        days: Vec<String>,
+        next: usize,
+    },
+    Minutes {
+        candles: Vec<Candle>, // Symbols without ticks replay their M1 candle path
+        next: usize,
+    },
+}
+
+struct TickReplay {
+    source: ReplaySource,
+    ticks: Vec<TickRecord>, // Current chunk
+    position: usize,
+    builder: BarBuilder,
+    last: Option<TickRecord>,
+    structure: Option<StructureTracker>, // Fed every closed bar when structure alerts are on
+    patterns: Option<PatternScanner>,    // Same for candlestick pattern alerts
+}
+
+impl TickReplay {
+    fn next_chunk(&mut self) -> Result<bool, String> {
+        self.ticks = match &mut self.source {
+            ReplaySource::Days { dir, days, next } => {
+                let Some(day) = days.get(*next) else {
+                    return Ok(false);
+                };
+                *next += 1;
+                read_day(&day_path(dir, day))?
+            }
+            ReplaySource::Minutes { candles, next } => {
+                if *next >= candles.len() {
+                    return Ok(false);
+                }
+                let end = (*next + 1440).min(candles.len());
+                let chunk = candles[*next..end].iter().flat_map(candle_path).collect();
+                *next = end;
+                chunk
+            }
+        };
+        self.position = 0;
+
+        Ok(true)
+    }
+
+    fn next_tick(&mut self) -> Result<Option<TickRecord>, String> {
+        while self.position >= self.ticks.len() {
+            if !self.next_chunk()? {
+                return Ok(None);
+            }
+        }
+
+        let tick = self.ticks[self.position];
+        self.position += 1;
+        self.last = Some(tick);
+
+        Ok(Some(tick))
+    }
+
+    fn peek_time(&self) -> Option<i64> {
+        self.ticks.get(self.position).map(|t| t.timestamp_ms)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayStep {
+    pub session_id: u32,
+    pub ticks: Vec<TickRecord>,  // Ticks played in this step
+    pub completed: Vec<Candle>,  // Bars closed during this step
+    pub forming: Option<Candle>, // Bar still being built
+    pub bid: Option<f64>,
+    pub ask: Option<f64>,
+    pub spread: Option<f64>, // Ask - bid of the last tick, in price units
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub structure: Vec<StructureEvent>, // Swings and breaks revealed by the closed bars
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<PatternHit>, // Patterns completed by the closed bars
+    pub finished: bool,
+}
+
+// Longest step a single call plays, so fast-forwarding can't block the UI
+const MAX_STEP_TICKS: usize = 50_000;
+
+fn step(
+    session_id: u32,
+    replay: &mut TickReplay,
+    count: usize,
+    until_ms: Option<i64>,
+) -> Result<ReplayStep, String> {
+    let mut ticks = Vec::new();
+    let mut completed = Vec::new();
+    let mut structure = Vec::new();
+    let mut patterns = Vec::new();
+    let mut finished = false;
+
+    while ticks.len() < count.min(MAX_STEP_TICKS) || until_ms.is_some() {
+        if ticks.len() >= MAX_STEP_TICKS {
+            break;
+        }
+
+        if let (Some(until), Some(next)) = (until_ms, replay.peek_time()) {
+            if next > until {
+                break;
+            }
+        }
+
+        let Some(tick) = replay.next_tick()? else {
+            finished = true;
+            break;
+        };
+
+        let closed = replay.builder.push(&tick);
+        if let Some(tracker) = &mut replay.structure {
+            structure.extend(closed.iter().flat_map(|bar| tracker.push(bar)));
+        }
+        if let Some(scanner) = &mut replay.patterns {
+            patterns.extend(closed.iter().flat_map(|bar| scanner.push(bar)));
+        }
+        completed.extend(closed);
+        ticks.push(tick);
+
+        if until_ms.is_some_and(|until| tick.timestamp_ms >= until) {
+            break;
+        }
+    }
+
+    let quote = replay.last.filter(|t| t.bid > 0.0 && t.ask > 0.0);
+
+    Ok(ReplayStep {
+        session_id,
+        ticks,
+        completed,
+        forming: replay.builder.current(),
+        bid: quote.map(|t| t.bid),
+        ask: quote.map(|t| t.ask),
+        spread: quote.map(|t| t.ask - t.bid),
+        structure,
+        patterns,
+        finished,
+    })
+}
+
+// Start replaying a symbol's ticks as `timeframe` candles, or as `bar_type` bars. Symbols
+// without ticks replay their M1 candles. With `from_ms` the bar containing it is rebuilt
+// silently first, so the forming candle is complete from its open. With `structure` every
+// step also reports the swings and structure breaks its closed bars revealed, with
+// `patterns` the candlestick patterns they completed.
+#[tauri::command]
+pub async fn start_tick_replay(
+    app_handle: AppHandle,
+    symbol: String,
+    timeframe: String,
+    from_ms: Option<i64>,
+    bar_type: Option<BarType>,
+    structure: Option<StructureConfig>,
+    patterns: Option<PatternConfig>,
+) -> Result<ReplayStep, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    let bar_open_ms = match from_ms {
+        Some(from) => bar_start(from.div_euclid(1000), &timeframe)
+            .map(|start| start * 1000)
+            .ok_or(format!("Unsupported timeframe: {}", timeframe))?,
+        None => i64::MIN,
+    };
+
+    let symbol_data = load_symbol_data(&app_handle, &symbol)?;
+
+    // ATR boxes only use history before the replay start, or the opening candles when
+    // replaying from the beginning, so the future stays hidden
+    let mut reference = Vec::new();
+    if let Some(BarType::Renko {
+        box_size: None,
+        atr_period,
+    }) = bar_type
+    {
+        reference = load_timeframe_candles(&symbol_data, &timeframe)?;
+        match from_ms {
+            Some(_) => reference.retain(|c| c.timestamp * 1000 < bar_open_ms),
+            None => reference.truncate(atr_period.unwrap_or(DEFAULT_ATR_PERIOD) + 1),
+        }
+    }
+
+    let dir = ticks_dir(&app_dir, &symbol);
+    let index = load_index(&dir)?;
+
+    let source = if !index.is_empty() {
+        ReplaySource::Days {
+            dir,
+            days: index
+                .iter()
+                .filter(|(_, day)| day.last_ms >= bar_open_ms)
+                .map(|(day, _)| day.clone())
+                .collect(),
+            next: 0,
+        }
+    } else if symbol_data.timeframes.iter().any(|tf| tf.name == "M1") {
+        if bar_type.is_some_and(|bar_type| bar_type.needs_ticks()) {
+            return Err("Tick bars need stored tick data".to_string());
+        }
+
+        let mut candles = load_timeframe_candles(&symbol_data, "M1")?;
+        candles.retain(|c| c.timestamp * 1000 >= bar_open_ms);
+        ReplaySource::Minutes { candles, next: 0 }
+    } else {
+        return Err(format!("{} has no ticks or M1 candles to replay", symbol));
+    };
+
+    let mut replay = TickReplay {
+        source,
+        ticks: Vec::new(),
+        position: 0,
+        builder: BarBuilder::new(&timeframe, bar_type.as_ref(), &reference)?,
+        last: None,
+        structure: structure.as_ref().map(StructureTracker::new).transpose()?,
+        patterns: patterns.as_ref().map(PatternScanner::new).transpose()?,
+    };
+
+    // Time candles before the start already have their structure and patterns, so alerts
+    // carry on from them
+    let warm_up = replay.structure.is_some() || replay.patterns.is_some();
+    if warm_up && bar_type.is_none() && from_ms.is_some() {
+        for candle in load_timeframe_candles(&symbol_data, &timeframe)?
+            .iter()
+            .take_while(|c| c.timestamp * 1000 < bar_open_ms)
+        {
+            if let Some(tracker) = &mut replay.structure {
+                tracker.push(candle);
+            }
+            if let Some(scanner) = &mut replay.patterns {
+                scanner.push(candle);
+            }
+        }
+    }
+
+    // Skip to the bar open, then build up to the requested time without reporting it
+    let mut quote = None;
+    while let Some(tick) = replay.next_tick()? {
+        if tick.timestamp_ms >= bar_open_ms {
+            replay.position -= 1;
+            replay.last = quote;
+            break;
+        }
+        quote = Some(tick);
+    }
+    if let Some(from) = from_ms {
+        step(0, &mut replay, 0, Some(from - 1))?;
+    }
+
+    let state = app_handle.state::<TickReplays>();
+    let mut sessions = state
+        .sessions
+        .lock()
+        .map_err(|_| "Replay state is unavailable".to_string())?;
+
+    sessions.0 += 1;
+    let session_id = sessions.0;
+
+    let first = step(session_id, &mut replay, 0, None)?;
+    sessions.1.insert(session_id, replay);
+
+    Ok(first)
+}
+
+// Play `count` ticks (1 by default), or every tick up to `until_ms` for timed playback
+#[tauri::command]
+pub async fn step_tick_replay(
+    app_handle: AppHandle,
+    session_id: u32,
+    count: Option<usize>,
+    until_ms: Option<i64>,
+) -> Result<ReplayStep, String> {
+    let state = app_handle.state::<TickReplays>();
+    let mut sessions = state
+        .sessions
+        .lock()
+        .map_err(|_| "Replay state is unavailable".to_string())?;
+
+    let replay = sessions
+        .1
+        .get_mut(&session_id)
+        .ok_or(format!("Replay session {} not found", session_id))?;
+
+    step(session_id, replay, count.unwrap_or(1), until_ms)
+}
+
+#[tauri::command]
+pub async fn stop_tick_replay(app_handle: AppHandle, session_id: u32) -> Result<(), String> {
+    let state = app_handle.state::<TickReplays>();
+    let mut sessions = state
+        .sessions
+        .lock()
+        .map_err(|_| "Replay state is unavailable".to_string())?;
+
+    sessions.1.remove(&session_id);
+
+    Ok(())
+}
+
+// Tick store contents of a symbol, None when it has no ticks
+#[tauri::command]
+pub async fn get_tick_summary(
+    app_handle: AppHandle,
+    symbol: String,
+) -> Result<Option<TickSummary>, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    Ok(summarize(&load_index(&ticks_dir(&app_dir, &symbol))?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(timestamp_ms: i64, bid: f64) -> TickRecord {
+        TickRecord {
+            timestamp_ms,
+            bid,
+            ask: bid + 0.0001,
+            last: 0.0,
+            volume: 0.0,
+            flags: TICK_FLAG_BID | TICK_FLAG_ASK,
+        }
+    }
+
+    #[test]
+    fn store_ticks_merges_partial_days() {
+        let app_dir = std::env::temp_dir().join(format!("ticks-test-{}", std::process::id()));
+        let day = 1_704_153_600_000; // 2024-01-02 00:00 UTC
+        let hour = 3_600_000;
+
+        // Morning first, then an afternoon export that overlaps it at 11:00
+        let mut morning = vec![tick(day + 9 * hour, 1.1), tick(day + 11 * hour, 1.2)];
+        store_ticks(&app_dir, "TEST", &mut morning).unwrap();
+        let mut afternoon = vec![tick(day + 11 * hour, 1.3), tick(day + 12 * hour, 1.4)];
+        let summary = store_ticks(&app_dir, "TEST", &mut afternoon)
+            .unwrap()
+            .unwrap();
+
+        let mut stored = Vec::new();
+        for_each_tick(&app_dir, "TEST", None, |t| {
+            stored.push((t.timestamp_ms, t.bid))
+        })
+        .unwrap();
+        fs::remove_dir_all(&app_dir).ok();
+
+        assert_eq!(
+            stored,
+            vec![
+                (day + 9 * hour, 1.1),
+                (day + 11 * hour, 1.3),
+                (day + 12 * hour, 1.4)
+            ]
+        );
+        assert_eq!(summary.count, 3);
+        assert_eq!(summary.first_ms, day + 9 * hour);
+        assert_eq!(summary.last_ms, day + 12 * hour);
+    }
+}
@@ -0,0 +1,347 @@
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use std::fmt;
+
+use super::candles::Candle;
+
+// Daylight saving schedules for broker servers that shift a fixed offset by one hour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DstRule {
+    Us, // Second Sunday of March to first Sunday of November, 02:00 New York time
+    Eu, // Last Sunday of March to last Sunday of October, 01:00 UTC
+}
+
+// Timezone the timestamps of a source file are written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SourceTimezone {
+    Utc,
+    Named(Tz), // IANA zone, DST handled by the tz database
+    Fixed {
+        offset: FixedOffset, // Standard (winter) offset
+        dst: Option<DstRule>,
+    },
+}
+
+fn nth_sunday(year: i32, month: u32, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, n).unwrap_or(NaiveDate::MIN)
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    }
+    .unwrap_or(NaiveDate::MAX);
+
+    let last_day = next_month.pred_opt().unwrap_or(next_month);
+    last_day - Duration::days(last_day.weekday().num_days_from_sunday() as i64)
+}
+
+impl DstRule {
+    // Whether daylight saving is in effect at a UTC instant
+    fn in_effect(&self, utc: DateTime<Utc>) -> bool {
+        let year = utc.year();
+        let (start, end) = match self {
+            DstRule::Us => (
+                nth_sunday(year, 3, 2).and_hms_opt(7, 0, 0),
+                nth_sunday(year, 11, 1).and_hms_opt(6, 0, 0),
+            ),
+            DstRule::Eu => (
+                last_sunday(year, 3).and_hms_opt(1, 0, 0),
+                last_sunday(year, 10).and_hms_opt(1, 0, 0),
+            ),
+        };
+
+        match (start, end) {
+            (Some(start), Some(end)) => {
+                let now = utc.naive_utc();
+                now >= start && now < end
+            }
+            _ => false,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            DstRule::Us => "US",
+            DstRule::Eu => "EU",
+        }
+    }
+}
+
+impl SourceTimezone {
+    // "UTC", an IANA name ("Europe/Athens", "EST"), or a fixed offset with an optional
+    // DST rule: "+02:00" or "+02:00/US" (GMT+2, GMT+3 while the US is on summer time)
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let value = value.trim();
+
+        if value.is_empty() || ["UTC", "GMT", "Z"].contains(&value.to_uppercase().as_str()) {
+            return Ok(SourceTimezone::Utc);
+        }
+
+        if value.starts_with('+') || value.starts_with('-') {
+            let (offset, rule) = match value.split_once('/') {
+                Some((offset, rule)) => (offset, Some(rule)),
+                None => (value, None),
+            };
+
+            let dst = match rule.map(|r| r.trim().to_uppercase()) {
+                None => None,
+                Some(r) if r == "US" => Some(DstRule::Us),
+                Some(r) if r == "EU" => Some(DstRule::Eu),
+                Some(r) => return Err(format!("Unknown DST rule '{}' (use US or EU)", r)),
+            };
+
+            let offset = parse_offset(offset.trim())
+                .ok_or(format!("Invalid UTC offset '{}' (use e.g. +02:00)", offset))?;
+
+            if offset.local_minus_utc() == 0 && dst.is_none() {
+                return Ok(SourceTimezone::Utc);
+            }
+
+            return Ok(SourceTimezone::Fixed { offset, dst });
+        }
+
+        value.parse::<Tz>().map(SourceTimezone::Named).map_err(|_| {
+            format!(
+                "Unknown timezone '{}' (use UTC, an IANA name like Europe/Athens or an offset like +02:00/US)",
+                value
+            )
+        })
+    }
+
+    pub fn is_utc(&self) -> bool {
+        *self == SourceTimezone::Utc
+    }
+
+    // Seconds east of UTC at a UTC instant
+    pub fn offset_at(&self, timestamp: i64) -> i64 {
+        let Some(utc) = DateTime::from_timestamp(timestamp, 0) else {
+            return 0;
+        };
+
+        match self {
+            SourceTimezone::Utc => 0,
+            SourceTimezone::Named(tz) => tz
+                .offset_from_utc_datetime(&utc.naive_utc())
+                .fix()
+                .local_minus_utc() as i64,
+            SourceTimezone::Fixed { offset, dst } => {
+                let summer = dst.is_some_and(|rule| rule.in_effect(utc));
+                offset.local_minus_utc() as i64 + if summer { 3600 } else { 0 }
+            }
+        }
+    }
+
+    // Wall-clock time in this zone to a UTC unix timestamp. Ambiguous times (clocks going
+    // back) take the earlier instant, skipped times (clocks going forward) the later one.
+    pub fn local_to_utc(&self, local: NaiveDateTime) -> i64 {
+        let naive_ts = local.and_utc().timestamp();
+
+        match self {
+            SourceTimezone::Utc => naive_ts,
+            SourceTimezone::Named(tz) => tz
+                .from_local_datetime(&local)
+                .earliest()
+                .or_else(|| {
+                    tz.from_local_datetime(&(local + Duration::hours(1)))
+                        .earliest()
+                })
+                .map(|dt| dt.timestamp())
+                .unwrap_or(naive_ts),
+            SourceTimezone::Fixed { offset, .. } => {
+                let standard = naive_ts - offset.local_minus_utc() as i64;
+                // Try the summer offset first, keep it only if DST applies at that instant
+                let summer = standard - 3600;
+                if self.offset_at(summer) != offset.local_minus_utc() as i64 {
+                    summer
+                } else {
+                    standard
+                }
+            }
+        }
+    }
+
+    // The format registry parses a file's wall-clock time as if it were UTC; move such a
+    // timestamp to the real UTC instant
+    pub fn wall_clock_to_utc(&self, timestamp: i64) -> i64 {
+        match DateTime::from_timestamp(timestamp, 0) {
+            Some(local) if !self.is_utc() => self.local_to_utc(local.naive_utc()),
+            _ => timestamp,
+        }
+    }
+
+    pub fn shift_to_utc(&self, candles: &mut Vec<Candle>) {
+        if self.is_utc() {
+            return;
+        }
+
+        for candle in candles.iter_mut() {
+            candle.timestamp = self.wall_clock_to_utc(candle.timestamp);
+        }
+
+        // Clocks going back repeat an hour of wall-clock time, keep the first bar of each instant
+        candles.sort_by_key(|c| c.timestamp);
+        candles.dedup_by_key(|c| c.timestamp);
+    }
+
+    // UTC candles to wall-clock time in this zone, for display
+    pub fn shift_from_utc(&self, candles: &mut [Candle]) {
+        if self.is_utc() {
+            return;
+        }
+
+        for candle in candles.iter_mut() {
+            candle.timestamp += self.offset_at(candle.timestamp);
+        }
+    }
+}
+
+// "+02:00", "+0200" or "+2"
+fn parse_offset(value: &str) -> Option<FixedOffset> {
+    if let Ok(offset) = value.parse::<FixedOffset>() {
+        return Some(offset);
+    }
+
+    let hours: i32 = value.parse().ok()?;
+    FixedOffset::east_opt(hours * 3600)
+}
+
+impl fmt::Display for SourceTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SourceTimezone::Utc => write!(f, "UTC"),
+            SourceTimezone::Named(tz) => write!(f, "{}", tz.name()),
+            SourceTimezone::Fixed { offset, dst } => match dst {
+                Some(rule) => write!(f, "{}/{}", offset, rule.name()),
+                None => write!(f, "{}", offset),
+            },
+        }
+    }
+}
+
+// Parse an optional zone from a request, falling back to `default`
+pub fn resolve_timezone(
+    value: Option<&str>,
+    default: SourceTimezone,
+) -> Result<SourceTimezone, String> {
+    match value {
+        Some(value) if !value.trim().is_empty() => SourceTimezone::parse(value),
+        _ => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(hour, minute, 0))
+            .unwrap()
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+        at(year, month, day, hour, minute).and_utc().timestamp()
+    }
+
+    #[test]
+    fn parses_offsets_with_dst_rules() {
+        let zone = SourceTimezone::parse("+02:00/US").unwrap();
+        assert_eq!(
+            zone,
+            SourceTimezone::Fixed {
+                offset: FixedOffset::east_opt(7200).unwrap(),
+                dst: Some(DstRule::Us),
+            }
+        );
+        assert_eq!(zone.to_string(), "+02:00/US");
+
+        assert_eq!(
+            SourceTimezone::parse("+2/eu").unwrap().to_string(),
+            "+02:00/EU"
+        );
+        assert_eq!(
+            SourceTimezone::parse("+00:00").unwrap(),
+            SourceTimezone::Utc
+        );
+        assert_eq!(
+            SourceTimezone::parse("Europe/Athens").unwrap(),
+            SourceTimezone::Named(Tz::Europe__Athens)
+        );
+        assert!(SourceTimezone::parse("+02:00/XX").is_err());
+        assert!(SourceTimezone::parse("+25:00").is_err());
+    }
+
+    #[test]
+    fn offsets_follow_the_us_and_eu_switch_dates() {
+        let us = SourceTimezone::parse("+02:00/US").unwrap();
+        let eu = SourceTimezone::parse("+02:00/EU").unwrap();
+        let new_york = SourceTimezone::parse("America/New_York").unwrap();
+
+        // 2024: US summer time from March 10 07:00 UTC to November 3 06:00 UTC
+        for (timestamp, expected) in [
+            (utc(2024, 3, 10, 6, 59), 7200),
+            (utc(2024, 3, 10, 7, 0), 10800),
+            (utc(2024, 11, 3, 5, 59), 10800),
+            (utc(2024, 11, 3, 6, 0), 7200),
+        ] {
+            assert_eq!(us.offset_at(timestamp), expected);
+            assert_eq!(new_york.offset_at(timestamp), expected - 7 * 3600);
+        }
+
+        // EU summer time from March 31 01:00 UTC to October 27 01:00 UTC
+        for (timestamp, expected) in [
+            (utc(2024, 3, 31, 0, 59), 7200),
+            (utc(2024, 3, 31, 1, 0), 10800),
+            (utc(2024, 10, 27, 0, 59), 10800),
+            (utc(2024, 10, 27, 1, 0), 7200),
+        ] {
+            assert_eq!(eu.offset_at(timestamp), expected);
+        }
+
+        // Between the two switches the rules disagree
+        let gap = utc(2024, 3, 20, 12, 0);
+        assert_eq!((us.offset_at(gap), eu.offset_at(gap)), (10800, 7200));
+    }
+
+    #[test]
+    fn skipped_times_move_forward_and_repeated_times_take_the_first() {
+        let us = SourceTimezone::parse("+02:00/US").unwrap();
+        let new_york = SourceTimezone::parse("America/New_York").unwrap();
+
+        // Clocks jump from 02:00 to 03:00 in New York, 09:00 to 10:00 at GMT+2/US
+        assert_eq!(
+            new_york.local_to_utc(at(2024, 3, 10, 2, 30)),
+            utc(2024, 3, 10, 7, 30)
+        );
+        assert_eq!(
+            us.local_to_utc(at(2024, 3, 10, 9, 30)),
+            utc(2024, 3, 10, 7, 30)
+        );
+
+        // 01:30 happens twice in New York and 08:30 twice at GMT+2/US, the summer one first
+        assert_eq!(
+            new_york.local_to_utc(at(2024, 11, 3, 1, 30)),
+            utc(2024, 11, 3, 5, 30)
+        );
+        assert_eq!(
+            us.local_to_utc(at(2024, 11, 3, 8, 30)),
+            utc(2024, 11, 3, 5, 30)
+        );
+
+        // Ordinary times either side of the switches
+        assert_eq!(
+            us.local_to_utc(at(2024, 1, 15, 12, 0)),
+            utc(2024, 1, 15, 10, 0)
+        );
+        assert_eq!(
+            us.local_to_utc(at(2024, 7, 15, 12, 0)),
+            utc(2024, 7, 15, 9, 0)
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::candles::Candle;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    Unparsable,
+    NonPositivePrice,
+    HighBelowLow,
+    OpenOutOfRange,
+    CloseOutOfRange,
+    DuplicateTimestamp,
+    NonMonotonic,
+    Spike, // Heuristic, reported as a warning and never rejects or drops a row
+}
+
+impl IssueKind {
+    fn is_error(&self) -> bool {
+        !matches!(self, IssueKind::Spike)
+    }
+}
+
+// What to do with rows that fail validation
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidRowPolicy {
+    #[default]
+    Reject, // Refuse the file if any row has an error
+    Skip,   // Drop the bad rows
+    Repair, // Fix what can be fixed (swap/widen high-low, sort), drop the rest
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationIssue {
+    pub line: usize,
+    pub kind: IssueKind,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationReport {
+    pub timeframe: String,
+    pub total_rows: usize,
+    pub valid_rows: usize, // Rows kept after applying the policy
+    pub skipped_rows: usize,
+    pub repaired_rows: usize,
+    pub error_count: usize,
+    pub warning_count: usize,
+    pub issue_counts: HashMap<IssueKind, usize>,
+    pub issues: Vec<ValidationIssue>, // First MAX_REPORTED_ISSUES only
+    pub truncated: bool,
+}
+
+impl ValidationReport {
+    pub fn has_errors(&self) -> bool {
+        self.error_count > 0
+    }
+
+    // One-line summary for error messages, e.g. "3 errors (line 12: high 1.05 < low 1.07)"
+    pub fn summary(&self) -> String {
+        match self.issues.iter().find(|i| i.kind.is_error()) {
+            Some(first) => format!(
+                "{} error{} (line {}: {})",
+                self.error_count,
+                if self.error_count == 1 { "" } else { "s" },
+                first.line,
+                first.message
+            ),
+            None => "no errors".to_string(),
+        }
+    }
+
+    fn record(&mut self, line: usize, kind: IssueKind, message: String) {
+        *self.issue_counts.entry(kind).or_insert(0) += 1;
+
+        if kind.is_error() {
+            self.error_count += 1;
+        } else {
+            self.warning_count += 1;
+        }
+
+        if self.issues.len() < MAX_REPORTED_ISSUES {
+            self.issues.push(ValidationIssue {
+                line,
+                kind,
+                message,
+            });
+        } else {
+            self.truncated = true;
+        }
+    }
+}
+
+// A data row as read from the file, with its 1-based line number
+pub struct ParsedRow {
+    pub line: usize,
+    pub candle: Result<Candle, String>,
+}
+
+// Keep the report small enough to send to the frontend for multi-million row files
+const MAX_REPORTED_ISSUES: usize = 500;
+
+// A wick or close is a spike when it moves this many typical bar ranges at once...
+const SPIKE_RANGE_MULTIPLE: f64 = 15.0;
+// ...and at least this far in relative terms, so flat markets don't flag every tick
+const MIN_SPIKE_MOVE: f64 = 0.002;
+
+// Median of (high - low) / close, the "normal" size of a bar in this file
+fn typical_range(candles: &[&Candle]) -> f64 {
+    let mut ranges: Vec<f64> = candles
+        .iter()
+        .filter(|c| c.close > 0.0 && c.high >= c.low)
+        .map(|c| (c.high - c.low) / c.close)
+        .collect();
+
+    if ranges.is_empty() {
+        return 0.0;
+    }
+
+    ranges.sort_by(|a, b| a.total_cmp(b));
+    ranges[ranges.len() / 2]
+}
+
+// Whether most consecutive rows go back in time, i.e. the export lists the newest bar first
+fn is_newest_first(rows: &[ParsedRow]) -> bool {
+    let timestamps: Vec<i64> = rows
+        .iter()
+        .filter_map(|r| r.candle.as_ref().ok())
+        .map(|c| c.timestamp)
+        .collect();
+
+    let descending = timestamps.windows(2).filter(|w| w[1] < w[0]).count();
+    descending * 2 > timestamps.len().saturating_sub(1)
+}
+
+// Check every row and build the candles to store under `policy`.
+// With `Reject` the returned candles are only meaningful when the report has no errors.
+// Spikes are only reported, unless `trim_spikes` asks for spiking wicks to be cut back to the body.
+pub fn validate_rows(
+    timeframe: &str,
+    rows: Vec<ParsedRow>,
+    policy: InvalidRowPolicy,
+    trim_spikes: bool,
+) -> (ValidationReport, Vec<Candle>) {
+    // Read newest-first files oldest-first, otherwise every row would be out of order
+    let rows: Vec<ParsedRow> = if is_newest_first(&rows) {
+        rows.into_iter().rev().collect()
+    } else {
+        rows
+    };
+
+    let mut report = ValidationReport {
+        timeframe: timeframe.to_string(),
+        total_rows: rows.len(),
+        valid_rows: 0,
+        skipped_rows: 0,
+        repaired_rows: 0,
+        error_count: 0,
+        warning_count: 0,
+        issue_counts: HashMap::new(),
+        issues: Vec::new(),
+        truncated: false,
+    };
+
+    let parsed: Vec<&Candle> = rows.iter().filter_map(|r| r.candle.as_ref().ok()).collect();
+    let spike_threshold = (typical_range(&parsed) * SPIKE_RANGE_MULTIPLE).max(MIN_SPIKE_MOVE);
+
+    // (line, candle, already repaired)
+    let mut kept: Vec<(usize, Candle, bool)> = Vec::with_capacity(rows.len());
+    let mut seen = HashSet::with_capacity(rows.len());
+    let mut last_timestamp: Option<i64> = None;
+    let mut needs_sort = false;
+
+    for row in rows {
+        let line = row.line;
+
+        let mut candle = match row.candle {
+            Ok(candle) => candle,
+            Err(e) => {
+                report.record(line, IssueKind::Unparsable, e);
+                report.skipped_rows += 1;
+                continue;
+            }
+        };
+
+        let mut drop_row = false;
+        let mut repaired = false;
+
+        if [candle.open, candle.high, candle.low, candle.close]
+            .iter()
+            .any(|p| *p <= 0.0 || !p.is_finite())
+        {
+            report.record(
+                line,
+                IssueKind::NonPositivePrice,
+                format!(
+                    "non-positive price (O {} H {} L {} C {})",
+                    candle.open, candle.high, candle.low, candle.close
+                ),
+            );
+            drop_row = true;
+        }
+
+        if candle.high < candle.low {
+            report.record(
+                line,
+                IssueKind::HighBelowLow,
+                format!("high {} < low {}", candle.high, candle.low),
+            );
+            match policy {
+                InvalidRowPolicy::Repair => {
+                    std::mem::swap(&mut candle.high, &mut candle.low);
+                    repaired = true;
+                }
+                _ => drop_row = true,
+            }
+        }
+
+        for (kind, name, value) in [
+            (IssueKind::OpenOutOfRange, "open", candle.open),
+            (IssueKind::CloseOutOfRange, "close", candle.close),
+        ] {
+            // Only meaningful once high and low are the right way round
+            if candle.high >= candle.low && (value > candle.high || value < candle.low) {
+                report.record(
+                    line,
+                    kind,
+                    format!(
+                        "{} {} outside high-low range {}-{}",
+                        name, value, candle.low, candle.high
+                    ),
+                );
+                match policy {
+                    InvalidRowPolicy::Repair => {
+                        candle.high = candle.high.max(value);
+                        candle.low = candle.low.min(value);
+                        repaired = true;
+                    }
+                    _ => drop_row = true,
+                }
+            }
+        }
+
+        if drop_row {
+            report.skipped_rows += 1;
+            continue;
+        }
+
+        if seen.contains(&candle.timestamp) {
+            report.record(
+                line,
+                IssueKind::DuplicateTimestamp,
+                format!("duplicate timestamp {}", candle.timestamp),
+            );
+            // Keep the first occurrence, whatever the policy
+            report.skipped_rows += 1;
+            continue;
+        }
+
+        if matches!(last_timestamp, Some(last) if candle.timestamp < last) {
+            report.record(
+                line,
+                IssueKind::NonMonotonic,
+                format!(
+                    "timestamp {} is earlier than the previous row",
+                    candle.timestamp
+                ),
+            );
+
+            if policy != InvalidRowPolicy::Repair {
+                report.skipped_rows += 1;
+                continue;
+            }
+            needs_sort = true;
+        }
+
+        seen.insert(candle.timestamp);
+        last_timestamp = Some(last_timestamp.map_or(candle.timestamp, |t| t.max(candle.timestamp)));
+        kept.push((line, candle, repaired));
+    }
+
+    if needs_sort {
+        kept.sort_by_key(|(_, c, _)| c.timestamp);
+    }
+
+    // Spikes need both neighbours, so they are checked once the rows are in order
+    let mut candles = Vec::with_capacity(kept.len());
+
+    for i in 0..kept.len() {
+        let (line, mut candle, mut repaired) = kept[i].clone();
+        let body_high = candle.open.max(candle.close);
+        let body_low = candle.open.min(candle.close);
+
+        // A close far from both neighbours, in the same direction
+        let close_spike = match (i.checked_sub(1).map(|p| &kept[p].1), kept.get(i + 1)) {
+            (Some(prev), Some((_, next, _))) => {
+                let from_prev = (candle.close - prev.close) / prev.close;
+                let to_next = (candle.close - next.close) / next.close;
+                from_prev.abs() > spike_threshold
+                    && to_next.abs() > spike_threshold
+                    && from_prev.signum() == to_next.signum()
+            }
+            _ => false,
+        };
+
+        let upper_spike = (candle.high - body_high) / candle.close > spike_threshold;
+        let lower_spike = (body_low - candle.low) / candle.close > spike_threshold;
+
+        if close_spike || upper_spike || lower_spike {
+            report.record(
+                line,
+                IssueKind::Spike,
+                format!(
+                    "possible spike (O {} H {} L {} C {})",
+                    candle.open, candle.high, candle.low, candle.close
+                ),
+            );
+
+            // Only the wick that spiked is trimmed, a spiking close is left for the user to judge
+            if trim_spikes && upper_spike {
+                candle.high = body_high;
+                repaired = true;
+            }
+            if trim_spikes && lower_spike {
+                candle.low = body_low;
+                repaired = true;
+            }
+        }
+
+        if repaired {
+            report.repaired_rows += 1;
+        }
+
+        candles.push(candle);
+    }
+
+    report.valid_rows = candles.len();
+    report.issues.sort_by_key(|issue| issue.line);
+
+    (report, candles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(line: usize, timestamp: i64, open: f64, high: f64, low: f64, close: f64) -> ParsedRow {
+        ParsedRow {
+            line,
+            candle: Ok(Candle {
+                timestamp,
+                open,
+                high,
+                low,
+                close,
+                volume: 0.0,
+                adj_close: None,
+            }),
+        }
+    }
+
+    // Quiet bars around one whose high spikes far above its body
+    fn rows_with_upper_spike() -> Vec<ParsedRow> {
+        (0..9)
+            .map(|i| {
+                let high = if i == 4 { 1.5 } else { 1.001 };
+                row(i + 2, 60 * i as i64, 1.0, high, 0.999, 1.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn newest_first_files_are_reversed() {
+        let rows = (0..5)
+            .map(|i| row(i + 2, 60 * (4 - i as i64), 1.0, 1.001, 0.999, 1.0))
+            .collect();
+        let (report, candles) = validate_rows("M1", rows, InvalidRowPolicy::Skip, false);
+
+        assert!(!report.has_errors());
+        assert_eq!(report.skipped_rows, 0);
+        let timestamps: Vec<i64> = candles.iter().map(|c| c.timestamp).collect();
+        assert_eq!(timestamps, vec![0, 60, 120, 180, 240]);
+    }
+
+    #[test]
+    fn a_single_late_row_is_still_out_of_order() {
+        let rows = [0, 60, 180, 120, 240]
+            .into_iter()
+            .enumerate()
+            .map(|(i, t)| row(i + 2, t, 1.0, 1.001, 0.999, 1.0))
+            .collect();
+        let (report, candles) = validate_rows("M1", rows, InvalidRowPolicy::Skip, false);
+
+        assert_eq!(report.issue_counts.get(&IssueKind::NonMonotonic), Some(&1));
+        assert_eq!(candles.len(), 4);
+    }
+
+    #[test]
+    fn spikes_are_only_reported_by_default() {
+        for policy in [
+            InvalidRowPolicy::Reject,
+            InvalidRowPolicy::Skip,
+            InvalidRowPolicy::Repair,
+        ] {
+            let (report, candles) = validate_rows("M1", rows_with_upper_spike(), policy, false);
+
+            assert_eq!(report.issue_counts.get(&IssueKind::Spike), Some(&1));
+            assert!(!report.has_errors());
+            assert_eq!(candles.len(), 9);
+            assert_eq!(candles[4].high, 1.5);
+            assert_eq!(report.repaired_rows, 0);
+        }
+    }
+
+    #[test]
+    fn trimming_only_cuts_the_wick_that_spiked() {
+        let (report, candles) = validate_rows(
+            "M1",
+            rows_with_upper_spike(),
+            InvalidRowPolicy::Reject,
+            true,
+        );
+
+        assert_eq!(report.repaired_rows, 1);
+        assert_eq!(candles[4].high, 1.0);
+        assert_eq!(candles[4].low, 0.999);
+    }
+}
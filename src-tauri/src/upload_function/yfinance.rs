@@ -1,11 +1,15 @@
 use reqwest;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::PathBuf;
 use tauri::{AppHandle, Manager};
 
-use super::local_upload::{SymbolData, TimeframeInfo};
+use super::candles::Candle;
+use super::corporate_actions::{save_corporate_actions, CorporateActions, Dividend, Split};
+use super::local_upload::{SessionHours, SymbolData, SymbolMetadata, TimeframeInfo};
+use super::merge::{finish_import, import_target, MergePolicy, MergeReport};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct YFinanceRequest {
@@ -14,6 +18,12 @@ pub struct YFinanceRequest {
     pub start_date: String,
     pub end_date: String,
     pub timeframes: Vec<String>,
+    #[serde(default)]
+    pub include_events: bool, // Also request dividends and splits
+    #[serde(default)]
+    pub base_url: Option<String>, // Override for a proxy or a local stub
+    #[serde(default)]
+    pub merge: Option<MergePolicy>, // Splice into an existing symbol instead of refusing it
 }
 
 #[derive(Debug, Serialize)]
@@ -23,6 +33,51 @@ pub struct YFinanceResult {
     pub symbol: String,
     pub timeframes_downloaded: Vec<String>,
     pub total_candles: usize,
+    pub warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merge: Option<MergeReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct YFinanceSearchResult {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub exchange: Option<String>,
+    pub instrument_type: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct YFinanceSymbolCheck {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub exchange: Option<String>,
+    pub instrument_type: Option<String>,
+    pub currency: Option<String>,
+    pub first_trade_date: Option<String>, // YYYY-MM-DD
+    pub first_trade_timestamp: Option<i64>,
+}
+
+const YAHOO_BASE_URL: &str = "https://query1.finance.yahoo.com";
+
+fn yahoo_base_url(base_url: Option<&str>) -> &str {
+    base_url
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or(YAHOO_BASE_URL)
+        .trim_end_matches('/')
+}
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+#[derive(Debug)]
+struct RequestPlan {
+    windows: Vec<(i64, i64)>,
+    warning: Option<String>, // Set when the start had to be clamped
+}
+
+struct YahooChart {
+    candles: Vec<Candle>,
+    actions: CorporateActions,
+    metadata: SymbolMetadata,
 }
 
 // Map yfinance intervals to your timeframe format
@@ -42,6 +97,124 @@ fn map_timeframe_to_yfinance(tf: &str) -> Option<String> {
     }
 }
 
+// Yahoo's intraday limits as (max days per request, max days of history back from today)
+fn interval_limits(interval: &str) -> Option<(i64, i64)> {
+    match interval {
+        "1m" => Some((7, 30)),
+        "2m" | "5m" | "15m" | "30m" => Some((60, 60)),
+        "1h" | "4h" => Some((730, 730)),
+        _ => None,
+    }
+}
+
+// Split [start, end) into windows Yahoo will accept for this interval. The start is
+// clamped to the oldest bar Yahoo still serves; a warning explains any clamping.
+fn plan_request_windows(
+    interval: &str,
+    start: i64,
+    end: i64,
+    now: i64,
+) -> Result<RequestPlan, String> {
+    if end <= start {
+        return Err("End date must be after start date".to_string());
+    }
+
+    let Some((max_span_days, lookback_days)) = interval_limits(interval) else {
+        return Ok(RequestPlan {
+            windows: vec![(start, end)],
+            warning: None,
+        });
+    };
+
+    // Stay an hour inside the lookback window, Yahoo rejects requests right on the edge
+    let oldest_available = now - lookback_days * SECONDS_PER_DAY + 3600;
+    let end = end.min(now);
+
+    if end <= oldest_available {
+        return Err(format!(
+            "No {} history available for the requested range (Yahoo only keeps the last {} days)",
+            interval, lookback_days
+        ));
+    }
+
+    let warning = if start < oldest_available {
+        Some(format!(
+            "{} history starts at {}: Yahoo only keeps the last {} days",
+            interval,
+            chrono::DateTime::from_timestamp(oldest_available, 0)
+                .map(|dt| dt.format("%Y-%m-%d").to_string())
+                .unwrap_or_default(),
+            lookback_days
+        ))
+    } else {
+        None
+    };
+
+    let span = max_span_days * SECONDS_PER_DAY;
+    let mut windows = Vec::new();
+    let mut window_start = start.max(oldest_available);
+
+    while window_start < end {
+        let window_end = (window_start + span).min(end);
+        windows.push((window_start, window_end));
+        window_start = window_end;
+    }
+
+    Ok(RequestPlan { windows, warning })
+}
+
+// Fetch every window for one interval and merge the results, dropping duplicate bars
+async fn fetch_yfinance_windows(
+    base_url: &str,
+    symbol: &str,
+    interval: &str,
+    windows: &[(i64, i64)],
+    include_events: bool,
+) -> Result<YahooChart, String> {
+    let mut merged = BTreeMap::new();
+    let mut actions = CorporateActions::default();
+    let mut metadata = SymbolMetadata::default();
+    let mut last_error = None;
+
+    for (window_start, window_end) in windows {
+        match fetch_yfinance_data(
+            base_url,
+            symbol,
+            interval,
+            &window_start.to_string(),
+            &window_end.to_string(),
+            include_events,
+        )
+        .await
+        {
+            Ok(chart) => {
+                for candle in chart.candles {
+                    merged.insert(candle.timestamp, candle);
+                }
+                actions.merge(chart.actions);
+                metadata = chart.metadata;
+            }
+            Err(e) => {
+                println!(
+                    "✗ {} window {}-{} - {}",
+                    interval, window_start, window_end, e
+                );
+                last_error = Some(e);
+            }
+        }
+    }
+
+    if merged.is_empty() {
+        return Err(last_error.unwrap_or_else(|| "No valid candle data found".to_string()));
+    }
+
+    Ok(YahooChart {
+        candles: merged.into_values().collect(),
+        actions,
+        metadata,
+    })
+}
+
 fn convert_timeframe_to_display(timeframe: &str) -> String {
     match timeframe {
         "M1" => "1 min".to_string(),
@@ -58,17 +231,128 @@ fn convert_timeframe_to_display(timeframe: &str) -> String {
     }
 }
 
+async fn search_yfinance(base_url: &str, query: &str) -> Result<Vec<YFinanceSearchResult>, String> {
+    let url = format!("{}/v1/finance/search", base_url);
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(&url)
+        .query(&[("q", query), ("quotesCount", "10"), ("newsCount", "0")])
+        .header("User-Agent", "Mozilla/5.0")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to search symbols: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("API returned error: {}", response.status()));
+    }
+
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    let text = |quote: &serde_json::Value, key: &str| quote[key].as_str().map(|s| s.to_string());
+
+    let results = json["quotes"]
+        .as_array()
+        .map(|quotes| {
+            quotes
+                .iter()
+                .filter_map(|quote| {
+                    Some(YFinanceSearchResult {
+                        symbol: text(quote, "symbol")?,
+                        name: text(quote, "longname").or_else(|| text(quote, "shortname")),
+                        exchange: text(quote, "exchDisp").or_else(|| text(quote, "exchange")),
+                        instrument_type: text(quote, "quoteType"),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(results)
+}
+
+// Ask for a single daily bar to confirm the ticker exists and find where its history starts
+async fn check_yfinance_history(
+    base_url: &str,
+    symbol: &str,
+) -> Result<YFinanceSymbolCheck, String> {
+    let url = format!(
+        "{}/v8/finance/chart/{}?range=1d&interval=1d",
+        base_url, symbol
+    );
+
+    let client = reqwest::Client::new();
+    let response = client
+        .get(&url)
+        .header("User-Agent", "Mozilla/5.0")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch data: {}", e))?;
+
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err(format!(
+            "Symbol '{}' was not found on Yahoo Finance",
+            symbol
+        ));
+    }
+
+    if !response.status().is_success() {
+        return Err(format!("API returned error: {}", response.status()));
+    }
+
+    let json: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+
+    if let Some(description) = json["chart"]["error"]["description"].as_str() {
+        return Err(format!("Symbol '{}': {}", symbol, description));
+    }
+
+    let meta = &json["chart"]["result"][0]["meta"];
+    if !meta.is_object() {
+        return Err(format!(
+            "Symbol '{}' was not found on Yahoo Finance",
+            symbol
+        ));
+    }
+
+    let text = |key: &str| meta[key].as_str().map(|s| s.to_string());
+    let first_trade_timestamp = meta["firstTradeDate"].as_i64();
+
+    Ok(YFinanceSymbolCheck {
+        symbol: text("symbol").unwrap_or_else(|| symbol.to_string()),
+        name: text("longName").or_else(|| text("shortName")),
+        exchange: text("fullExchangeName").or_else(|| text("exchangeName")),
+        instrument_type: text("instrumentType"),
+        currency: text("currency"),
+        first_trade_date: first_trade_timestamp
+            .and_then(|ts| chrono::DateTime::from_timestamp(ts, 0))
+            .map(|dt| dt.format("%Y-%m-%d").to_string()),
+        first_trade_timestamp,
+    })
+}
+
 async fn fetch_yfinance_data(
+    base_url: &str,
     symbol: &str,
     interval: &str,
     start_date: &str,
     end_date: &str,
-) -> Result<Vec<(i64, f64, f64, f64, f64, i64)>, String> {
-    let url = format!(
-        "https://query1.finance.yahoo.com/v8/finance/chart/{}?interval={}&period1={}&period2={}",
-        symbol, interval, start_date, end_date
+    include_events: bool,
+) -> Result<YahooChart, String> {
+    let mut url = format!(
+        "{}/v8/finance/chart/{}?interval={}&period1={}&period2={}",
+        base_url, symbol, interval, start_date, end_date
     );
 
+    if include_events {
+        url.push_str("&events=div,splits");
+    }
+
     let client = reqwest::Client::new();
     let response = client
         .get(&url)
@@ -90,9 +374,11 @@ async fn fetch_yfinance_data(
         serde_json::from_str(&text).map_err(|e| format!("Failed to parse JSON: {}", e))?;
 
     // Extract data from the response
-    let chart = json["chart"]["result"][0]
-        .as_object()
-        .ok_or("Invalid response structure")?;
+    // Indexing a Value yields null for missing keys, so responses without events are fine
+    let chart = &json["chart"]["result"][0];
+    if !chart.is_object() {
+        return Err("Invalid response structure".to_string());
+    }
 
     let timestamps = chart["timestamp"]
         .as_array()
@@ -133,13 +419,32 @@ async fn fetch_yfinance_data(
         .map(|v| v.as_i64())
         .collect::<Vec<Option<i64>>>();
 
+    // Only daily and longer intervals carry an adjclose series
+    let adj_closes = chart["indicators"]["adjclose"][0]["adjclose"]
+        .as_array()
+        .map(|values| {
+            values
+                .iter()
+                .map(|v| v.as_f64())
+                .collect::<Vec<Option<f64>>>()
+        })
+        .unwrap_or_default();
+
     let mut candles = Vec::new();
 
     for i in 0..timestamps.len() {
         if let (Some(open), Some(high), Some(low), Some(close), Some(volume)) =
             (opens[i], highs[i], lows[i], closes[i], volumes[i])
         {
-            candles.push((timestamps[i], open, high, low, close, volume));
+            candles.push(Candle {
+                timestamp: timestamps[i],
+                open,
+                high,
+                low,
+                close,
+                volume: volume as f64,
+                adj_close: adj_closes.get(i).copied().flatten(),
+            });
         }
     }
 
@@ -147,33 +452,146 @@ async fn fetch_yfinance_data(
         return Err("No valid candle data found".to_string());
     }
 
-    Ok(candles)
+    let actions = parse_corporate_actions(&chart["events"]);
+    let metadata = parse_metadata(&chart["meta"], &candles);
+
+    Ok(YahooChart {
+        candles,
+        actions,
+        metadata,
+    })
+}
+
+fn parse_metadata(meta: &serde_json::Value, candles: &[Candle]) -> SymbolMetadata {
+    let text = |key: &str| meta[key].as_str().map(|s| s.to_string());
+    let gmt_offset = meta["gmtoffset"].as_i64();
+
+    // currentTradingPeriod holds UTC timestamps for today's pre, regular and post sessions
+    let regular = &meta["currentTradingPeriod"]["regular"];
+    let regular_session = match (
+        regular["start"].as_i64(),
+        regular["end"].as_i64(),
+        regular["gmtoffset"].as_i64().or(gmt_offset),
+    ) {
+        (Some(start), Some(end), Some(offset)) => {
+            let local_time = |ts: i64| {
+                chrono::DateTime::from_timestamp(ts + offset, 0)
+                    .map(|dt| dt.format("%H:%M").to_string())
+            };
+            match (local_time(start), local_time(end)) {
+                (Some(start), Some(end)) => Some(SessionHours { start, end }),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let price_precision = meta["priceHint"]
+        .as_u64()
+        .map(|hint| hint as u32)
+        .or_else(|| infer_price_precision(candles));
+
+    SymbolMetadata {
+        source: "yfinance".to_string(),
+        exchange: text("fullExchangeName").or_else(|| text("exchangeName")),
+        exchange_timezone: text("exchangeTimezoneName"),
+        gmt_offset,
+        currency: text("currency"),
+        instrument_type: text("instrumentType"),
+        price_precision,
+        regular_session,
+    }
+}
+
+// Most decimal places seen in the closes, capped at 6 to ignore float noise
+fn infer_price_precision(candles: &[Candle]) -> Option<u32> {
+    candles
+        .iter()
+        .take(500)
+        .map(|c| {
+            let text = format!("{}", c.close);
+            text.split_once('.')
+                .map(|(_, decimals)| decimals.len().min(6) as u32)
+                .unwrap_or(0)
+        })
+        .max()
 }
 
-fn save_candles_to_csv(
-    file_path: &PathBuf,
-    candles: &[(i64, f64, f64, f64, f64, i64)],
-) -> Result<(), String> {
+// The events block maps each date to an event object, e.g.
+// "dividends": {"1699885800": {"amount": 0.24, "date": 1699885800}}
+fn parse_corporate_actions(events: &serde_json::Value) -> CorporateActions {
+    let mut actions = CorporateActions::default();
+
+    if let Some(dividends) = events["dividends"].as_object() {
+        for dividend in dividends.values() {
+            if let (Some(timestamp), Some(amount)) =
+                (dividend["date"].as_i64(), dividend["amount"].as_f64())
+            {
+                actions.dividends.push(Dividend { timestamp, amount });
+            }
+        }
+    }
+
+    if let Some(splits) = events["splits"].as_object() {
+        for split in splits.values() {
+            if let (Some(timestamp), Some(numerator), Some(denominator)) = (
+                split["date"].as_i64(),
+                split["numerator"].as_f64(),
+                split["denominator"].as_f64(),
+            ) {
+                actions.splits.push(Split {
+                    timestamp,
+                    numerator,
+                    denominator,
+                });
+            }
+        }
+    }
+
+    actions.dividends.sort_by_key(|d| d.timestamp);
+    actions.splits.sort_by_key(|s| s.timestamp);
+
+    actions
+}
+
+fn save_candles_to_csv(file_path: &PathBuf, candles: &[Candle]) -> Result<(), String> {
     let mut file = File::create(file_path).map_err(|e| format!("Failed to create file: {}", e))?;
 
+    let has_adj_close = candles.iter().any(|c| c.adj_close.is_some());
+
     // Write header
-    writeln!(file, "timestamp,open,high,low,close,volume")
-        .map_err(|e| format!("Failed to write header: {}", e))?;
+    if has_adj_close {
+        writeln!(file, "timestamp,open,high,low,close,volume,adjclose")
+    } else {
+        writeln!(file, "timestamp,open,high,low,close,volume")
+    }
+    .map_err(|e| format!("Failed to write header: {}", e))?;
 
     // Write data
-    for (timestamp, open, high, low, close, volume) in candles {
-        writeln!(
+    for c in candles {
+        write!(
             file,
             "{},{},{},{},{},{}",
-            timestamp, open, high, low, close, volume
+            c.timestamp, c.open, c.high, c.low, c.close, c.volume
         )
         .map_err(|e| format!("Failed to write data: {}", e))?;
+
+        if has_adj_close {
+            write!(
+                file,
+                ",{}",
+                c.adj_close.map(|v| v.to_string()).unwrap_or_default()
+            )
+            .map_err(|e| format!("Failed to write data: {}", e))?;
+        }
+
+        writeln!(file).map_err(|e| format!("Failed to write data: {}", e))?;
     }
 
     Ok(())
 }
 
-fn convert_date_to_timestamp(date_str: &str) -> Result<String, String> {
+fn convert_date_to_timestamp(date_str: &str) -> Result<i64, String> {
     use chrono::NaiveDate;
 
     let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
@@ -182,9 +600,29 @@ fn convert_date_to_timestamp(date_str: &str) -> Result<String, String> {
     let datetime = date
         .and_hms_opt(0, 0, 0)
         .ok_or("Failed to create datetime")?;
-    let timestamp = datetime.and_utc().timestamp();
+    Ok(datetime.and_utc().timestamp())
+}
+
+#[tauri::command]
+pub async fn search_yfinance_symbols(
+    query: String,
+    base_url: Option<String>,
+) -> Result<Vec<YFinanceSearchResult>, String> {
+    let query = query.trim();
+
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    search_yfinance(yahoo_base_url(base_url.as_deref()), query).await
+}
 
-    Ok(timestamp.to_string())
+#[tauri::command]
+pub async fn check_yfinance_symbol(
+    symbol: String,
+    base_url: Option<String>,
+) -> Result<YFinanceSymbolCheck, String> {
+    check_yfinance_history(yahoo_base_url(base_url.as_deref()), symbol.trim()).await
 }
 
 #[tauri::command]
@@ -203,52 +641,106 @@ pub async fn fetch_yfinance_data_command(
         return Err("Symbol name can only contain letters, numbers, and underscores".to_string());
     }
 
-    // Check if symbol already exists
     let app_dir = app_handle
         .path()
         .app_data_dir()
         .map_err(|e| format!("Failed to get app data directory: {}", e))?;
 
-    let symbols_dir = app_dir.join("symbols");
-    let symbol_json = symbols_dir.join(format!("{}.json", save_as));
+    // Existing symbols are only written to through a merge
+    let target = import_target(&app_handle, &save_as, request.merge)?;
 
-    if symbol_json.exists() {
-        return Err(format!(
-            "Symbol '{}' already exists. Please delete it first or choose a different name.",
-            save_as
-        ));
+    // Convert dates to timestamps
+    let mut start_timestamp = convert_date_to_timestamp(&request.start_date)?;
+    let end_timestamp = convert_date_to_timestamp(&request.end_date)?;
+
+    if end_timestamp <= start_timestamp {
+        return Err("End date must be after start date".to_string());
     }
 
-    // Create temporary directory for CSV files
-    let temp_dir = app_dir.join("temp").join(&save_as);
-    fs::create_dir_all(&temp_dir).map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let base_url = yahoo_base_url(request.base_url.as_deref());
+    let mut warnings = Vec::new();
 
-    // Convert dates to timestamps
-    let start_timestamp = convert_date_to_timestamp(&request.start_date)?;
-    let end_timestamp = convert_date_to_timestamp(&request.end_date)?;
+    // Catch typos and ranges before the listing date up front, not per timeframe
+    let check = check_yfinance_history(base_url, &request.symbol).await?;
+
+    if let (Some(first_trade), Some(first_date)) =
+        (check.first_trade_timestamp, &check.first_trade_date)
+    {
+        if first_trade >= end_timestamp {
+            return Err(format!(
+                "{} has no history in the requested range (first trade {})",
+                request.symbol, first_date
+            ));
+        }
+
+        if first_trade > start_timestamp {
+            warnings.push(format!(
+                "History for {} starts at {}",
+                request.symbol, first_date
+            ));
+            start_timestamp = first_trade;
+        }
+    }
+
+    // Downloaded candles are kept in the app data directory for later loads
+    let data_dir = app_dir.join("data").join(&target);
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+
+    let now = chrono::Utc::now().timestamp();
 
     let mut timeframe_infos = Vec::new();
     let mut total_candles = 0;
     let mut downloaded_timeframes = Vec::new();
+    let mut actions = CorporateActions::default();
+    let mut metadata = None;
 
     // Download data for each timeframe
     for tf in &request.timeframes {
         let yf_interval =
             map_timeframe_to_yfinance(tf).ok_or(format!("Unsupported timeframe: {}", tf))?;
 
-        println!("Fetching {} data for {}...", tf, request.symbol);
+        let windows = match plan_request_windows(&yf_interval, start_timestamp, end_timestamp, now)
+        {
+            Ok(plan) => {
+                if let Some(warning) = plan.warning {
+                    println!("! {} - {}", tf, warning);
+                    warnings.push(format!("{}: {}", tf, warning));
+                }
+                plan.windows
+            }
+            Err(e) => {
+                println!("✗ {} - {}", tf, e);
+                warnings.push(format!("{}: {}", tf, e));
+                continue;
+            }
+        };
 
-        match fetch_yfinance_data(
+        println!(
+            "Fetching {} data for {} in {} request{}...",
+            tf,
+            request.symbol,
+            windows.len(),
+            if windows.len() == 1 { "" } else { "s" }
+        );
+
+        match fetch_yfinance_windows(
+            base_url,
             &request.symbol,
             &yf_interval,
-            &start_timestamp,
-            &end_timestamp,
+            &windows,
+            request.include_events,
         )
         .await
         {
-            Ok(candles) => {
-                let csv_path = temp_dir.join(format!("{}.csv", tf));
+            Ok(YahooChart {
+                candles,
+                actions: chart_actions,
+                metadata: chart_metadata,
+            }) => {
+                let csv_path = data_dir.join(format!("{}.csv", tf));
                 save_candles_to_csv(&csv_path, &candles)?;
+                actions.merge(chart_actions);
+                metadata.get_or_insert(chart_metadata);
 
                 let display_name = convert_timeframe_to_display(tf);
                 let candle_count = candles.len();
@@ -258,6 +750,10 @@ pub async fn fetch_yfinance_data_command(
                     display_name,
                     candle_count,
                     file_path: csv_path.to_string_lossy().to_string(),
+                    source_sha: None,
+                    format: None,
+                    coverage: None,
+                    source_timezone: Some("UTC".to_string()), // Epoch timestamps
                 });
 
                 total_candles += candle_count;
@@ -273,8 +769,15 @@ pub async fn fetch_yfinance_data_command(
     }
 
     if timeframe_infos.is_empty() {
-        // Clean up temp directory
-        let _ = fs::remove_dir_all(&temp_dir);
+        // Clean up data directory
+        let _ = fs::remove_dir_all(&data_dir);
+
+        if !warnings.is_empty() {
+            return Err(format!(
+                "Failed to download any timeframe data. {}",
+                warnings.join("; ")
+            ));
+        }
         return Err("Failed to download any timeframe data".to_string());
     }
 
@@ -290,32 +793,48 @@ pub async fn fetch_yfinance_data_command(
 
     // Save symbol data
     let symbol_data = SymbolData {
-        symbol: save_as.clone(),
+        symbol: target.clone(),
         timeframes: timeframe_infos,
         total_candles,
         uploaded_at: chrono::Utc::now().to_rfc3339(),
+        metadata,
+        ticks: None,
     };
 
     save_symbol_data(&app_handle, &symbol_data)?;
 
-    // Clean up temp directory
-    let _ = fs::remove_dir_all(&temp_dir);
+    let merge = finish_import(&app_handle, &save_as, &target, request.merge, "yfinance")?;
+
+    if request.include_events && !actions.is_empty() {
+        save_corporate_actions(&app_dir, &save_as, &actions)?;
+    }
 
     Ok(YFinanceResult {
         success: true,
         message: format!(
-            "Successfully downloaded {} timeframe{} with {} total candles",
+            "Successfully downloaded {} timeframe{} with {} total candles{}",
             downloaded_timeframes.len(),
             if downloaded_timeframes.len() == 1 {
                 ""
             } else {
                 "s"
             },
-            total_candles
+            total_candles,
+            if request.include_events {
+                format!(
+                    ", {} dividends and {} splits",
+                    actions.dividends.len(),
+                    actions.splits.len()
+                )
+            } else {
+                String::new()
+            }
         ),
         symbol: save_as,
         timeframes_downloaded: downloaded_timeframes,
         total_candles,
+        warnings,
+        merge,
     })
 }
 
@@ -341,3 +860,218 @@ fn save_symbol_data(app_handle: &AppHandle, symbol_data: &SymbolData) -> Result<
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_server::{query_param, serve, Response};
+    use super::*;
+
+    const SEARCH_JSON: &str = r#"{
+        "quotes": [
+            {"symbol": "AAPL", "longname": "Apple Inc.", "exchDisp": "NASDAQ", "quoteType": "EQUITY"},
+            {"symbol": "APC.F", "shortname": "APPLE INC", "exchange": "FRA", "quoteType": "EQUITY"},
+            {"longname": "No symbol, skipped"}
+        ]
+    }"#;
+
+    const CHECK_JSON: &str = r#"{"chart": {"result": [{"meta": {
+        "symbol": "AAPL", "longName": "Apple Inc.", "fullExchangeName": "NasdaqGS",
+        "instrumentType": "EQUITY", "currency": "USD", "firstTradeDate": 345479400
+    }}], "error": null}}"#;
+
+    // Two daily bars starting at period1, the second without a volume
+    fn chart_json(period1: i64) -> String {
+        format!(
+            r#"{{"chart": {{"result": [{{
+                "meta": {{"currency": "USD"}},
+                "timestamp": [{t0}, {t1}],
+                "indicators": {{
+                    "quote": [{{"open": [1.0, 2.0], "high": [1.5, 2.5], "low": [0.5, 1.5],
+                               "close": [1.2, 2.2], "volume": [100, null]}}],
+                    "adjclose": [{{"adjclose": [1.1, 2.1]}}]
+                }}
+            }}]}}}}"#,
+            t0 = period1,
+            t1 = period1 + SECONDS_PER_DAY
+        )
+    }
+
+    fn yahoo_stub() -> super::super::test_server::TestServer {
+        serve(|target| {
+            if target.starts_with("/v1/finance/search") {
+                Response::json(SEARCH_JSON)
+            } else if target.starts_with("/v8/finance/chart/MISSING") {
+                Response::status(404)
+            } else if target.starts_with("/v8/finance/chart/DELISTED") {
+                Response::json(
+                    r#"{"chart": {"result": null, "error": {"description": "No data found, symbol may be delisted"}}}"#,
+                )
+            } else if target.starts_with("/v8/finance/chart/AAPL") {
+                match query_param(target, "period1") {
+                    Some(period1) => Response::json(chart_json(period1.parse().unwrap())),
+                    None => Response::json(CHECK_JSON),
+                }
+            } else {
+                Response::status(404)
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn search_maps_quotes() {
+        let server = yahoo_stub();
+        let results = search_yfinance(&server.base_url, "apple").await.unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].symbol, "AAPL");
+        assert_eq!(results[0].name.as_deref(), Some("Apple Inc."));
+        assert_eq!(results[0].exchange.as_deref(), Some("NASDAQ"));
+        assert_eq!(results[1].name.as_deref(), Some("APPLE INC"));
+        assert_eq!(results[1].exchange.as_deref(), Some("FRA"));
+
+        let requests = server.requests.lock().unwrap();
+        assert_eq!(query_param(&requests[0], "q").as_deref(), Some("apple"));
+    }
+
+    #[tokio::test]
+    async fn check_reads_listing_metadata() {
+        let server = yahoo_stub();
+        let check = check_yfinance_history(&server.base_url, "AAPL")
+            .await
+            .unwrap();
+
+        assert_eq!(check.name.as_deref(), Some("Apple Inc."));
+        assert_eq!(check.currency.as_deref(), Some("USD"));
+        assert_eq!(check.first_trade_timestamp, Some(345479400));
+        assert_eq!(check.first_trade_date.as_deref(), Some("1980-12-12"));
+    }
+
+    #[tokio::test]
+    async fn check_reports_unknown_symbols() {
+        let server = yahoo_stub();
+
+        let missing = check_yfinance_history(&server.base_url, "MISSING").await;
+        assert!(missing.unwrap_err().contains("was not found"));
+
+        let delisted = check_yfinance_history(&server.base_url, "DELISTED").await;
+        assert!(delisted.unwrap_err().contains("delisted"));
+    }
+
+    #[tokio::test]
+    async fn windows_are_merged_without_duplicates() {
+        let server = yahoo_stub();
+        let day = SECONDS_PER_DAY;
+        // The second window starts on the first window's second bar
+        let windows = [(10 * day, 11 * day), (11 * day, 12 * day)];
+
+        let chart = fetch_yfinance_windows(&server.base_url, "AAPL", "1d", &windows, false)
+            .await
+            .unwrap();
+
+        // Bars without a volume are dropped, so each window contributes its first bar only
+        let times: Vec<i64> = chart.candles.iter().map(|c| c.timestamp).collect();
+        assert_eq!(times, vec![10 * day, 11 * day]);
+        assert_eq!(chart.candles[0].adj_close, Some(1.1));
+        assert_eq!(server.requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn ranges_split_into_whole_windows() {
+        let day = SECONDS_PER_DAY;
+        let now = 1000 * day;
+
+        // 21 days of 1m bars is exactly three 7 day windows, with no empty tail
+        let plan = plan_request_windows("1m", now - 21 * day, now, now).unwrap();
+        assert_eq!(
+            plan.windows,
+            vec![
+                (now - 21 * day, now - 14 * day),
+                (now - 14 * day, now - 7 * day),
+                (now - 7 * day, now),
+            ]
+        );
+        assert!(plan.warning.is_none());
+
+        // Daily bars have no limits and go out as a single request
+        let plan = plan_request_windows("1d", 0, now, now).unwrap();
+        assert_eq!(plan.windows, vec![(0, now)]);
+        assert_eq!(interval_limits("1d"), None);
+    }
+
+    #[test]
+    fn starts_beyond_the_lookback_are_clamped() {
+        let day = SECONDS_PER_DAY;
+        let now = 1000 * day;
+        let oldest = now - 30 * day + 3600;
+
+        let plan = plan_request_windows("1m", now - 40 * day, now - 10 * day, now).unwrap();
+        assert_eq!(
+            plan.windows,
+            vec![
+                (oldest, oldest + 7 * day),
+                (oldest + 7 * day, oldest + 14 * day),
+                (oldest + 14 * day, now - 10 * day),
+            ]
+        );
+        assert!(plan.warning.unwrap().contains("last 30 days"));
+
+        // Nothing left once the whole range is older than the lookback
+        let error = plan_request_windows("1m", now - 40 * day, now - 31 * day, now);
+        assert!(error.unwrap_err().contains("No 1m history"));
+    }
+
+    #[test]
+    fn end_before_start_is_rejected() {
+        let day = SECONDS_PER_DAY;
+        let now = 1000 * day;
+
+        for interval in ["1m", "1h", "1d"] {
+            let error = plan_request_windows(interval, now - day, now - 2 * day, now);
+            assert_eq!(error.unwrap_err(), "End date must be after start date");
+            assert!(plan_request_windows(interval, now - day, now - day, now).is_err());
+        }
+    }
+
+    #[test]
+    fn corporate_actions_are_read_from_chart_events() {
+        let events: serde_json::Value = serde_json::from_str(
+            r#"{
+                "dividends": {
+                    "1699885800": {"amount": 0.24, "date": 1699885800},
+                    "1691674200": {"amount": 0.24, "date": 1691674200},
+                    "1": {"amount": "bad", "date": 1}
+                },
+                "splits": {
+                    "1598880600": {"date": 1598880600, "numerator": 4, "denominator": 1, "splitRatio": "4:1"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let actions = parse_corporate_actions(&events);
+
+        let dividends: Vec<(i64, f64)> = actions
+            .dividends
+            .iter()
+            .map(|d| (d.timestamp, d.amount))
+            .collect();
+        assert_eq!(dividends, vec![(1691674200, 0.24), (1699885800, 0.24)]);
+        assert_eq!(actions.splits.len(), 1);
+        assert_eq!(
+            (actions.splits[0].numerator, actions.splits[0].denominator),
+            (4.0, 1.0)
+        );
+
+        assert!(parse_corporate_actions(&serde_json::Value::Null).is_empty());
+    }
+
+    #[test]
+    fn base_url_override() {
+        assert_eq!(yahoo_base_url(None), YAHOO_BASE_URL);
+        assert_eq!(yahoo_base_url(Some(" ")), YAHOO_BASE_URL);
+        assert_eq!(
+            yahoo_base_url(Some("http://127.0.0.1:9000/")),
+            "http://127.0.0.1:9000"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use crate::upload_function::bars::BarType;
+
+// Bumped whenever the format changes, older files are upgraded in `upgrade`
+pub const WORKSPACE_VERSION: u32 = 1;
+
+pub const AUTOSAVE_NAME: &str = "Autosave";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceIndicator {
+    pub name: String,
+    #[serde(default)]
+    pub params: serde_json::Value, // Whatever the indicator was configured with
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct DrawingPoint {
+    pub timestamp: i64, // Unix seconds
+    pub price: f64,
+}
+
+// A shape placed by the user, as opposed to the generated analysis drawings
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceDrawing {
+    pub shape: String, // ShapeModal name, e.g. "Trend Line" or "Fibonacci"
+    pub points: Vec<DrawingPoint>,
+    #[serde(default)]
+    pub style: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceChart {
+    pub symbol: String,
+    pub timeframe: String,
+    #[serde(default)]
+    pub chart_type: Option<String>,
+    #[serde(default)]
+    pub indicators: Vec<WorkspaceIndicator>,
+    #[serde(default)]
+    pub drawings: Vec<WorkspaceDrawing>,
+}
+
+// Where the replay was, restored by starting a new replay from `time_ms`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplayCursor {
+    pub symbol: String,
+    pub timeframe: String,
+    pub time_ms: i64,
+    #[serde(default)]
+    pub speed: Option<f64>,
+    #[serde(default)]
+    pub bar_type: Option<BarType>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderKind {
+    Market,
+    Limit,
+    Stop,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceOrder {
+    pub id: String,
+    pub symbol: String,
+    pub side: String, // "buy" or "sell"
+    pub kind: OrderKind,
+    pub size: f64,
+    #[serde(default)]
+    pub price: Option<f64>, // Missing for market orders
+    #[serde(default)]
+    pub stop_loss: Option<f64>,
+    #[serde(default)]
+    pub take_profit: Option<f64>,
+    pub created_at: i64, // Replay time, unix seconds
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AccountState {
+    pub balance: f64,
+    pub equity: f64,
+    #[serde(default)]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub realized_pnl: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Workspace {
+    #[serde(default)]
+    pub version: u32,
+    pub name: String,
+    #[serde(default)]
+    pub saved_at: String,
+    #[serde(default)]
+    pub charts: Vec<WorkspaceChart>,
+    #[serde(default)]
+    pub active_chart: usize,
+    #[serde(default)]
+    pub replay: Option<ReplayCursor>,
+    #[serde(default)]
+    pub orders: Vec<WorkspaceOrder>,
+    #[serde(default)]
+    pub account: Option<AccountState>,
+    #[serde(default)]
+    pub layout: serde_json::Value, // Panel sizes and placement, owned by the frontend
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub saved_at: String,
+    pub charts: Vec<String>, // "SYMBOL TF" of each chart
+    pub autosave: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WorkspaceResult {
+    pub success: bool,
+    pub message: String,
+    pub name: String,
+}
+
+fn workspaces_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+
+    Ok(app_dir.join("workspaces"))
+}
+
+// Names become file names, so only keep characters that are safe everywhere
+fn workspace_file(app_handle: &AppHandle, name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Workspace name cannot be empty".to_string());
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'))
+        || name.starts_with('.')
+    {
+        return Err(format!(
+            "Invalid workspace name '{}', use letters, digits, spaces, '-', '_' or '.'",
+            name
+        ));
+    }
+
+    Ok(workspaces_dir(app_handle)?.join(format!("{}.json", name)))
+}
+
+// Bring an older file up to the current version
+fn upgrade(mut workspace: Workspace) -> Result<Workspace, String> {
+    if workspace.version > WORKSPACE_VERSION {
+        return Err(format!(
+            "Workspace '{}' was saved by a newer version (format {})",
+            workspace.name, workspace.version
+        ));
+    }
+
+    // Version 0 files predate the version field and need nothing else
+    workspace.version = WORKSPACE_VERSION;
+
+    Ok(workspace)
+}
+
+fn read_workspace(path: &Path) -> Result<Workspace, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read workspace: {}", e))?;
+    let workspace: Workspace =
+        serde_json::from_str(&content).map_err(|e| format!("Failed to parse workspace: {}", e))?;
+
+    upgrade(workspace)
+}
+
+#[tauri::command]
+pub async fn workspace_save(
+    app_handle: AppHandle,
+    mut workspace: Workspace,
+) -> Result<WorkspaceResult, String> {
+    workspace.name = workspace.name.trim().to_string();
+    let path = workspace_file(&app_handle, &workspace.name)?;
+
+    if workspace.active_chart >= workspace.charts.len().max(1) {
+        return Err(format!(
+            "Active chart {} is not one of the {} charts",
+            workspace.active_chart,
+            workspace.charts.len()
+        ));
+    }
+
+    workspace.version = WORKSPACE_VERSION;
+    workspace.saved_at = chrono::Utc::now().to_rfc3339();
+
+    let dir = workspaces_dir(&app_handle)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create workspaces directory: {}", e))?;
+
+    let json = serde_json::to_string_pretty(&workspace)
+        .map_err(|e| format!("Failed to serialize workspace: {}", e))?;
+
+    // Write next to the target and swap it in, so an autosave can't leave half a file
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, json).map_err(|e| format!("Failed to write workspace: {}", e))?;
+    fs::rename(&temp, &path).map_err(|e| format!("Failed to save workspace: {}", e))?;
+
+    Ok(WorkspaceResult {
+        success: true,
+        message: format!("Workspace '{}' saved", workspace.name),
+        name: workspace.name,
+    })
+}
+
+#[tauri::command]
+pub async fn workspace_open(app_handle: AppHandle, name: String) -> Result<Workspace, String> {
+    let path = workspace_file(&app_handle, &name)?;
+
+    if !path.exists() {
+        return Err(format!("Workspace '{}' not found", name.trim()));
+    }
+
+    read_workspace(&path)
+}
+
+// Saved workspaces, most recent first
+#[tauri::command]
+pub async fn workspace_list(app_handle: AppHandle) -> Result<Vec<WorkspaceInfo>, String> {
+    let dir = workspaces_dir(&app_handle)?;
+
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries =
+        fs::read_dir(&dir).map_err(|e| format!("Failed to read workspaces directory: {}", e))?;
+
+    let mut workspaces = Vec::new();
+
+    for entry in entries {
+        let path = entry
+            .map_err(|e| format!("Failed to read entry: {}", e))?
+            .path();
+
+        if path.extension().and_then(|s| s.to_str()) != Some("json") {
+            continue;
+        }
+
+        match read_workspace(&path) {
+            Ok(workspace) => workspaces.push(WorkspaceInfo {
+                autosave: workspace.name == AUTOSAVE_NAME,
+                charts: workspace
+                    .charts
+                    .iter()
+                    .map(|chart| format!("{} {}", chart.symbol, chart.timeframe))
+                    .collect(),
+                saved_at: workspace.saved_at,
+                name: workspace.name,
+            }),
+            Err(e) => println!("✗ Skipping workspace {}: {}", path.display(), e),
+        }
+    }
+
+    workspaces.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+
+    Ok(workspaces)
+}
@@ -1,10 +1,233 @@
 // src/stores/chartStore.ts
 import { writable } from "svelte/store";
+import { invoke } from "@tauri-apps/api/core";
+import type { ChartType, GeneralSettings } from "./settingsStore";
+
+export interface Gap {
+	start: number;
+	end: number;
+	duration_seconds: number;
+	missing_bars: number;
+}
+
+export interface GapReport {
+	timeframe: string;
+	calendar: string;
+	first_bar: number | null;
+	last_bar: number | null;
+	actual_bars: number;
+	expected_bars: number;
+	missing_bars: number;
+	coverage_percent: number;
+	gaps: Gap[];
+	truncated: boolean;
+}
+
+// Matches the backend's BarType, passed to get_candles and start_tick_replay
+export type BarType =
+	| { kind: "heikin_ashi" }
+	| { kind: "renko"; box_size?: number; atr_period?: number }
+	| { kind: "range"; size: number }
+	| { kind: "tick"; count: number }
+	| { kind: "volume"; volume: number };
+
+// Bar request for a chart type, null for plain time candles
+export function barTypeFor(chartType: ChartType | string, settings: GeneralSettings): BarType | null {
+	switch (chartType) {
+		case "heikin_ashi":
+			return { kind: "heikin_ashi" };
+		case "renko":
+			return settings.renkoBoxSize > 0
+				? { kind: "renko", box_size: settings.renkoBoxSize }
+				: { kind: "renko", atr_period: settings.renkoAtrPeriod };
+		case "range":
+			return { kind: "range", size: settings.rangeBarSize };
+		case "tick":
+			return { kind: "tick", count: settings.tickBarCount };
+		case "volume":
+			return { kind: "volume", volume: settings.volumeBarSize };
+		default:
+			return null;
+	}
+}
+
+export interface ProfileRow {
+	price: number; // Bottom of the row
+	volume: number;
+	tpo?: string;
+}
+
+export interface VolumeProfile {
+	start: number;
+	end: number;
+	source: string;
+	row_size: number;
+	total_volume: number;
+	poc: number;
+	value_area_high: number;
+	value_area_low: number;
+	high_volume_nodes: number[];
+	low_volume_nodes: number[];
+	rows: ProfileRow[];
+	tpo?: {
+		periods: number;
+		poc: number;
+		value_area_high: number;
+		value_area_low: number;
+		initial_balance_high: number | null;
+		initial_balance_low: number | null;
+		single_prints: number;
+	};
+}
+
+export interface SessionDefinition {
+	name: string;
+	kind: "session" | "killzone";
+	timezone: string; // IANA zone or fixed offset with DST rule, e.g. "+02:00/US"
+	start: string; // "HH:MM" local
+	end: string;
+	days?: string[]; // "Mon".."Sun", Mon-Fri when missing
+}
+
+export interface SessionWindow {
+	name: string;
+	kind: "session" | "killzone";
+	open: number; // UTC unix seconds
+	close: number;
+	stats?: {
+		open: number;
+		high: number;
+		low: number;
+		close: number;
+		range: number;
+		high_time: number;
+		low_time: number;
+		bars: number;
+	};
+}
+
+export interface ProfileOptions {
+	start?: number; // Unix seconds, e.g. the visible range of the chart
+	end?: number;
+	per_session?: boolean;
+	tpo?: boolean;
+	row_size?: number;
+	rows?: number;
+	value_area_percent?: number;
+}
+
+export type SwingMethod = { method: "fractal"; strength: number } | { method: "zigzag"; deviation: number };
+
+export interface SwingPoint {
+	side: "high" | "low";
+	timestamp: number;
+	price: number;
+	label: "HH" | "HL" | "LH" | "LL" | null;
+	confirmed_at: number; // Bar that confirmed the swing
+}
+
+export interface StructureBreak {
+	kind: "bos" | "choch";
+	direction: "bullish" | "bearish";
+	level: number;
+	swing_time: number;
+	timestamp: number;
+}
+
+export type StructureEvent = ({ type: "swing" } & SwingPoint) | ({ type: "break" } & StructureBreak);
+
+export interface Drawing {
+	kind: "label" | "line" | "rectangle";
+	time1: number;
+	price1: number;
+	time2: number | null;
+	price2: number | null;
+	text: string;
+	color: string;
+}
+
+export interface StructureResult {
+	swings: SwingPoint[];
+	breaks: StructureBreak[];
+	zones: {
+		kind: "support" | "resistance";
+		low: number;
+		high: number;
+		touches: number;
+		first_touch: number;
+		last_touch: number;
+	}[];
+	drawings: Drawing[];
+	trend: "bullish" | "bearish" | null;
+}
+
+export type PatternKind =
+	| "doji"
+	| "pin_bar"
+	| "engulfing"
+	| "inside_bar"
+	| "outside_bar"
+	| "harami"
+	| "morning_star"
+	| "evening_star"
+	| "three_white_soldiers"
+	| "three_black_crows";
+
+export interface PatternConfig {
+	patterns?: PatternKind[]; // All when missing
+	tolerances?: Partial<Record<PatternKind, number>>; // 0-1, meaning depends on the pattern
+}
+
+export interface PatternHit {
+	pattern: PatternKind;
+	direction: "bullish" | "bearish" | null;
+	strength: number; // 0-1
+	start: number;
+	timestamp: number; // Bar that completed the pattern
+}
+
+export interface FibAnchor {
+	timestamp: number; // Unix seconds
+	price: number;
+}
+
+export interface FibLevelSet {
+	name: string;
+	retracement: number[];
+	extension: number[];
+	projection: number[]; // Only drawn with a third anchor
+}
+
+export interface FibResult {
+	anchors: FibAnchor[]; // Snapped to candle highs/lows when requested
+	level_set: string;
+	levels: {
+		kind: "retracement" | "extension" | "projection";
+		ratio: number;
+		price: number;
+		first_touch: number | null;
+		touches: number;
+	}[];
+}
+
+export interface FibOptions {
+	levelSet?: string;
+	snap?: boolean;
+	snapWindow?: number;
+}
 
 interface ChartState {
 	selectedSymbol: string | null;
 	selectedTimeframe: string | null;
 	availableTimeframes: string[];
+	gapReport: GapReport | null;
+	profiles: VolumeProfile[];
+	sessions: SessionWindow[];
+	structure: StructureResult | null;
+	structureConfig: SwingMethod | null; // Also sent to tick replays for structure alerts
+	patterns: PatternHit[];
+	patternConfig: PatternConfig | null; // Same for pattern alerts
+	fibonacci: FibResult | null;
 }
 
 function createChartStore() {
@@ -12,6 +235,14 @@ function createChartStore() {
 		selectedSymbol: null,
 		selectedTimeframe: null,
 		availableTimeframes: [],
+		gapReport: null,
+		profiles: [],
+		sessions: [],
+		structure: null,
+		structureConfig: null,
+		patterns: [],
+		patternConfig: null,
+		fibonacci: null,
 	});
 
 	return {
@@ -22,22 +253,167 @@ function createChartStore() {
 				selectedSymbol: symbol,
 				availableTimeframes: timeframes,
 				selectedTimeframe: timeframes[0] || null,
+				gapReport: null,
+				profiles: [],
+				sessions: [],
+				structure: null,
+				patterns: [],
+				fibonacci: null,
 			}));
 		},
 		setTimeframe: (timeframe: string) => {
 			update((state) => ({
 				...state,
 				selectedTimeframe: timeframe,
+				gapReport: null,
+				structure: null,
+				patterns: [],
+				fibonacci: null,
 			}));
 		},
+		// Gap regions of the selected timeframe, used to shade missing data on the chart
+		loadGaps: async (symbol: string, timeframe: string) => {
+			try {
+				const reports = await invoke<GapReport[]>("analyze_gaps", { symbol, timeframe });
+				update((state) => ({ ...state, gapReport: reports[0] ?? null }));
+			} catch (error) {
+				console.error("Failed to analyze gaps:", error);
+			}
+		},
+		// Volume/market profiles of the selected symbol, ticks are used when stored
+		loadProfiles: async (symbol: string, options: ProfileOptions = {}) => {
+			try {
+				const profiles = await invoke<VolumeProfile[]>("compute_volume_profile", {
+					request: { symbol, ...options },
+				});
+				update((state) => ({ ...state, profiles }));
+			} catch (error) {
+				console.error("Failed to compute volume profile:", error);
+			}
+		},
+		// Session and killzone windows with their high/low, the built-in ones unless given
+		loadSessions: async (
+			symbol: string,
+			start: number,
+			end: number,
+			sessions?: SessionDefinition[]
+		) => {
+			try {
+				const windows = await invoke<SessionWindow[]>("get_sessions", {
+					request: { symbol, start, end, sessions: sessions ?? null },
+				});
+				update((state) => ({ ...state, sessions: windows }));
+			} catch (error) {
+				console.error("Failed to load sessions:", error);
+			}
+		},
+		// Swings, BOS/CHoCH and support/resistance zones of the selected timeframe
+		loadStructure: async (
+			symbol: string,
+			timeframe: string,
+			config: SwingMethod = { method: "fractal", strength: 2 }
+		) => {
+			try {
+				const structure = await invoke<StructureResult>("detect_structure", {
+					request: { symbol, timeframe, config },
+				});
+				update((state) => ({ ...state, structure, structureConfig: config }));
+			} catch (error) {
+				console.error("Failed to detect market structure:", error);
+			}
+		},
+		clearStructure: () => {
+			update((state) => ({ ...state, structure: null, structureConfig: null }));
+		},
+		// Candlestick patterns of the selected timeframe
+		loadPatterns: async (symbol: string, timeframe: string, config: PatternConfig = {}) => {
+			try {
+				const patterns = await invoke<PatternHit[]>("scan_patterns", {
+					request: { symbol, timeframe, config },
+				});
+				update((state) => ({ ...state, patterns, patternConfig: config }));
+			} catch (error) {
+				console.error("Failed to scan candlestick patterns:", error);
+			}
+		},
+		clearPatterns: () => {
+			update((state) => ({ ...state, patterns: [], patternConfig: null }));
+		},
+		// Fibonacci levels between two or three anchors, with the candles that reached them since
+		loadFibonacci: async (
+			symbol: string,
+			timeframe: string,
+			anchors: FibAnchor[],
+			options: FibOptions = {}
+		) => {
+			try {
+				const fibonacci = await invoke<FibResult>("compute_fibonacci", {
+					request: {
+						symbol,
+						timeframe,
+						anchors,
+						level_set: options.levelSet ?? null,
+						snap: options.snap ?? true,
+						snap_window: options.snapWindow ?? null,
+					},
+				});
+				update((state) => ({ ...state, fibonacci }));
+			} catch (error) {
+				console.error("Failed to compute Fibonacci levels:", error);
+			}
+		},
+		clearFibonacci: () => {
+			update((state) => ({ ...state, fibonacci: null }));
+		},
+		clearProfiles: () => {
+			update((state) => ({ ...state, profiles: [] }));
+		},
 		reset: () => {
 			set({
 				selectedSymbol: null,
 				selectedTimeframe: null,
 				availableTimeframes: [],
+				gapReport: null,
+				profiles: [],
+				sessions: [],
+				structure: null,
+				structureConfig: null,
+				patterns: [],
+				patternConfig: null,
+				fibonacci: null,
 			});
 		},
 	};
 }
 
 export const chartStore = createChartStore();
+
+// Saved Fibonacci level sets, the built-in "Default" included
+export async function listFibLevelSets(): Promise<FibLevelSet[]> {
+	try {
+		return await invoke<FibLevelSet[]>("list_fib_level_sets");
+	} catch (error) {
+		console.error("Failed to list Fibonacci level sets:", error);
+		return [];
+	}
+}
+
+export async function saveFibLevelSet(levelSet: FibLevelSet): Promise<boolean> {
+	try {
+		await invoke("save_fib_level_set", { levelSet });
+		return true;
+	} catch (error) {
+		console.error("Failed to save Fibonacci level set:", error);
+		return false;
+	}
+}
+
+export async function deleteFibLevelSet(name: string): Promise<boolean> {
+	try {
+		await invoke("delete_fib_level_set", { name });
+		return true;
+	} catch (error) {
+		console.error("Failed to delete Fibonacci level set:", error);
+		return false;
+	}
+}
@@ -0,0 +1,316 @@
+// src/stores/replayStore.ts
+import { writable, get } from "svelte/store";
+import { invoke } from "@tauri-apps/api/core";
+import type {
+	BarType,
+	PatternConfig,
+	PatternHit,
+	StructureEvent,
+	SwingMethod,
+} from "./chartStore";
+
+export interface Tick {
+	timestamp_ms: number;
+	bid: number;
+	ask: number;
+	last: number;
+	volume: number;
+	flags: number;
+}
+
+export interface ReplayCandle {
+	timestamp: number;
+	open: number;
+	high: number;
+	low: number;
+	close: number;
+	volume: number;
+}
+
+interface ReplayStep {
+	session_id: number;
+	ticks: Tick[];
+	completed: ReplayCandle[];
+	forming: ReplayCandle | null;
+	bid: number | null;
+	ask: number | null;
+	spread: number | null;
+	structure?: StructureEvent[];
+	patterns?: PatternHit[];
+	finished: boolean;
+}
+
+export interface BlindTrade {
+	side: "buy" | "sell";
+	size: number;
+	entry_time: number; // Disguised unix seconds, as shown on the chart
+	entry_price: number;
+	exit_time?: number;
+	exit_price?: number;
+	note?: string;
+}
+
+export interface BlindOptions {
+	timeframe: string;
+	symbols?: string[]; // Pool to draw from, all symbols when missing
+	min_lookback?: number;
+	min_forward?: number;
+	scale_prices?: boolean;
+	offset_prices?: boolean;
+	bar_type?: BarType | null;
+}
+
+// The real identity of a blind session, only known once it ends
+export interface BlindReveal {
+	alias: string;
+	symbol: string;
+	timeframe: string;
+	real_start: number;
+	real_end: number | null;
+	disguise: { price_scale: number; price_offset: number; time_shift: number };
+	started_at: string;
+	ended_at: string;
+	trades: (BlindTrade & {
+		real_entry_time: number;
+		real_entry_price: number;
+		real_exit_time: number | null;
+		real_exit_price: number | null;
+		pnl: number | null;
+	})[];
+}
+
+interface BlindStart {
+	session_id: number;
+	alias: string;
+	timeframe: string;
+	history: ReplayCandle[];
+	first: ReplayStep;
+}
+
+interface ReplayState {
+	sessionId: number | null;
+	blind: boolean; // Symbol shows the alias, prices and dates are disguised
+	symbol: string | null;
+	timeframe: string | null;
+	candles: ReplayCandle[]; // Closed bars built so far
+	forming: ReplayCandle | null;
+	time: number | null; // Last played tick, unix ms
+	bid: number | null;
+	ask: number | null;
+	spread: number | null;
+	structure: StructureEvent[]; // Swings and breaks revealed so far
+	patterns: PatternHit[]; // Candlestick patterns completed so far
+	finished: boolean;
+	playing: boolean;
+}
+
+const initialState: ReplayState = {
+	sessionId: null,
+	blind: false,
+	symbol: null,
+	timeframe: null,
+	candles: [],
+	forming: null,
+	time: null,
+	bid: null,
+	ask: null,
+	spread: null,
+	structure: [],
+	patterns: [],
+	finished: false,
+	playing: false,
+};
+
+// Market time advanced per real second at 1x speed
+const PLAYBACK_MS_PER_SECOND = 1000;
+const PLAYBACK_INTERVAL_MS = 100;
+const MAX_IDLE_MS = 60_000;
+
+function createReplayStore() {
+	const store = writable<ReplayState>({ ...initialState });
+	const { subscribe, set, update } = store;
+	let timer: ReturnType<typeof setInterval> | null = null;
+	let speed = 1;
+	let structureListener: ((events: StructureEvent[]) => void) | null = null;
+	let patternListener: ((hits: PatternHit[]) => void) | null = null;
+
+	function apply(step: ReplayStep) {
+		update((state) => ({
+			...state,
+			sessionId: step.session_id,
+			candles: step.completed.length ? [...state.candles, ...step.completed] : state.candles,
+			forming: step.forming,
+			time: step.ticks.length ? step.ticks[step.ticks.length - 1].timestamp_ms : state.time,
+			bid: step.bid,
+			ask: step.ask,
+			spread: step.spread,
+			structure: step.structure?.length ? [...state.structure, ...step.structure] : state.structure,
+			patterns: step.patterns?.length ? [...state.patterns, ...step.patterns] : state.patterns,
+			finished: step.finished,
+		}));
+		if (step.structure?.length) structureListener?.(step.structure);
+		if (step.patterns?.length) patternListener?.(step.patterns);
+	}
+
+	function clearTimer() {
+		if (timer) {
+			clearInterval(timer);
+			timer = null;
+		}
+	}
+
+	async function step(count = 1, untilMs?: number) {
+		const { sessionId, blind } = get(store);
+		if (sessionId === null) return;
+
+		try {
+			const result = await invoke<ReplayStep>(blind ? "step_blind_replay" : "step_tick_replay", {
+				sessionId,
+				count,
+				untilMs: untilMs ?? null,
+			});
+			apply(result);
+			if (result.finished) pause();
+		} catch (error) {
+			console.error("Failed to step tick replay:", error);
+			pause();
+		}
+	}
+
+	function pause() {
+		clearTimer();
+		update((state) => ({ ...state, playing: false }));
+	}
+
+	async function stop() {
+		clearTimer();
+		const { sessionId, blind } = get(store);
+		set({ ...initialState });
+
+		if (sessionId !== null && blind) {
+			try {
+				await invoke("end_blind_replay", { sessionId });
+			} catch (error) {
+				console.error("Failed to end blind replay:", error);
+			}
+		} else if (sessionId !== null) {
+			try {
+				await invoke("stop_tick_replay", { sessionId });
+			} catch (error) {
+				console.error("Failed to stop tick replay:", error);
+			}
+		}
+	}
+
+	return {
+		subscribe,
+		// Start replaying a symbol, optionally from a point in time (unix ms), as derived bars
+		// and with structure and pattern events for alerts
+		start: async (
+			symbol: string,
+			timeframe: string,
+			fromMs?: number,
+			barType?: BarType | null,
+			structure?: SwingMethod | null,
+			patterns?: PatternConfig | null
+		) => {
+			await stop();
+			try {
+				const result = await invoke<ReplayStep>("start_tick_replay", {
+					symbol,
+					timeframe,
+					fromMs: fromMs ?? null,
+					barType: barType ?? null,
+					structure: structure ?? null,
+					patterns: patterns ?? null,
+				});
+				update((state) => ({ ...state, symbol, timeframe }));
+				apply(result);
+				return true;
+			} catch (error) {
+				console.error("Failed to start tick replay:", error);
+				return false;
+			}
+		},
+		// Replay a random symbol from a random start, under an alias with disguised prices/dates
+		startBlind: async (options: BlindOptions) => {
+			await stop();
+			try {
+				const result = await invoke<BlindStart>("start_blind_replay", { request: options });
+				update((state) => ({
+					...state,
+					blind: true,
+					symbol: result.alias,
+					timeframe: result.timeframe,
+					candles: result.history,
+				}));
+				apply(result.first);
+				return true;
+			} catch (error) {
+				console.error("Failed to start blind replay:", error);
+				return false;
+			}
+		},
+		recordBlindTrade: async (trade: BlindTrade) => {
+			const { sessionId, blind } = get(store);
+			if (sessionId === null || !blind) return false;
+			try {
+				await invoke("record_blind_trade", { sessionId, trade });
+				return true;
+			} catch (error) {
+				console.error("Failed to record blind trade:", error);
+				return false;
+			}
+		},
+		// End a blind session and reveal what was replayed, also logged with its trades
+		endBlind: async (): Promise<BlindReveal | null> => {
+			clearTimer();
+			const { sessionId, blind } = get(store);
+			if (sessionId === null || !blind) return null;
+
+			set({ ...initialState });
+			try {
+				return await invoke<BlindReveal>("end_blind_replay", { sessionId });
+			} catch (error) {
+				console.error("Failed to end blind replay:", error);
+				return null;
+			}
+		},
+		step,
+		setSpeed: (value: number) => {
+			speed = value;
+		},
+		// Called with the new swings and breaks of every step, for structure alerts
+		onStructure: (listener: ((events: StructureEvent[]) => void) | null) => {
+			structureListener = listener;
+		},
+		// Called with the patterns completed by every step, for pattern alerts
+		onPatterns: (listener: ((hits: PatternHit[]) => void) | null) => {
+			patternListener = listener;
+		},
+		// Play in market time at `speed` x real time, every tick in between included
+		play: () => {
+			clearTimer();
+			update((state) => ({ ...state, playing: true }));
+
+			let busy = false;
+			let clock: number | null = null;
+			timer = setInterval(async () => {
+				const { time, finished } = get(store);
+				if (busy || finished) return;
+
+				busy = true;
+				clock = Math.max(clock ?? time ?? 0, time ?? 0);
+				clock += (PLAYBACK_MS_PER_SECOND * speed * PLAYBACK_INTERVAL_MS) / 1000;
+				// Before the first tick, or after a quiet stretch (weekends), jump to the next tick
+				const idle = time === null || clock - time > MAX_IDLE_MS;
+				await (idle ? step(1) : step(0, clock));
+				busy = false;
+			}, PLAYBACK_INTERVAL_MS);
+		},
+		pause,
+		stop,
+	};
+}
+
+export const replayStore = createReplayStore();
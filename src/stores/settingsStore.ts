@@ -1,12 +1,43 @@
-import { writable } from "svelte/store";
-import { getSetting, setSetting, getAllSettings } from "../db/settingsDb";
+import { writable, get } from "svelte/store";
+import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
+
+// Time-based types plus bars derived from ticks or M1 candles by the backend
+export type ChartType =
+	| "candlesticks"
+	| "bar"
+	| "line"
+	| "heikin_ashi"
+	| "renko"
+	| "range"
+	| "tick"
+	| "volume";
 
 export interface GeneralSettings {
 	autoSave: boolean;
 	showGrid: boolean;
-	defaultChartType: "candlesticks" | "bar" | "line";
+	defaultChartType: ChartType;
+	displayTimezone: string; // Zone chart times are shown in, stored data is always UTC
+	renkoBoxSize: number; // Price units, 0 sizes boxes from the ATR
+	renkoAtrPeriod: number;
+	rangeBarSize: number; // Price units
+	tickBarCount: number;
+	volumeBarSize: number;
 }
 
+// Zones offered for imports and display. Fixed offsets with "/US" or "/EU" follow that DST schedule.
+export const timezonePresets: { value: string; label: string }[] = [
+	{ value: "UTC", label: "UTC" },
+	{ value: "+02:00/US", label: "Broker GMT+2/+3 (US DST)" },
+	{ value: "+02:00/EU", label: "Broker GMT+2/+3 (EU DST)" },
+	{ value: "-05:00", label: "EST without DST (HistData)" },
+	{ value: "America/New_York", label: "New York" },
+	{ value: "Europe/London", label: "London" },
+	{ value: "Europe/Berlin", label: "Frankfurt" },
+	{ value: "Asia/Tokyo", label: "Tokyo" },
+	{ value: "Australia/Sydney", label: "Sydney" },
+];
+
 export interface TradeSettings {
 	bidDistance: number;
 	askDistance: number;
@@ -35,6 +66,12 @@ const defaultSettings: AppSettings = {
 		autoSave: true,
 		showGrid: true,
 		defaultChartType: "candlesticks",
+		displayTimezone: "UTC",
+		renkoBoxSize: 0,
+		renkoAtrPeriod: 14,
+		rangeBarSize: 0.001,
+		tickBarCount: 100,
+		volumeBarSize: 1000,
 	},
 	trade: {
 		bidDistance: 5,
@@ -53,11 +90,46 @@ const defaultSettings: AppSettings = {
 	},
 };
 
+type SettingsSection = keyof AppSettings;
+
+export type BundleSection =
+	| "settings"
+	| "shortcuts"
+	| "import_profiles"
+	| "indicator_templates"
+	| "drawing_styles"
+	| "fib_level_sets";
+
+export interface BundleImportResult {
+	success: boolean;
+	message: string;
+	imported: { section: BundleSection; count: number }[];
+	issues: string[]; // "section[index].key: problem" for everything that was skipped
+}
+
+interface SettingsChanged {
+	section: SettingsSection | null; // null when everything changed
+	settings: AppSettings;
+}
+
+// The backend owns the settings: it validates and stores every change, then announces it
+// on "settings://changed" so every window and the replay engine see the same values.
 function createSettingsStore() {
-	const { subscribe, set, update } = writable<AppSettings>(defaultSettings);
+	const store = writable<AppSettings>(defaultSettings);
+	const { subscribe, set } = store;
 
 	let isInitialized = false;
 
+	async function updateSection(section: SettingsSection, patch: Record<string, unknown>) {
+		try {
+			set(await invoke<AppSettings>("update_settings", { section, patch }));
+		} catch (error) {
+			console.error(`Failed to update ${section} settings:`, error);
+			// Redraw the inputs with the values that are still in force
+			set(get(store));
+		}
+	}
+
 	return {
 		subscribe,
 
@@ -65,26 +137,12 @@ function createSettingsStore() {
 			if (isInitialized) return;
 
 			try {
-				const savedSettings = await getAllSettings();
-
-				const loadedSettings: AppSettings = {
-					general: {
-						...defaultSettings.general,
-						...(savedSettings.general || {}),
-					},
-					trade: {
-						...defaultSettings.trade,
-						...(savedSettings.trade || {}),
-					},
-					appearance: {
-						...defaultSettings.appearance,
-						...(savedSettings.appearance || {}),
-					},
-				};
-
-				set(loadedSettings);
+				set(await invoke<AppSettings>("get_settings"));
+				await listen<SettingsChanged>("settings://changed", (event) => {
+					set(event.payload.settings);
+				});
 				isInitialized = true;
-				console.log("Settings loaded from database");
+				console.log("Settings loaded from backend");
 			} catch (error) {
 				console.error("Failed to load settings:", error);
 				set(defaultSettings);
@@ -95,49 +153,55 @@ function createSettingsStore() {
 			key: K,
 			value: GeneralSettings[K]
 		) {
-			update((state) => {
-				const newState = {
-					...state,
-					general: { ...state.general, [key]: value },
-				};
-				setSetting("general", newState.general);
-				return newState;
-			});
+			await updateSection("general", { [key]: value });
 		},
 
 		async updateTrade<K extends keyof TradeSettings>(
 			key: K,
 			value: TradeSettings[K]
 		) {
-			update((state) => {
-				const newState = {
-					...state,
-					trade: { ...state.trade, [key]: value },
-				};
-				setSetting("trade", newState.trade);
-				return newState;
-			});
+			await updateSection("trade", { [key]: value });
 		},
 
 		async updateAppearance<K extends keyof AppearanceSettings>(
 			key: K,
 			value: AppearanceSettings[K]
 		) {
-			update((state) => {
-				const newState = {
-					...state,
-					appearance: { ...state.appearance, [key]: value },
-				};
-				setSetting("appearance", newState.appearance);
-				return newState;
-			});
+			await updateSection("appearance", { [key]: value });
+		},
+
+		// Write settings, shortcuts, import profiles, templates and styles to one JSON file
+		async exportBundle(path: string, sections?: BundleSection[]): Promise<boolean> {
+			try {
+				await invoke("export_settings_bundle", { path, sections: sections ?? null });
+				return true;
+			} catch (error) {
+				console.error("Failed to export settings:", error);
+				return false;
+			}
+		},
+
+		// Settings changes arrive through "settings://changed", the rest is read on next use
+		async importBundle(
+			path: string,
+			mode: "merge" | "replace",
+			dryRun = false
+		): Promise<BundleImportResult> {
+			try {
+				return await invoke<BundleImportResult>("import_settings_bundle", {
+					request: { path, mode, dry_run: dryRun },
+				});
+			} catch (error) {
+				return { success: false, message: String(error), imported: [], issues: [] };
+			}
 		},
 
 		async reset() {
-			set(defaultSettings);
-			await setSetting("general", defaultSettings.general);
-			await setSetting("trade", defaultSettings.trade);
-			await setSetting("appearance", defaultSettings.appearance);
+			try {
+				set(await invoke<AppSettings>("reset_settings"));
+			} catch (error) {
+				console.error("Failed to reset settings:", error);
+			}
 		},
 	};
 }
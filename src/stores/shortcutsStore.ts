@@ -1,4 +1,5 @@
 import { writable } from "svelte/store";
+import { invoke } from "@tauri-apps/api/core";
 import shortcutsConfig from "../shortcuts.json";
 
 export interface Shortcut {
@@ -14,6 +15,13 @@ export interface ShortcutsState {
 	enabled: boolean;
 }
 
+// A user's keys for a shipped shortcut, saved as a "shortcut" preset by the backend
+interface ShortcutBinding {
+	kind: "shortcut";
+	id: string;
+	keys: string[];
+}
+
 function createShortcutsStore() {
 	const { subscribe, set, update } = writable<ShortcutsState>({
 		shortcuts: shortcutsConfig.shortcuts,
@@ -22,19 +30,36 @@ function createShortcutsStore() {
 
 	return {
 		subscribe,
-		loadShortcuts: () => {
+		// Shipped shortcuts with the saved bindings applied
+		loadShortcuts: async () => {
+			let bindings: ShortcutBinding[] = [];
+			try {
+				bindings = await invoke<ShortcutBinding[]>("list_presets", { kind: "shortcut" });
+			} catch (error) {
+				console.error("Failed to load shortcut bindings:", error);
+			}
+
 			set({
-				shortcuts: shortcutsConfig.shortcuts,
+				shortcuts: shortcutsConfig.shortcuts.map((shortcut) => {
+					const binding = bindings.find((b) => b.id === shortcut.id);
+					return binding ? { ...shortcut, keys: binding.keys } : shortcut;
+				}),
 				enabled: true,
 			});
 		},
-		updateShortcut: (id: string, keys: string[]) => {
+		updateShortcut: async (id: string, keys: string[]) => {
 			update((state) => ({
 				...state,
 				shortcuts: state.shortcuts.map((shortcut) =>
 					shortcut.id === id ? { ...shortcut, keys } : shortcut
 				),
 			}));
+
+			try {
+				await invoke("save_preset", { preset: { kind: "shortcut", id, keys } });
+			} catch (error) {
+				console.error(`Failed to save shortcut ${id}:`, error);
+			}
 		},
 		toggleEnabled: () => {
 			update((state) => ({
@@ -6,6 +6,32 @@ export interface TimeframeInfo {
 	display_name: string;
 	candle_count: number;
 	file_path: string;
+	format?: string;
+	coverage?: number;
+	source_timezone?: string;
+}
+
+export interface SessionHours {
+	start: string;
+	end: string;
+}
+
+export interface SymbolMetadata {
+	source: string;
+	exchange: string | null;
+	exchange_timezone: string | null;
+	gmt_offset: number | null;
+	currency: string | null;
+	instrument_type: string | null;
+	price_precision: number | null;
+	regular_session: SessionHours | null;
+}
+
+export interface TickSummary {
+	count: number;
+	first_ms: number;
+	last_ms: number;
+	days: number;
 }
 
 export interface SymbolData {
@@ -13,6 +39,30 @@ export interface SymbolData {
 	timeframes: TimeframeInfo[];
 	total_candles: number;
 	uploaded_at: string;
+	metadata?: SymbolMetadata;
+	ticks?: TickSummary; // Present when the symbol has stored ticks for replay
+}
+
+export type MergePolicy = "prefer_existing" | "prefer_new" | "average";
+
+export interface MergeReport {
+	policy: MergePolicy;
+	source: string;
+	timeframes: {
+		timeframe: string;
+		existing_bars: number;
+		new_bars: number;
+		overlapping_bars: number;
+		added_bars: number;
+		total_bars: number;
+	}[];
+}
+
+// One line summary of a merge import, e.g. "merged: 1200 added, 80 overlapping"
+export function describeMerge(report: MergeReport): string {
+	const added = report.timeframes.reduce((sum, tf) => sum + tf.added_bars, 0);
+	const overlapping = report.timeframes.reduce((sum, tf) => sum + tf.overlapping_bars, 0);
+	return `merged: ${added} added, ${overlapping} overlapping`;
 }
 
 function createSymbolsStore() {
@@ -0,0 +1,277 @@
+// src/stores/workspaceStore.ts
+import { writable, get } from "svelte/store";
+import { invoke } from "@tauri-apps/api/core";
+import {
+	chartStore,
+	type BarType,
+	type PatternConfig,
+	type SwingMethod,
+} from "./chartStore";
+import { replayStore } from "./replayStore";
+import { settingsStore } from "./settingsStore";
+import { symbolsStore } from "./symbolsStore";
+
+export interface WorkspaceIndicator {
+	name: string;
+	params?: unknown;
+}
+
+export interface WorkspaceDrawing {
+	shape: string;
+	points: { timestamp: number; price: number }[];
+	style?: unknown;
+}
+
+export interface WorkspaceChart {
+	symbol: string;
+	timeframe: string;
+	chart_type?: string | null;
+	indicators: WorkspaceIndicator[];
+	drawings: WorkspaceDrawing[];
+}
+
+export interface WorkspaceOrder {
+	id: string;
+	symbol: string;
+	side: "buy" | "sell";
+	kind: "market" | "limit" | "stop";
+	size: number;
+	price?: number | null;
+	stop_loss?: number | null;
+	take_profit?: number | null;
+	created_at: number;
+}
+
+export interface AccountState {
+	balance: number;
+	equity: number;
+	currency?: string | null;
+	realized_pnl: number;
+}
+
+export interface Workspace {
+	version?: number;
+	name: string;
+	saved_at?: string;
+	charts: WorkspaceChart[];
+	active_chart: number;
+	replay: {
+		symbol: string;
+		timeframe: string;
+		time_ms: number;
+		speed?: number | null;
+		bar_type?: BarType | null;
+	} | null;
+	orders: WorkspaceOrder[];
+	account: AccountState | null;
+	layout: unknown;
+}
+
+export interface WorkspaceInfo {
+	name: string;
+	saved_at: string;
+	charts: string[];
+	autosave: boolean;
+}
+
+export const AUTOSAVE_NAME = "Autosave";
+const AUTOSAVE_INTERVAL_MS = 60_000;
+
+interface WorkspaceState {
+	current: string | null; // Name of the open workspace
+	lastSaved: string | null;
+	orders: WorkspaceOrder[]; // Kept as loaded until the app has an order panel
+	account: AccountState | null;
+	layout: unknown;
+}
+
+// Indicators the chart currently shows, in the names IndicatorModal uses
+function currentIndicators(): WorkspaceIndicator[] {
+	const chart = get(chartStore);
+	const indicators: WorkspaceIndicator[] = [];
+
+	const profile = chart.profiles[0];
+	if (profile) {
+		const name = profile.tpo
+			? "Market Profile (TPO)"
+			: chart.profiles.length > 1
+				? "Session Volume Profile"
+				: "Volume Profile";
+		indicators.push({ name });
+	}
+	if (chart.structureConfig) {
+		indicators.push({ name: "Market Structure", params: chart.structureConfig });
+	}
+	if (chart.patternConfig) {
+		indicators.push({ name: "Candlestick Patterns", params: chart.patternConfig });
+	}
+
+	return indicators;
+}
+
+function currentDrawings(): WorkspaceDrawing[] {
+	const { fibonacci } = get(chartStore);
+	return fibonacci
+		? [{ shape: "Fibonacci", points: fibonacci.anchors, style: { level_set: fibonacci.level_set } }]
+		: [];
+}
+
+function createWorkspaceStore() {
+	const store = writable<WorkspaceState>({
+		current: null,
+		lastSaved: null,
+		orders: [],
+		account: null,
+		layout: {},
+	});
+	const { subscribe, update } = store;
+	let autosaveTimer: ReturnType<typeof setInterval> | null = null;
+
+	function capture(name: string): Workspace | null {
+		const chart = get(chartStore);
+		const replay = get(replayStore);
+		const state = get(store);
+		if (!chart.selectedSymbol || !chart.selectedTimeframe) return null;
+
+		return {
+			name,
+			charts: [
+				{
+					symbol: chart.selectedSymbol,
+					timeframe: chart.selectedTimeframe,
+					chart_type: get(settingsStore).general.defaultChartType,
+					indicators: currentIndicators(),
+					drawings: currentDrawings(),
+				},
+			],
+			active_chart: 0,
+			// Blind sessions are never saved, that would give their identity away
+			replay:
+				replay.sessionId !== null && !replay.blind && replay.symbol && replay.timeframe && replay.time
+					? { symbol: replay.symbol, timeframe: replay.timeframe, time_ms: replay.time }
+					: null,
+			orders: state.orders,
+			account: state.account,
+			layout: state.layout,
+		};
+	}
+
+	async function save(name: string): Promise<boolean> {
+		const workspace = capture(name);
+		if (!workspace) return false;
+
+		try {
+			await invoke("workspace_save", { workspace });
+			update((state) => ({
+				...state,
+				current: name === AUTOSAVE_NAME ? state.current : name,
+				lastSaved: new Date().toISOString(),
+			}));
+			return true;
+		} catch (error) {
+			console.error("Failed to save workspace:", error);
+			return false;
+		}
+	}
+
+	async function restoreIndicators(symbol: string, chart: WorkspaceChart) {
+		for (const indicator of chart.indicators) {
+			if (indicator.name === "Volume Profile") {
+				await chartStore.loadProfiles(symbol);
+			} else if (indicator.name === "Session Volume Profile") {
+				await chartStore.loadProfiles(symbol, { per_session: true });
+			} else if (indicator.name === "Market Profile (TPO)") {
+				await chartStore.loadProfiles(symbol, { per_session: true, tpo: true });
+			} else if (indicator.name === "Market Structure") {
+				await chartStore.loadStructure(symbol, chart.timeframe, indicator.params as SwingMethod);
+			} else if (indicator.name === "Candlestick Patterns") {
+				await chartStore.loadPatterns(symbol, chart.timeframe, indicator.params as PatternConfig);
+			}
+		}
+
+		for (const drawing of chart.drawings) {
+			if (drawing.shape === "Fibonacci") {
+				const style = (drawing.style ?? {}) as { level_set?: string };
+				await chartStore.loadFibonacci(symbol, chart.timeframe, drawing.points, {
+					levelSet: style.level_set,
+					snap: false,
+				});
+			}
+		}
+	}
+
+	async function open(name: string): Promise<boolean> {
+		try {
+			const workspace = await invoke<Workspace>("workspace_open", { name });
+			const chart = workspace.charts[workspace.active_chart] ?? workspace.charts[0];
+
+			if (chart) {
+				const symbols = await symbolsStore.loadSymbols();
+				const symbolData = symbols.find((s) => s.symbol === chart.symbol);
+				if (!symbolData) {
+					console.error(`Workspace symbol ${chart.symbol} is no longer available`);
+					return false;
+				}
+
+				chartStore.setSymbol(chart.symbol, symbolData.timeframes.map((tf) => tf.name));
+				chartStore.setTimeframe(chart.timeframe);
+				await restoreIndicators(chart.symbol, chart);
+			}
+
+			const { structureConfig, patternConfig } = get(chartStore);
+			if (workspace.replay) {
+				await replayStore.start(
+					workspace.replay.symbol,
+					workspace.replay.timeframe,
+					workspace.replay.time_ms,
+					workspace.replay.bar_type ?? null,
+					structureConfig,
+					patternConfig
+				);
+			} else {
+				await replayStore.stop();
+			}
+
+			update((state) => ({
+				...state,
+				current: name === AUTOSAVE_NAME ? state.current : workspace.name,
+				orders: workspace.orders,
+				account: workspace.account,
+				layout: workspace.layout ?? {},
+			}));
+			return true;
+		} catch (error) {
+			console.error("Failed to open workspace:", error);
+			return false;
+		}
+	}
+
+	return {
+		subscribe,
+		save,
+		open,
+		list: async (): Promise<WorkspaceInfo[]> => {
+			try {
+				return await invoke<WorkspaceInfo[]>("workspace_list");
+			} catch (error) {
+				console.error("Failed to list workspaces:", error);
+				return [];
+			}
+		},
+		// Save to the "Autosave" workspace every minute while GeneralSettings.autoSave is on
+		startAutosave: () => {
+			if (autosaveTimer) return;
+			autosaveTimer = setInterval(() => {
+				if (get(settingsStore).general.autoSave) save(AUTOSAVE_NAME);
+			}, AUTOSAVE_INTERVAL_MS);
+		},
+		stopAutosave: () => {
+			if (autosaveTimer) {
+				clearInterval(autosaveTimer);
+				autosaveTimer = null;
+			}
+		},
+	};
+}
+
+export const workspaceStore = createWorkspaceStore();